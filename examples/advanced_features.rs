@@ -6,7 +6,7 @@ use ink::env::{
     test,
 };
 
-use lending_smart_contract::{LendingContract, types::{RateAdjustmentReason, CompoundFrequency, PaymentStructure, GracePeriodReason, BenchmarkCategory, ReportType, ProposalType, VoteChoice}, errors::LendingError, AccountId};
+use lending_smart_contract::{LendingContract, types::{Asset, RateAdjustmentReason, CompoundFrequency, PaymentStructure, GracePeriodReason, BenchmarkCategory, ReportType, ProposalType, VoteChoice, MILLIS_PER_DAY, MILLIS_PER_WEEK, MILLIS_PER_MONTH, MILLIS_PER_QUARTER, MILLIS_PER_YEAR}, errors::LendingError, AccountId};
 
 /// Example demonstrating advanced features of the lending smart contract
 fn main() {
@@ -33,7 +33,8 @@ fn main() {
     // Loan 1: Small loan with low interest
     println!("   Creating Loan 1 (Small, Low Interest)...");
     test::set_caller::<DefaultEnvironment>(accounts.alice);
-    match contract.create_loan(500, 300, 500, 750) { // 500 amount, 3% interest, 500 blocks, 750 collateral
+    test::set_value_transferred::<DefaultEnvironment>(750); // Collateral is escrowed with the request
+    match contract.create_loan(500, 300, 500, 750) { // 500 amount, 3% interest, 500 ms, 750 collateral
         Ok(loan_id) => println!("   ✅ Loan 1 created with ID: {}", loan_id),
         Err(e) => {
             println!("   ❌ Failed to create loan 1: {:?}", e);
@@ -44,7 +45,8 @@ fn main() {
     // Loan 2: Medium loan with medium interest
     println!("   Creating Loan 2 (Medium, Medium Interest)...");
     test::set_caller::<DefaultEnvironment>(accounts.bob);
-    match contract.create_loan(2000, 800, 1500, 3000) { // 2000 amount, 8% interest, 1500 blocks, 3000 collateral
+    test::set_value_transferred::<DefaultEnvironment>(3000); // Collateral is escrowed with the request
+    match contract.create_loan(2000, 800, 1500, 3000) { // 2000 amount, 8% interest, 1500 ms, 3000 collateral
         Ok(loan_id) => println!("   ✅ Loan 2 created with ID: {}", loan_id),
         Err(e) => {
             println!("   ❌ Failed to create loan 2: {:?}", e);
@@ -55,7 +57,8 @@ fn main() {
     // Loan 3: Large loan with high interest
     println!("   Creating Loan 3 (Large, High Interest)...");
     test::set_caller::<DefaultEnvironment>(accounts.charlie);
-    match contract.create_loan(5000, 1200, 2000, 7500) { // 5000 amount, 12% interest, 2000 blocks, 7500 collateral
+    test::set_value_transferred::<DefaultEnvironment>(7500); // Collateral is escrowed with the request
+    match contract.create_loan(5000, 1200, 2000, 7500) { // 5000 amount, 12% interest, 2000 ms, 7500 collateral
         Ok(loan_id) => println!("   ✅ Loan 3 created with ID: {}", loan_id),
         Err(e) => {
             println!("   ❌ Failed to create loan 3: {:?}", e);
@@ -162,7 +165,7 @@ fn main() {
     // Repay Loan 1 (small loan)
     println!("   Repaying Loan 1...");
     test::set_caller::<DefaultEnvironment>(accounts.alice);
-    let original_repayment = 500 + ((500 * 300) / 10000); // Principal + Interest
+    let original_repayment = contract.get_current_debt(1).unwrap(); // Principal + interest accrued so far
     test::set_value_transferred::<DefaultEnvironment>(original_repayment);
     match contract.repay_loan(1) {
         Ok(()) => println!("   ✅ Loan 1 repaid successfully"),
@@ -182,7 +185,7 @@ fn main() {
     // Test loan extension (we'll implement this next)
     println!("\n--- Testing Loan Extension ---");
     let loan_info = contract.get_loan(1).unwrap();
-    println!("Current loan duration: {} blocks", loan_info.duration);
+    println!("Current loan duration: {} ms", loan_info.duration);
     println!("Loan extension feature coming in next phase...");
 
     // Test loan extension functionality
@@ -313,7 +316,7 @@ fn main() {
     for (i, adjustment) in loan_info.interest_rate_adjustments.iter().enumerate() {
         println!("   Adjustment {}: {} → {} basis points", i + 1, adjustment.old_rate, adjustment.new_rate);
         println!("     Reason: {:?}", adjustment.reason);
-        println!("     Timestamp: block {}", adjustment.timestamp);
+        println!("     Timestamp: {} ms", adjustment.timestamp);
         if let Some(risk_change) = adjustment.risk_score_change {
             println!("     Risk score change: {}", risk_change);
         }
@@ -335,8 +338,8 @@ fn main() {
     println!("   Base interest rate: {} basis points ({}%)", final_loan.base_interest_rate, final_loan.base_interest_rate as f64 / 100.0);
     println!("   Risk multiplier: {} ({}x)", final_loan.risk_multiplier, final_loan.risk_multiplier as f64 / 1000.0);
     println!("   Effective interest rate: {} basis points ({}%)", final_loan.interest_rate, final_loan.interest_rate as f64 / 100.0);
-    println!("   Last rate update: block {}", final_loan.last_interest_update);
-    println!("   Update frequency: {} blocks ({} days)", final_loan.interest_update_frequency, final_loan.interest_update_frequency / 14400);
+    println!("   Last rate update: {} ms", final_loan.last_interest_update);
+    println!("   Update frequency: {} ms ({} days)", final_loan.interest_update_frequency, final_loan.interest_update_frequency / MILLIS_PER_DAY);
     
     // Test 7: Demonstrate rate calculation formula
     println!("\n7. Rate Calculation Formula Demonstration...");
@@ -378,10 +381,11 @@ fn main() {
             let updated_loan = contract.get_loan(1).unwrap();
             println!("   New interest type: {:?}", updated_loan.interest_type);
             println!("   Compound frequency: {:?}", updated_loan.compound_frequency);
-            println!("   Compound period blocks: {}", updated_loan.compound_period_blocks);
+            println!("   Compound period ms: {}", updated_loan.compound_period_ms);
             println!("   New remaining balance: {}", updated_loan.remaining_balance);
             println!("   Total compounded interest: {}", updated_loan.total_compounded_interest);
         }
+        Err(LendingError::LoanNotActive) => println!("   ✅ Loan 1 is already repaid, so its terms can no longer change"),
         Err(e) => println!("   ❌ Failed to convert to compound interest: {:?}", e),
     }
     
@@ -390,7 +394,8 @@ fn main() {
     
     // Create a new loan for testing different frequencies
     test::set_caller::<DefaultEnvironment>(accounts.frank);
-    let loan4_id = contract.create_loan(1000, 1000, 2000, 1500).unwrap(); // 10% interest, 2000 blocks
+    test::set_value_transferred::<DefaultEnvironment>(1500); // Collateral is escrowed with the request
+    let loan4_id = contract.create_loan(1000, 1000, 2000, 1500).unwrap(); // 10% interest, 2000 ms
     
     // Fund the loan
     test::set_caller::<DefaultEnvironment>(accounts.alice);
@@ -404,7 +409,7 @@ fn main() {
             println!("   ✅ Successfully converted to monthly compound interest!");
             let loan4 = contract.get_loan(loan4_id).unwrap();
             println!("   Compound frequency: {:?}", loan4.compound_frequency);
-            println!("   Compound period: {} blocks ({} days)", loan4.compound_period_blocks, loan4.compound_period_blocks / 14400);
+            println!("   Compound period: {} ms ({} days)", loan4.compound_period_ms, loan4.compound_period_ms / MILLIS_PER_DAY);
             println!("   Initial balance: {}", loan4.amount);
             println!("   Current remaining balance: {}", loan4.remaining_balance);
         }
@@ -431,11 +436,11 @@ fn main() {
     println!("\n4. Compound Interest Information...");
     
     match contract.get_compound_interest_info(1) {
-        Ok((interest_type, frequency, period_blocks, accrued, total_compounded)) => {
+        Ok((interest_type, frequency, period_ms, accrued, total_compounded)) => {
             println!("   ✅ Compound interest info retrieved successfully!");
             println!("   Interest type: {:?}", interest_type);
             println!("   Compound frequency: {:?}", frequency);
-            println!("   Period blocks: {} ({} days)", period_blocks, period_blocks / 14400);
+            println!("   Period ms: {} ({} days)", period_ms, period_ms / MILLIS_PER_DAY);
             println!("   Accrued interest: {}", accrued);
             println!("   Total compounded interest: {}", total_compounded);
         }
@@ -470,11 +475,11 @@ fn main() {
     println!("\n6. Compound Frequency Comparison...");
     
     let frequencies = [
-        (CompoundFrequency::Daily, "Daily", 14400),
-        (CompoundFrequency::Weekly, "Weekly", 100800),
-        (CompoundFrequency::Monthly, "Monthly", 432000),
-        (CompoundFrequency::Quarterly, "Quarterly", 1296000),
-        (CompoundFrequency::Annually, "Annually", 5184000),
+        (CompoundFrequency::Daily, "Daily", MILLIS_PER_DAY),
+        (CompoundFrequency::Weekly, "Weekly", MILLIS_PER_WEEK),
+        (CompoundFrequency::Monthly, "Monthly", MILLIS_PER_MONTH),
+        (CompoundFrequency::Quarterly, "Quarterly", MILLIS_PER_QUARTER),
+        (CompoundFrequency::Annually, "Annually", MILLIS_PER_YEAR),
     ];
     
     println!("   Compound Frequency Comparison (Principal: 1000, Rate: 10%, 1 year):");
@@ -482,8 +487,8 @@ fn main() {
     println!("   │ Frequency   │ Periods/Yr │ Rate/Period │ Final Amount    │");
     println!("   ├─────────────┼─────────────┼─────────────┼─────────────────┤");
     
-    for (_freq, name, blocks) in frequencies.iter() {
-        let periods_per_year = 5184000 / blocks; // 5184000 blocks per year
+    for (_freq, name, ms) in frequencies.iter() {
+        let periods_per_year = MILLIS_PER_YEAR / ms; // Periods in a 365-day year
         let rate_per_period = 0.10 / periods_per_year as f64; // 10% annual rate
         let compound_factor = (1.0 + rate_per_period).powf(periods_per_year as f64);
         let final_amount = 1000.0 * compound_factor;
@@ -527,14 +532,14 @@ fn main() {
     println!("   Current interest rate: {} basis points ({}%)", loan_info.interest_rate, loan_info.interest_rate as f64 / 100.0);
     
     // Set loan to interest-only for 3 periods (daily payments)
-    match contract.set_interest_only_periods(1, 3, 14400) { // 3 periods, daily (14400 blocks)
+    match contract.set_interest_only_periods(1, 3, MILLIS_PER_DAY) { // 3 periods, daily
         Ok(_) => {
             println!("   ✅ Successfully set to interest-only payment structure!");
             let updated_loan = contract.get_loan(1).unwrap();
             println!("   New payment structure: {:?}", updated_loan.payment_structure);
             println!("   Interest-only periods: {}/{}", updated_loan.interest_only_periods_used, updated_loan.interest_only_periods);
-            println!("   Payment period blocks: {} ({} days)", updated_loan.payment_period_blocks, updated_loan.payment_period_blocks / 14400);
-            println!("   Next payment due: block {}", updated_loan.next_payment_due);
+            println!("   Payment period ms: {} ({} days)", updated_loan.payment_period_ms, updated_loan.payment_period_ms / MILLIS_PER_DAY);
+            println!("   Next payment due: {} ms", updated_loan.next_payment_due);
             println!("   Minimum payment amount: {}", updated_loan.minimum_payment_amount);
         }
         Err(e) => println!("   ❌ Failed to set interest-only structure: {:?}", e),
@@ -545,7 +550,8 @@ fn main() {
     
     // Create a new loan for testing different payment structures
     test::set_caller::<DefaultEnvironment>(accounts.frank);
-    let loan5_id = contract.create_loan(1500, 1200, 3000, 2250).unwrap(); // 12% interest, 3000 blocks
+    test::set_value_transferred::<DefaultEnvironment>(2250); // Collateral is escrowed with the request
+    let loan5_id = contract.create_loan(1500, 1200, 3000, 2250).unwrap(); // 12% interest, 3000 ms
     
     // Fund the loan
     test::set_caller::<DefaultEnvironment>(accounts.bob);
@@ -554,14 +560,14 @@ fn main() {
     
     // Set to weekly interest-only payments for 2 periods
     test::set_caller::<DefaultEnvironment>(accounts.bob);
-    match contract.set_interest_only_periods(loan5_id, 2, 100800) { // 2 periods, weekly (100800 blocks)
+    match contract.set_interest_only_periods(loan5_id, 2, MILLIS_PER_WEEK) { // 2 periods, weekly
         Ok(_) => {
             println!("   ✅ Successfully set to weekly interest-only structure!");
             let loan5 = contract.get_loan(loan5_id).unwrap();
             println!("   Payment structure: {:?}", loan5.payment_structure);
             println!("   Interest-only periods: {}/{}", loan5.interest_only_periods_used, loan5.interest_only_periods);
-            println!("   Payment period: {} blocks ({} days)", loan5.payment_period_blocks, loan5.payment_period_blocks / 14400);
-            println!("   Next payment due: block {}", loan5.next_payment_due);
+            println!("   Payment period: {} ms ({} days)", loan5.payment_period_ms, loan5.payment_period_ms / MILLIS_PER_DAY);
+            println!("   Next payment due: {} ms", loan5.next_payment_due);
         }
         Err(e) => println!("   ❌ Failed to set weekly interest-only: {:?}", e),
     }
@@ -576,7 +582,7 @@ fn main() {
             println!("   Structure: {:?}", structure);
             println!("   Interest-only periods: {}/{}", used_periods, total_periods);
             println!("   Current period: {}", current_period);
-            println!("   Next payment due: block {}", next_due);
+            println!("   Next payment due: {} ms", next_due);
             println!("   Minimum payment: {}", min_payment);
         }
         Err(e) => println!("   ❌ Failed to get payment structure info: {:?}", e),
@@ -588,8 +594,8 @@ fn main() {
     let loan1 = contract.get_loan(1).unwrap();
     let principal = loan1.amount as f64;
     let rate = loan1.interest_rate as f64 / 10000.0; // Convert basis points to decimal
-    let period_blocks = loan1.payment_period_blocks as f64;
-    let time_factor = period_blocks / 5184000.0; // Convert to years
+    let period_ms = loan1.payment_period_ms as f64;
+    let time_factor = period_ms / MILLIS_PER_YEAR as f64; // Convert to years
     
     // Calculate interest payment for one period
     let interest_payment = (principal * rate * time_factor) as u128;
@@ -597,7 +603,7 @@ fn main() {
     println!("   Payment Schedule for Loan 1:");
     println!("   Principal: {}", principal);
     println!("   Interest rate: {}% per year", rate * 100.0);
-    println!("   Payment period: {} blocks ({} days)", period_blocks, period_blocks / 14400.0);
+    println!("   Payment period: {} ms ({} days)", period_ms, period_ms / MILLIS_PER_DAY as f64);
     println!("   Interest per period: {}", interest_payment);
     println!("   Total interest-only periods: {}", loan1.interest_only_periods);
     
@@ -614,7 +620,7 @@ fn main() {
     }
     
     // After interest-only periods
-    let remaining_periods = (loan1.duration / loan1.payment_period_blocks) - loan1.interest_only_periods as u64;
+    let remaining_periods = (loan1.duration / loan1.payment_period_ms) - loan1.interest_only_periods as u64;
     if remaining_periods > 0 {
         let principal_per_period = principal / remaining_periods as f64;
         let total_payment = interest_payment + principal_per_period as u128;
@@ -686,16 +692,16 @@ fn main() {
     
     // Check current loan state
     let loan_info = contract.get_loan(1).unwrap();
-    println!("   Current grace period: {} blocks ({} minutes)", loan_info.grace_period_blocks, loan_info.grace_period_blocks / 600);
+    println!("   Current grace period: {} ms ({} minutes)", loan_info.grace_period_ms, loan_info.grace_period_ms / 60_000);
     println!("   Grace period extensions used: {}/{}", loan_info.grace_period_extensions, loan_info.max_grace_period_extensions);
     println!("   Current grace period reason: {:?}", loan_info.grace_period_reason);
     
     // Set custom grace period for the loan
-    match contract.set_custom_grace_period(1, 200, 3) { // 200 blocks grace period, max 3 extensions
+    match contract.set_custom_grace_period(1, 1_200_000, 3) { // 20 minute grace period, max 3 extensions
         Ok(_) => {
             println!("   ✅ Successfully set custom grace period!");
             let updated_loan = contract.get_loan(1).unwrap();
-            println!("   New grace period: {} blocks ({} minutes)", updated_loan.grace_period_blocks, updated_loan.grace_period_blocks / 600);
+            println!("   New grace period: {} ms ({} minutes)", updated_loan.grace_period_ms, updated_loan.grace_period_ms / 60_000);
             println!("   Max extensions allowed: {}", updated_loan.max_grace_period_extensions);
         }
         Err(e) => println!("   ❌ Failed to set custom grace period: {:?}", e),
@@ -705,11 +711,11 @@ fn main() {
     println!("\n2. Granting Grace Period Extension...");
     
     // Grant grace period extension for good payment history
-    match contract.grant_grace_period(1, 100, GracePeriodReason::GoodPaymentHistory) {
+    match contract.grant_grace_period(1, 600_000, GracePeriodReason::GoodPaymentHistory) {
         Ok(_) => {
             println!("   ✅ Successfully granted grace period extension!");
             let updated_loan = contract.get_loan(1).unwrap();
-            println!("   New total grace period: {} blocks ({} minutes)", updated_loan.grace_period_blocks, updated_loan.grace_period_blocks / 600);
+            println!("   New total grace period: {} ms ({} minutes)", updated_loan.grace_period_ms, updated_loan.grace_period_ms / 60_000);
             println!("   Extensions used: {}/{}", updated_loan.grace_period_extensions, updated_loan.max_grace_period_extensions);
             println!("   Current reason: {:?}", updated_loan.grace_period_reason);
         }
@@ -721,7 +727,8 @@ fn main() {
     
     // Create a new loan for testing different grace period scenarios
     test::set_caller::<DefaultEnvironment>(accounts.frank);
-    let loan6_id = contract.create_loan(800, 600, 1800, 1200).unwrap(); // 6% interest, 1800 blocks
+    test::set_value_transferred::<DefaultEnvironment>(1200); // Collateral is escrowed with the request
+    let loan6_id = contract.create_loan(800, 600, 1800, 1200).unwrap(); // 6% interest, 1800 ms
     
     // Fund the loan
     test::set_caller::<DefaultEnvironment>(accounts.charlie);
@@ -730,11 +737,11 @@ fn main() {
     
     // Grant grace period for first-time borrower
     test::set_caller::<DefaultEnvironment>(accounts.charlie);
-    match contract.grant_grace_period(loan6_id, 150, GracePeriodReason::FirstTimeBorrower) {
+    match contract.grant_grace_period(loan6_id, 900_000, GracePeriodReason::FirstTimeBorrower) {
         Ok(_) => {
             println!("   ✅ Successfully granted first-time borrower grace period!");
             let loan6 = contract.get_loan(loan6_id).unwrap();
-            println!("   Grace period: {} blocks ({} minutes)", loan6.grace_period_blocks, loan6.grace_period_blocks / 600);
+            println!("   Grace period: {} ms ({} minutes)", loan6.grace_period_ms, loan6.grace_period_ms / 60_000);
             println!("   Reason: {:?}", loan6.grace_period_reason);
         }
         Err(e) => println!("   ❌ Failed to grant first-time borrower grace: {:?}", e),
@@ -744,17 +751,17 @@ fn main() {
     println!("\n4. Grace Period Information...");
     
     match contract.get_grace_period_info(1) {
-        Ok((grace_blocks, grace_used, extensions, max_extensions, reason, history)) => {
+        Ok((grace_ms, grace_used, extensions, max_extensions, reason, history)) => {
             println!("   ✅ Grace period info retrieved successfully!");
-            println!("   Total grace period: {} blocks ({} minutes)", grace_blocks, grace_blocks / 600);
-            println!("   Grace period used: {} blocks", grace_used);
+            println!("   Total grace period: {} ms ({} minutes)", grace_ms, grace_ms / 60_000);
+            println!("   Grace period used: {} ms", grace_used);
             println!("   Extensions used: {}/{}", extensions, max_extensions);
             println!("   Current reason: {:?}", reason);
             println!("   History records: {}", history.len());
             
             // Show grace period history
             for (i, record) in history.iter().enumerate() {
-                println!("     Record {}: {:?} - {} blocks - Extension #{}", 
+                println!("     Record {}: {:?} - {} ms - Extension #{}", 
                     i + 1, record.reason, record.duration, record.extension_number);
             }
         }
@@ -777,7 +784,7 @@ fn main() {
     match contract.calculate_remaining_grace_period(1) {
         Ok(remaining) => {
             println!("   ✅ Remaining grace period calculated!");
-            println!("   Remaining grace period: {} blocks ({} minutes)", remaining, remaining / 600);
+            println!("   Remaining grace period: {} ms ({} minutes)", remaining, remaining / 60_000);
         }
         Err(e) => println!("   ❌ Failed to calculate remaining grace period: {:?}", e),
     }
@@ -786,11 +793,11 @@ fn main() {
     println!("\n6. Grace Period Flexibility Demonstration...");
     
     let grace_reasons = [
-        (GracePeriodReason::FirstTimeBorrower, "First Time Borrower", 200),
-        (GracePeriodReason::GoodPaymentHistory, "Good Payment History", 150),
-        (GracePeriodReason::MarketConditions, "Market Conditions", 100),
-        (GracePeriodReason::LenderDiscretion, "Lender Discretion", 300),
-        (GracePeriodReason::EmergencyCircumstances, "Emergency Circumstances", 500),
+        (GracePeriodReason::FirstTimeBorrower, "First Time Borrower", 1_200_000),
+        (GracePeriodReason::GoodPaymentHistory, "Good Payment History", 900_000),
+        (GracePeriodReason::MarketConditions, "Market Conditions", 600_000),
+        (GracePeriodReason::LenderDiscretion, "Lender Discretion", 1_800_000),
+        (GracePeriodReason::EmergencyCircumstances, "Emergency Circumstances", 3_000_000),
     ];
     
     println!("   Grace Period Reasons and Default Durations:");
@@ -800,7 +807,7 @@ fn main() {
     
     for (reason, description, duration) in grace_reasons.iter() {
         println!("   │ {:<23} │ {:<19} │ {:<11} │", 
-            format!("{:?}", reason), description, format!("{} blocks", duration));
+            format!("{:?}", reason), description, format!("{} ms", duration));
     }
     println!("   └─────────────────────────┴─────────────────────┴─────────────┘");
     
@@ -813,8 +820,8 @@ fn main() {
                     GracePeriodReason::None => "None",
                     _ => "Active",
                 };
-                println!("   Loan {}: Grace period: {} ({} blocks), Extensions: {}/{}", 
-                    loan_id, grace_str, loan.grace_period_blocks, loan.grace_period_extensions, loan.max_grace_period_extensions);
+                println!("   Loan {}: Grace period: {} ({} ms), Extensions: {}/{}", 
+                    loan_id, grace_str, loan.grace_period_ms, loan.grace_period_extensions, loan.max_grace_period_extensions);
             }
         }
     
@@ -822,7 +829,7 @@ fn main() {
     println!("\n8. Grace Period Benefits and Features...");
     
     println!("   This system provides:");
-    println!("   ✅ Configurable grace periods (100 blocks to 1 week)");
+    println!("   ✅ Configurable grace periods (10 minutes to 1 week)");
     println!("   ✅ Multiple grace period reasons with different durations");
     println!("   ✅ Grace period extensions (up to configurable maximum)");
     println!("   ✅ Complete grace period history tracking");
@@ -921,7 +928,7 @@ fn main() {
             println!("   Liquidity provided: {}", liquidity_provided);
             println!("   Pool share: {} basis points ({}%)", pool_share, pool_share as f64 / 100.0);
             println!("   Rewards earned: {}", rewards_earned);
-            println!("   Last reward claim: block {}", last_claim);
+            println!("   Last reward claim: {} ms", last_claim);
         }
         Err(e) => println!("   ❌ Failed to get Alice's provider info: {:?}", e),
     }
@@ -933,7 +940,7 @@ fn main() {
             println!("   Liquidity provided: {}", liquidity_provided);
             println!("   Pool share: {} basis points ({}%)", pool_share, pool_share as f64 / 100.0);
             println!("   Rewards earned: {}", rewards_earned);
-            println!("   Last reward claim: block {}", last_claim);
+            println!("   Last reward claim: {} ms", last_claim);
         }
         Err(e) => println!("   ❌ Failed to get Bob's provider info: {:?}", e),
     }
@@ -1063,7 +1070,7 @@ fn main() {
     for pool_id in 1..=3 {
         match contract.get_pool_rebalancing_info(pool_id) {
             Ok((performance_score, last_rebalance, _frequency, target_ratio, current_ratio, _auto_enabled)) => {
-                println!("   Pool {}: Performance: {}%, Last rebalance: block {}, Frequency: {} blocks", 
+                println!("   Pool {}: Performance: {}%, Last rebalance: {} ms, Frequency: {} ms", 
                     pool_id, performance_score as f64 / 100.0, last_rebalance, _frequency);
                 println!("     Target ratio: {}%, Current ratio: {}%, Auto-rebalancing: {}", 
                     target_ratio as f64 / 100.0, current_ratio as f64 / 100.0, _auto_enabled);
@@ -1089,10 +1096,10 @@ fn main() {
     test::set_caller::<DefaultEnvironment>(accounts.alice); // Pool 1 creator
     
     // Set more aggressive rebalancing for high-yield pool
-    match contract.set_rebalancing_parameters(1, 7200, 7500, 300) { // 12h frequency, 75% target, 3% threshold
+    match contract.set_rebalancing_parameters(1, MILLIS_PER_DAY / 2, 7500, 300) { // 12h frequency, 75% target, 3% threshold
         Ok(_) => {
             println!("   ✅ Successfully set custom rebalancing parameters for Pool 1!");
-            println!("   New frequency: 7200 blocks (12 hours)");
+            println!("   New frequency: 43,200,000 ms (12 hours)");
             println!("   New target ratio: 75%");
             println!("   New threshold: 3%");
         }
@@ -1129,7 +1136,7 @@ fn main() {
                                     Ok((performance_score, _last_rebalance, _frequency, _target_ratio, _current_ratio, _auto_enabled)) => {
             println!("   Updated Pool 1:");
             println!("   Performance score: {}%", performance_score as f64 / 100.0);
-            println!("   Last rebalance: block {}", _last_rebalance);
+            println!("   Last rebalance: {} ms", _last_rebalance);
             println!("   Current ratio: {}% (target: {}%)", 
                 _current_ratio as f64 / 100.0, _target_ratio as f64 / 100.0);
                             }
@@ -1178,9 +1185,9 @@ fn main() {
     println!("\n7. Rebalancing Parameter Comparison...");
     
     let rebalancing_configs = [
-        ("High-Yield", 7200, 7500, 300, "Aggressive"),
-        ("Conservative", 14400, 8000, 500, "Moderate"),
-        ("High-Risk", 3600, 6000, 200, "Very Aggressive"),
+        ("High-Yield", MILLIS_PER_DAY / 2, 7500, 300, "Aggressive"),
+        ("Conservative", MILLIS_PER_DAY, 8000, 500, "Moderate"),
+        ("High-Risk", MILLIS_PER_DAY / 4, 6000, 200, "Very Aggressive"),
     ];
     
     println!("   Pool Rebalancing Strategies:");
@@ -1189,7 +1196,7 @@ fn main() {
     println!("   ├─────────────┼─────────────┼─────────────┼─────────────┼─────────────────┤");
     
     for (name, freq, target, threshold, strategy) in rebalancing_configs.iter() {
-        let freq_hours = freq / 600; // Convert blocks to hours
+        let freq_hours = freq / 3_600_000; // Convert ms to hours
         println!("   │ {:<11} │ {:<11} │ {:<11} │ {:<11} │ {:<15} │", 
             name, format!("{}h", freq_hours), format!("{}%", *target as f64 / 100.0), 
             format!("{}%", *threshold as f64 / 100.0), strategy);
@@ -1302,7 +1309,7 @@ fn main() {
         Ok(_) => {
             println!("   ✅ Successfully staked 25,000 tokens as Alice!");
             println!("   Tier: Gold (1.5x multiplier)");
-            println!("   Lock period: 1 day (14,400 blocks)");
+            println!("   Lock period: 1 day (86,400,000 ms)");
             println!("   Early unstake penalty: 5%");
         }
         Err(e) => println!("   ❌ Failed to stake tokens: {:?}", e),
@@ -1314,7 +1321,7 @@ fn main() {
         Ok(_) => {
             println!("   ✅ Successfully staked 8,000 tokens as Bob!");
             println!("   Tier: Silver (1.2x multiplier)");
-            println!("   Lock period: 1 day (14,400 blocks)");
+            println!("   Lock period: 1 day (86,400,000 ms)");
             println!("   Early unstake penalty: 5%");
         }
         Err(e) => println!("   ❌ Failed to stake tokens: {:?}", e),
@@ -1326,7 +1333,7 @@ fn main() {
         Ok(_) => {
             println!("   ✅ Successfully staked 1,500 tokens as Charlie!");
             println!("   Tier: Bronze (1x multiplier)");
-            println!("   Lock period: 1 day (14,400 blocks)");
+            println!("   Lock period: 1 day (86,400,000 ms)");
             println!("   Early unstake penalty: 5%");
         }
         Err(e) => println!("   ❌ Failed to stake tokens: {:?}", e),
@@ -1483,7 +1490,7 @@ fn main() {
             println!("   Max single pool concentration: 80%");
             println!("   Max provider concentration: 50%");
             println!("   Min pool diversity: 2 pools");
-            println!("   Check frequency: Daily (14,400 blocks)");
+            println!("   Check frequency: Daily (86,400,000 ms)");
         }
         Err(e) => println!("   ❌ Failed to check concentration limits: {:?}", e),
     }
@@ -1647,12 +1654,12 @@ fn main() {
     
     // Create insurance policy for Loan 1
     test::set_caller::<DefaultEnvironment>(accounts.alice); // Loan 1 borrower
-    match contract.create_insurance_policy(1, 2000, 500, 5184000, 200) {
+    match contract.create_insurance_policy(1, 2000, 500, MILLIS_PER_YEAR, 200) {
         Ok(policy_id) => {
             println!("   ✅ Successfully created insurance policy with ID: {}", policy_id);
             println!("   Insured Amount: 2,000");
             println!("   Premium Rate: 5% (500 basis points)");
-            println!("   Coverage Period: 1 year (5,184,000 blocks)");
+            println!("   Coverage Period: 1 year (365 days)");
             println!("   Deductible: 200");
             println!("   Policy Status: Active");
         }
@@ -1860,7 +1867,7 @@ fn main() {
     // Test very long duration
     println!("   Testing very long duration...");
     test::set_caller::<DefaultEnvironment>(accounts.alice);
-    match contract.create_loan(100, 500, 2000000, 150) { // Very long duration
+    match contract.create_loan(100, 500, 2 * MILLIS_PER_YEAR, 150) { // Very long duration
         Ok(_) => println!("   ❌ Should have failed with very long duration"),
         Err(e) => {
             if e == LendingError::InvalidDuration {
@@ -1909,6 +1916,7 @@ fn main() {
     let duration = 1000;
     let collateral = 1500;
     
+    test::set_value_transferred::<DefaultEnvironment>(collateral); // Collateral is escrowed with the request
     let loan_id = contract.create_loan(loan_amount, interest_rate, duration, collateral)
         .expect("Failed to create loan for analytics demo");
     
@@ -2146,6 +2154,13 @@ fn main() {
     let rarity_score = 8500; // 85% rarity
     let market_demand = 9200; // 92% demand
     
+    // NFTs are valued at their collection's oracle price
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    let collection = Asset::Psp34(contract_address);
+    contract.configure_price_feed(collection, 10 * 60 * 1000, 1000, 60 * 60 * 1000).expect("Failed to configure NFT price feed");
+    contract.add_price_reporter(collection, accounts.alice).expect("Failed to add NFT price reporter");
+    contract.submit_price(collection, valuation).expect("Failed to report NFT price");
+    
     let nft_id = contract.add_nft_collateral(
         contract_address,
         token_id,
@@ -2154,7 +2169,6 @@ fn main() {
         symbol.clone(),
         decimals,
         total_supply,
-        valuation,
         rarity_score,
         market_demand,
     ).expect("Failed to add NFT collateral");
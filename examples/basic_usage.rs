@@ -34,12 +34,13 @@ fn main() {
     let duration = 1000;
     let collateral = 1500;
 
+    test::set_value_transferred::<DefaultEnvironment>(collateral); // Collateral is escrowed with the request
     match contract.create_loan(loan_amount, interest_rate, duration, collateral) {
         Ok(loan_id) => {
             println!("   ✅ Loan created with ID: {}", loan_id);
             println!("   - Amount: {}", loan_amount);
             println!("   - Interest Rate: {} basis points ({}%)", interest_rate, interest_rate / 100);
-            println!("   - Duration: {} blocks", duration);
+            println!("   - Duration: {} ms", duration);
             println!("   - Collateral: {}", collateral);
         }
         Err(e) => {
@@ -1,46 +1,423 @@
 use ink_prelude::string::String;
 
+/// Errors returned by the lending contract, grouped by subsystem.
+///
+/// Each variant has a stable numeric code from `LendingError::code`. The thousands
+/// digit names the subsystem: 1 general, 2 lending, 3 pools, 4 governance, 5 DeFi,
+/// 6 admin, 7 assets and 8 oracle. Codes are never reused, so integrations should match on them
+/// rather than on the encoded variant index.
 #[derive(Debug, PartialEq, Eq, parity_scale_codec::Encode, parity_scale_codec::Decode, scale_info::TypeInfo)]
 pub enum LendingError {
+    // General
     InsufficientBalance,
-    InsufficientCollateral,
+    TransferFailed,
+    InvalidAmount,
+    Unauthorized,
+    UserNotFound,
+    UserBlacklisted,
+    InvalidStatus,
+    ArithmeticOverflow,
+    DivisionByZero,
+
+    // Lending
     LoanNotFound,
     LoanNotActive,
     LoanAlreadyRepaid,
+    LoanExpired,
     InvalidInterestRate,
     InvalidDuration,
-    UserBlacklisted,
-    Unauthorized,
-    TransferFailed,
-    InvalidAmount,
-    LoanExpired,
+    InsufficientCollateral,
     CollateralSeized,
-    UserNotFound,
+    InvalidStatusTransition,
+    ContributionExceedsRequest,
+    FundingDeadlineNotReached,
+    LoanAlreadyDue,
+    LoanNotOverdue,
+    LoanHealthy,
+    DefaultDelayNotElapsed,
+    PaymentExceedsBalance,
+    MaxExtensionsReached,
+    MaxRefinancesReached,
+    TermsNotImproved,
+    RefinanceProposalNotFound,
+    ListingNotFound,
+    OfferNotFound,
+    InsufficientOfferLiquidity,
+    CollateralTypeNotAccepted,
+    GuaranteeNotFound,
+    LoanStillOpen,
+    NotVariableRate,
+    AlreadyVariableRate,
+    RateUpdateTooFrequent,
+    InvalidRiskMultiplier,
+    NotCompoundInterest,
+    AlreadyCompoundInterest,
+    CompoundingTooEarly,
+    NothingToCompound,
+    InvalidPaymentPeriod,
+    NotInterestOnly,
+    PaymentNotDue,
+    NoInterestOnlyPeriodsLeft,
+    InvalidGracePeriod,
+    MaxGracePeriodsReached,
+    AlreadyTimestampBased,
+    CreditScoreNotFound,
+    CollateralBasketFull,
+    CollateralPositionNotFound,
+    CreditLineNotFound,
+    CreditLimitExceeded,
+    CreditLineExpired,
+    CreditLineNotActive,
+    CreditTierLimitExceeded,
+    UnsecuredExposureCapExceeded,
+    InterestRateBelowQuote,
+    RefinanceProposalStale,
+
+    // Pools
+    PoolNotFound,
+    PoolNotActive,
+    YieldFarmingDisabled,
+    ProviderNotFound,
+    NoRewardsToClaim,
+    RebalanceTooFrequent,
+    InvalidPriceLevel,
+    InsufficientLiquidity,
+    NoTierQualifies,
+
+    // Governance
+    GovernanceTokenNotFound,
+    GovernanceTokenInactive,
+    TokenSupplyExceeded,
+    ProposalNotFound,
+    ProposalNotActive,
+    ProposalNotApproved,
+    VotingClosed,
+    AlreadyVoted,
+    NoVotingPower,
+    InsufficientProposalStake,
+    ExecutionDelayNotMet,
+    TreasuryNotFound,
+    MultiSigWalletNotFound,
+    DaoNotFound,
+
+    // DeFi
+    FlashLoanNotFound,
+    FlashLoanNotExecuted,
+    NftCollateralNotFound,
+    BridgeNotFound,
+    BridgeNotActive,
+    CrossChainTransferNotFound,
+    StakingPoolNotFound,
+    StakingPoolInactive,
+    StakingPositionNotFound,
+    InvalidLockPeriod,
+    CampaignNotFound,
+    CampaignInactive,
+    CampaignNotRunning,
+    MiningPositionNotFound,
+
+    // Admin
     InvalidBenchmarkData,
     ReportGenerationFailed,
-    InvalidStatus,
+    ReportNotFound,
+    LoanMetricsNotFound,
+    PerformanceMetricsNotFound,
+    BatchNotFound,
+    OptimizationNotFound,
+    UpgradeableContractNotFound,
+    ContractNotUpgradeable,
+    ParallelProcessNotFound,
+    InvalidOperationData,
+
+    // Assets
+    AssetNotRegistered,
+    AssetAlreadyRegistered,
+    AssetDisabled,
+    AssetMismatch,
+    NativeAssetOnly,
+    TokenTransferFailed,
+
+    // Oracle
+    PriceFeedNotFound,
+    ReporterNotAuthorized,
+    ReporterAlreadyAuthorized,
+    InvalidPrice,
+    PriceStale,
+    PriceDeviationTooLarge,
+}
+
+impl LendingError {
+    /// Stable numeric code for the error
+    pub fn code(&self) -> u16 {
+        match self {
+            LendingError::InsufficientBalance => 1001,
+            LendingError::TransferFailed => 1002,
+            LendingError::InvalidAmount => 1003,
+            LendingError::Unauthorized => 1004,
+            LendingError::UserNotFound => 1005,
+            LendingError::UserBlacklisted => 1006,
+            LendingError::InvalidStatus => 1007,
+            LendingError::ArithmeticOverflow => 1008,
+            LendingError::DivisionByZero => 1009,
+
+            LendingError::LoanNotFound => 2001,
+            LendingError::LoanNotActive => 2002,
+            LendingError::LoanAlreadyRepaid => 2003,
+            LendingError::LoanExpired => 2004,
+            LendingError::InvalidInterestRate => 2005,
+            LendingError::InvalidDuration => 2006,
+            LendingError::InsufficientCollateral => 2007,
+            LendingError::CollateralSeized => 2008,
+            LendingError::InvalidStatusTransition => 2009,
+            LendingError::ContributionExceedsRequest => 2010,
+            LendingError::FundingDeadlineNotReached => 2011,
+            LendingError::LoanAlreadyDue => 2012,
+            LendingError::LoanNotOverdue => 2013,
+            LendingError::LoanHealthy => 2014,
+            LendingError::DefaultDelayNotElapsed => 2015,
+            LendingError::PaymentExceedsBalance => 2016,
+            LendingError::MaxExtensionsReached => 2017,
+            LendingError::MaxRefinancesReached => 2018,
+            LendingError::TermsNotImproved => 2019,
+            LendingError::RefinanceProposalNotFound => 2020,
+            LendingError::ListingNotFound => 2021,
+            LendingError::OfferNotFound => 2022,
+            LendingError::InsufficientOfferLiquidity => 2023,
+            LendingError::CollateralTypeNotAccepted => 2024,
+            LendingError::GuaranteeNotFound => 2025,
+            LendingError::LoanStillOpen => 2026,
+            LendingError::NotVariableRate => 2027,
+            LendingError::AlreadyVariableRate => 2028,
+            LendingError::RateUpdateTooFrequent => 2029,
+            LendingError::InvalidRiskMultiplier => 2030,
+            LendingError::NotCompoundInterest => 2031,
+            LendingError::AlreadyCompoundInterest => 2032,
+            LendingError::CompoundingTooEarly => 2033,
+            LendingError::NothingToCompound => 2034,
+            LendingError::InvalidPaymentPeriod => 2035,
+            LendingError::NotInterestOnly => 2036,
+            LendingError::PaymentNotDue => 2037,
+            LendingError::NoInterestOnlyPeriodsLeft => 2038,
+            LendingError::InvalidGracePeriod => 2039,
+            LendingError::MaxGracePeriodsReached => 2040,
+            LendingError::AlreadyTimestampBased => 2041,
+            LendingError::CreditScoreNotFound => 2042,
+            LendingError::CollateralBasketFull => 2043,
+            LendingError::CollateralPositionNotFound => 2044,
+            LendingError::CreditLineNotFound => 2045,
+            LendingError::CreditLimitExceeded => 2046,
+            LendingError::CreditLineExpired => 2047,
+            LendingError::CreditLineNotActive => 2048,
+            LendingError::CreditTierLimitExceeded => 2049,
+            LendingError::UnsecuredExposureCapExceeded => 2050,
+            LendingError::InterestRateBelowQuote => 2051,
+            LendingError::RefinanceProposalStale => 2052,
+
+            LendingError::PoolNotFound => 3001,
+            LendingError::PoolNotActive => 3002,
+            LendingError::YieldFarmingDisabled => 3003,
+            LendingError::ProviderNotFound => 3004,
+            LendingError::NoRewardsToClaim => 3005,
+            LendingError::RebalanceTooFrequent => 3006,
+            LendingError::InvalidPriceLevel => 3007,
+            LendingError::InsufficientLiquidity => 3008,
+            LendingError::NoTierQualifies => 3009,
+
+            LendingError::GovernanceTokenNotFound => 4001,
+            LendingError::GovernanceTokenInactive => 4002,
+            LendingError::TokenSupplyExceeded => 4003,
+            LendingError::ProposalNotFound => 4004,
+            LendingError::ProposalNotActive => 4005,
+            LendingError::ProposalNotApproved => 4006,
+            LendingError::VotingClosed => 4007,
+            LendingError::AlreadyVoted => 4008,
+            LendingError::NoVotingPower => 4009,
+            LendingError::InsufficientProposalStake => 4010,
+            LendingError::ExecutionDelayNotMet => 4011,
+            LendingError::TreasuryNotFound => 4012,
+            LendingError::MultiSigWalletNotFound => 4013,
+            LendingError::DaoNotFound => 4014,
+
+            LendingError::FlashLoanNotFound => 5001,
+            LendingError::FlashLoanNotExecuted => 5002,
+            LendingError::NftCollateralNotFound => 5003,
+            LendingError::BridgeNotFound => 5004,
+            LendingError::BridgeNotActive => 5005,
+            LendingError::CrossChainTransferNotFound => 5006,
+            LendingError::StakingPoolNotFound => 5007,
+            LendingError::StakingPoolInactive => 5008,
+            LendingError::StakingPositionNotFound => 5009,
+            LendingError::InvalidLockPeriod => 5010,
+            LendingError::CampaignNotFound => 5011,
+            LendingError::CampaignInactive => 5012,
+            LendingError::CampaignNotRunning => 5013,
+            LendingError::MiningPositionNotFound => 5014,
+
+            LendingError::InvalidBenchmarkData => 6001,
+            LendingError::ReportGenerationFailed => 6002,
+            LendingError::ReportNotFound => 6003,
+            LendingError::LoanMetricsNotFound => 6004,
+            LendingError::PerformanceMetricsNotFound => 6005,
+            LendingError::BatchNotFound => 6006,
+            LendingError::OptimizationNotFound => 6007,
+            LendingError::UpgradeableContractNotFound => 6008,
+            LendingError::ContractNotUpgradeable => 6009,
+            LendingError::ParallelProcessNotFound => 6010,
+            LendingError::InvalidOperationData => 6011,
+
+            LendingError::AssetNotRegistered => 7001,
+            LendingError::AssetAlreadyRegistered => 7002,
+            LendingError::AssetDisabled => 7003,
+            LendingError::AssetMismatch => 7004,
+            LendingError::NativeAssetOnly => 7005,
+            LendingError::TokenTransferFailed => 7006,
+
+            LendingError::PriceFeedNotFound => 8001,
+            LendingError::ReporterNotAuthorized => 8002,
+            LendingError::ReporterAlreadyAuthorized => 8003,
+            LendingError::InvalidPrice => 8004,
+            LendingError::PriceStale => 8005,
+            LendingError::PriceDeviationTooLarge => 8006,
+        }
+    }
+
+    /// Human-readable description of the error
+    pub fn description(&self) -> &'static str {
+        match self {
+            LendingError::InsufficientBalance => "Insufficient balance",
+            LendingError::TransferFailed => "Transfer failed",
+            LendingError::InvalidAmount => "Invalid amount",
+            LendingError::Unauthorized => "Unauthorized action",
+            LendingError::UserNotFound => "User not found",
+            LendingError::UserBlacklisted => "User is blacklisted",
+            LendingError::InvalidStatus => "Invalid status",
+            LendingError::ArithmeticOverflow => "Arithmetic overflow",
+            LendingError::DivisionByZero => "Division by zero",
+
+            LendingError::LoanNotFound => "Loan not found",
+            LendingError::LoanNotActive => "Loan is not active",
+            LendingError::LoanAlreadyRepaid => "Loan already repaid",
+            LendingError::LoanExpired => "Loan has expired",
+            LendingError::InvalidInterestRate => "Invalid interest rate",
+            LendingError::InvalidDuration => "Invalid duration",
+            LendingError::InsufficientCollateral => "Insufficient collateral",
+            LendingError::CollateralSeized => "Collateral has been seized",
+            LendingError::InvalidStatusTransition => "Loan cannot move to that status",
+            LendingError::ContributionExceedsRequest => "Contribution exceeds the unfunded amount",
+            LendingError::FundingDeadlineNotReached => "Funding deadline not set or not yet reached",
+            LendingError::LoanAlreadyDue => "Loan is already due",
+            LendingError::LoanNotOverdue => "Loan is not overdue yet",
+            LendingError::LoanHealthy => "Loan is healthy and not past its grace period",
+            LendingError::DefaultDelayNotElapsed => "Default delay has not elapsed",
+            LendingError::PaymentExceedsBalance => "Payment exceeds the remaining balance",
+            LendingError::MaxExtensionsReached => "Maximum number of extensions reached",
+            LendingError::MaxRefinancesReached => "Maximum number of refinances reached",
+            LendingError::TermsNotImproved => "New terms are not better than the current ones",
+            LendingError::RefinanceProposalNotFound => "Refinance proposal not found",
+            LendingError::ListingNotFound => "Loan listing not found",
+            LendingError::OfferNotFound => "Loan offer not found",
+            LendingError::InsufficientOfferLiquidity => "Offer does not have enough liquidity",
+            LendingError::CollateralTypeNotAccepted => "Collateral type not accepted by the offer",
+            LendingError::GuaranteeNotFound => "Guarantee not found",
+            LendingError::LoanStillOpen => "Loan is still open",
+            LendingError::NotVariableRate => "Loan does not have a variable rate",
+            LendingError::AlreadyVariableRate => "Loan already has a variable rate",
+            LendingError::RateUpdateTooFrequent => "Rate was updated too recently",
+            LendingError::InvalidRiskMultiplier => "Invalid risk multiplier",
+            LendingError::NotCompoundInterest => "Loan does not use compound interest",
+            LendingError::AlreadyCompoundInterest => "Loan already uses compound interest",
+            LendingError::CompoundingTooEarly => "Compounding period has not elapsed",
+            LendingError::NothingToCompound => "No periods to compound",
+            LendingError::InvalidPaymentPeriod => "Invalid payment period",
+            LendingError::NotInterestOnly => "Loan is not in an interest-only period",
+            LendingError::PaymentNotDue => "Payment is not due yet",
+            LendingError::NoInterestOnlyPeriodsLeft => "No interest-only periods left",
+            LendingError::InvalidGracePeriod => "Invalid grace period",
+            LendingError::MaxGracePeriodsReached => "Maximum number of grace periods reached",
+            LendingError::AlreadyTimestampBased => "Loan already uses the timestamp clock",
+            LendingError::CreditScoreNotFound => "Credit score not found",
+            LendingError::CollateralBasketFull => "Collateral basket already holds the maximum number of positions",
+            LendingError::CollateralPositionNotFound => "Collateral basket has no such position",
+            LendingError::CreditLineNotFound => "Credit line not found",
+            LendingError::CreditLimitExceeded => "Draw would exceed the credit line's limit",
+            LendingError::CreditLineExpired => "Credit line has expired",
+            LendingError::CreditLineNotActive => "Credit line is not open for draws",
+            LendingError::CreditTierLimitExceeded => "Loan exceeds the maximum size of the borrower's credit tier",
+            LendingError::UnsecuredExposureCapExceeded => "Loan would take unsecured exposure in the asset past its cap",
+            LendingError::InterestRateBelowQuote => "Interest rate is below the rate quoted for the loan",
+            LendingError::RefinanceProposalStale => "Refinance proposal escrow no longer covers the loan balance",
+
+            LendingError::PoolNotFound => "Liquidity pool not found",
+            LendingError::PoolNotActive => "Liquidity pool is not active",
+            LendingError::YieldFarmingDisabled => "Yield farming is disabled for the pool",
+            LendingError::ProviderNotFound => "Liquidity provider not found",
+            LendingError::NoRewardsToClaim => "No rewards to claim",
+            LendingError::RebalanceTooFrequent => "Pool was rebalanced too recently",
+            LendingError::InvalidPriceLevel => "Invalid market depth price level",
+            LendingError::InsufficientLiquidity => "Not enough liquidity available",
+            LendingError::NoTierQualifies => "No reward tier qualifies",
+
+            LendingError::GovernanceTokenNotFound => "Governance token not found",
+            LendingError::GovernanceTokenInactive => "Governance token is not active",
+            LendingError::TokenSupplyExceeded => "Governance token supply exceeded",
+            LendingError::ProposalNotFound => "Proposal not found",
+            LendingError::ProposalNotActive => "Proposal is not open for voting",
+            LendingError::ProposalNotApproved => "Proposal has not been approved",
+            LendingError::VotingClosed => "Voting period is not open",
+            LendingError::AlreadyVoted => "Already voted on this proposal",
+            LendingError::NoVotingPower => "No voting power",
+            LendingError::InsufficientProposalStake => "Not enough governance tokens to propose",
+            LendingError::ExecutionDelayNotMet => "Execution delay has not elapsed",
+            LendingError::TreasuryNotFound => "Treasury not found",
+            LendingError::MultiSigWalletNotFound => "Multi-signature wallet not found",
+            LendingError::DaoNotFound => "DAO configuration not found",
+
+            LendingError::FlashLoanNotFound => "Flash loan not found",
+            LendingError::FlashLoanNotExecuted => "Flash loan has not been executed",
+            LendingError::NftCollateralNotFound => "NFT collateral not found",
+            LendingError::BridgeNotFound => "Cross-chain bridge not found",
+            LendingError::BridgeNotActive => "Cross-chain bridge is not active",
+            LendingError::CrossChainTransferNotFound => "Cross-chain transfer not found",
+            LendingError::StakingPoolNotFound => "Staking pool not found",
+            LendingError::StakingPoolInactive => "Staking pool is not active",
+            LendingError::StakingPositionNotFound => "Staking position not found",
+            LendingError::InvalidLockPeriod => "Invalid lock period",
+            LendingError::CampaignNotFound => "Liquidity mining campaign not found",
+            LendingError::CampaignInactive => "Liquidity mining campaign is not active",
+            LendingError::CampaignNotRunning => "Liquidity mining campaign is not running",
+            LendingError::MiningPositionNotFound => "Liquidity mining position not found",
+
+            LendingError::InvalidBenchmarkData => "Invalid benchmark data",
+            LendingError::ReportGenerationFailed => "Report generation failed",
+            LendingError::ReportNotFound => "Analytics report not found",
+            LendingError::LoanMetricsNotFound => "Loan performance metrics not found",
+            LendingError::PerformanceMetricsNotFound => "Performance metrics not found",
+            LendingError::BatchNotFound => "Batch operation not found",
+            LendingError::OptimizationNotFound => "Optimization not found",
+            LendingError::UpgradeableContractNotFound => "Upgradeable contract not found",
+            LendingError::ContractNotUpgradeable => "Contract is not upgradeable",
+            LendingError::ParallelProcessNotFound => "Parallel process not found",
+            LendingError::InvalidOperationData => "Invalid operation data",
+
+            LendingError::AssetNotRegistered => "Asset not registered",
+            LendingError::AssetAlreadyRegistered => "Asset already registered",
+            LendingError::AssetDisabled => "Asset is disabled",
+            LendingError::AssetMismatch => "Asset does not match the loan or pool",
+            LendingError::NativeAssetOnly => "Operation only supports the native currency",
+            LendingError::TokenTransferFailed => "Token transfer failed",
+
+            LendingError::PriceFeedNotFound => "Price feed not found",
+            LendingError::ReporterNotAuthorized => "Account is not a reporter for this price feed",
+            LendingError::ReporterAlreadyAuthorized => "Reporter already authorized",
+            LendingError::InvalidPrice => "Invalid price",
+            LendingError::PriceStale => "Price is stale",
+            LendingError::PriceDeviationTooLarge => "Price deviates too far from the current price",
+        }
+    }
 }
 
 impl From<LendingError> for String {
     fn from(error: LendingError) -> Self {
-        match error {
-            LendingError::InsufficientBalance => "Insufficient balance".into(),
-            LendingError::InsufficientCollateral => "Insufficient collateral".into(),
-            LendingError::LoanNotFound => "Loan not found".into(),
-            LendingError::LoanNotActive => "Loan is not active".into(),
-            LendingError::LoanAlreadyRepaid => "Loan already repaid".into(),
-            LendingError::InvalidInterestRate => "Invalid interest rate".into(),
-            LendingError::InvalidDuration => "Invalid duration".into(),
-            LendingError::UserBlacklisted => "User is blacklisted".into(),
-            LendingError::Unauthorized => "Unauthorized action".into(),
-            LendingError::TransferFailed => "Transfer failed".into(),
-            LendingError::InvalidAmount => "Invalid amount".into(),
-            LendingError::LoanExpired => "Loan has expired".into(),
-            LendingError::CollateralSeized => "Collateral has been seized".into(),
-            LendingError::UserNotFound => "User not found".into(),
-            LendingError::InvalidBenchmarkData => "Invalid benchmark data".into(),
-            LendingError::ReportGenerationFailed => "Report generation failed".into(),
-            LendingError::InvalidStatus => "Invalid status".into(),
-        }
+        error.description().into()
     }
-} 
\ No newline at end of file
+}
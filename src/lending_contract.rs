@@ -1,17 +1,24 @@
+use ink::env::call::{build_call, ExecutionInput, Selector};
+use ink::env::DefaultEnvironment;
 use ink::storage::Mapping;
 use ink_prelude::vec::Vec;
 
 use crate::types::{
-    Loan, LoanStatus, UserProfile, PartialPayment, PaymentType, RefinanceRecord,
-    InterestRateType, InterestRateAdjustment, RateAdjustmentReason, InterestType, CompoundFrequency, PaymentStructure,
-    GracePeriodReason, GracePeriodRecord, LiquidityPool, PoolStatus, LiquidityProvider, RewardToken, StakingRequirements, TierMultiplier,
+    Loan, LoanRequest, LoanStatus, LoanAction, UserProfile, LenderContribution, LoanListing, LoanOffer, OfferStatus, PartialPayment, PaymentType, RefinanceRecord, RefinanceProposal, RefinanceProposalStatus, Guarantee, GuaranteeStatus,
+    InterestRateType, InterestRateAdjustment, RateAdjustmentReason, InterestType, CompoundFrequency, PaymentStructure, Installment, InstallmentStatus,
+    GracePeriodReason, GracePeriodRecord, LiquidityPool, PoolConfig, PoolStatus, LiquidityProvider, RewardToken, StakingRequirements, TierMultiplier,
     MarketDepthLevel, OptimalDistribution, ConcentrationLimits, CollateralType, CollateralRequirement, InsurancePolicy, InsuranceStatus, FraudDetectionRule, FraudRuleType, FraudAction, ComplianceRecord, ComplianceStatus, ComplianceType, CreditScore, CreditFactor, CreditFactorType, CreditScoreRecord, RiskLevel,
     MarketStatistics, MarketTrend, LoanPerformanceMetrics, PortfolioAnalytics, HistoricalDataPoint, PerformanceBenchmark, BenchmarkCategory, AnalyticsReport, ReportType, AnalyticsMetric, MetricTrend,
-    FlashLoan, FlashLoanStatus, CrossChainBridge, BridgeStatus, CrossChainTransfer, TransferStatus, NFTCollateral, NFTMetadata, StakingPool, StakingPosition, LiquidityMining, LiquidityMiningPosition,
+    FeeSource, ProtocolRevenue, FlashLoan, FlashLoanStatus, CrossChainBridge, BridgeStatus, CrossChainTransfer, TransferStatus, NFTCollateral, NFTMetadata, StakingPool, StakingPosition, LiquidityMining, LiquidityMiningPosition,
     GovernanceToken, GovernanceProposal, ProposalType, ProposalStatus, Vote, VoteChoice, Treasury, TreasuryTransaction, MultiSignatureWallet, MultiSigTransaction, DAOConfiguration, GovernanceSnapshot,
     BatchOperation, BatchOperationType, BatchItem, BatchStatus, BatchItemStatus, StorageOptimization, StorageOptimizationType, OptimizationStatus, UpgradeableContract, ContractUpgrade, GasOptimization, GasOptimizationType, ParallelProcessing, ParallelProcessType, ParallelOperation, ParallelProcessStatus, ParallelOperationStatus, PerformanceMetrics, PerformanceRating,
+    LegacyLoan, DEFAULT_BLOCK_TIME_MS, MILLIS_PER_DAY, MILLIS_PER_WEEK, MILLIS_PER_YEAR, BORROW_INDEX_SCALE,
+    Asset, AssetConfig, AssetTotals, Psp22Error, NATIVE_DECIMALS, PriceFeed, PriceReport, PriceObservation, MAX_PRICE_OBSERVATIONS,
+    CollateralPosition, MAX_BASKET_POSITIONS, CreditLine, CreditLineTerms, CreditLineStatus, CreditTier, PricingModel,
 };
 use crate::errors::LendingError;
+use crate::math::{SafeMath, mul_div, bps_mul, bps_ratio, Rounding, Ray, Wad, BPS};
+use crate::oracle::{self, PriceBound};
 
 // ============================================================================
 // LENDING SMART CONTRACT - REFACTORED FOR SENIOR DEVELOPER STANDARDS
@@ -46,9 +53,38 @@ pub mod lending_contract {
         total_loans: u64,
         loans: Mapping<u64, Loan>,
         user_profiles: Mapping<AccountId, UserProfile>,
-        total_liquidity: Balance,
+        assets: Mapping<Asset, AssetConfig>, // Registry of assets loans and pools can be denominated in
+        registered_assets: Vec<Asset>,
+        asset_totals: Mapping<Asset, AssetTotals>, // Liquidity, escrow and fee totals per asset
+        price_feeds: Mapping<Asset, PriceFeed>, // Reporter-driven price feeds for cross-asset valuation
         protocol_fee: u16, // Basis points
+        protocol_revenue: ProtocolRevenue, // Collected protocol fees by source
+        treasury_account: Option<AccountId>, // Account allowed to withdraw protocol fees besides the owner
         min_collateral_ratio: u16, // Basis points
+        escrowed_collateral: Mapping<u64, Balance>, // Loan ID -> collateral held by the contract
+        liquidation_threshold: u16, // Default liquidation threshold in basis points
+        liquidation_bonus: u16, // Liquidator bonus in basis points
+        maintenance_margin: u16, // Default health factor in basis points below which a margin call is issued
+        collateral_factors: Mapping<(CollateralType, Asset), u16>, // Share of a basket position's value that counts towards borrowing power, in basis points
+        collateral_baskets: Mapping<u64, Vec<CollateralPosition>>, // Loan ID -> basket positions held besides the primary collateral
+        credit_tiers: Mapping<(RiskLevel, bool), CreditTier>, // (risk level, KYC verified) -> origination terms
+        unsecured_principal: Mapping<u64, Balance>, // Loan ID -> principal counted towards unsecured exposure
+        unsecured_exposure_caps: Mapping<Asset, Balance>, // Largest unsecured exposure allowed per asset
+        pricing_model: PricingModel, // Inputs of the minimum rate quoted for pool-funded loans
+        risk_multipliers: Mapping<RiskLevel, u16>, // Risk level -> multiplier applied to quoted rates (1000 = 1.0x)
+        default_delay_ms: u64, // Time in ms after the grace period before a loan can be declared defaulted
+        block_time_ms: u64, // Expected block time, used to migrate legacy block-based loans
+        keepers: Mapping<AccountId, bool>, // Accounts allowed to declare defaults
+        total_defaults: u64,
+        loan_guarantees: Mapping<u64, Guarantee>, // Loan ID -> guarantor stake
+        loan_listings: Mapping<u64, LoanListing>, // Loan ID -> lender position for sale
+        refinance_proposals: Mapping<u64, RefinanceProposal>,
+        total_refinance_proposals: u64,
+        loan_offers: Mapping<u64, LoanOffer>,
+        total_loan_offers: u64,
+        offer_depth_levels: Vec<MarketDepthLevel>, // Market depth backed by active offers, one level per interest rate
+        credit_lines: Mapping<u64, CreditLine>,
+        total_credit_lines: u64,
         total_pools: u64,
         liquidity_pools: Mapping<u64, LiquidityPool>,
         pool_liquidity_providers: Mapping<u64, Vec<AccountId>>,
@@ -139,6 +175,7 @@ pub mod lending_contract {
         #[ink(topic)]
         loan_id: u64,
         borrower: AccountId,
+        asset: Asset,
         amount: Balance,
         interest_rate: u16,
         duration: u64,
@@ -152,23 +189,141 @@ pub mod lending_contract {
         amount: Balance,
     }
 
+    #[ink(event)]
+    pub struct LoanContributionReceived {
+        #[ink(topic)]
+        loan_id: u64,
+        lender: AccountId,
+        amount: Balance,
+        funded_amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ContributionRefunded {
+        #[ink(topic)]
+        loan_id: u64,
+        lender: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanCancelled {
+        #[ink(topic)]
+        loan_id: u64,
+        borrower: AccountId,
+        collateral_refunded: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanExpired {
+        #[ink(topic)]
+        loan_id: u64,
+        borrower: AccountId,
+        expired_by: AccountId,
+        collateral_refunded: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanStatusChanged {
+        #[ink(topic)]
+        loan_id: u64,
+        from: LoanStatus,
+        to: LoanStatus,
+        reason: LoanAction,
+    }
+
     #[ink(event)]
     pub struct LoanRepaid {
         #[ink(topic)]
         loan_id: u64,
         borrower: AccountId,
+        payer: AccountId,
+        amount: Balance,
+        late_fees_paid: Balance,
+        interest_paid: Balance,
+        principal_paid: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CollateralReleased {
+        #[ink(topic)]
+        loan_id: u64,
+        recipient: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CollateralAdded {
+        #[ink(topic)]
+        loan_id: u64,
+        amount: Balance,
+        health_factor: u128,
+    }
+
+    #[ink(event)]
+    pub struct CollateralFactorSet {
+        #[ink(topic)]
+        asset: Asset,
+        collateral_type: CollateralType,
+        collateral_factor: u16,
+    }
+
+    #[ink(event)]
+    pub struct CreditTierSet {
+        risk_level: RiskLevel,
+        kyc_verified: bool,
+        tier: Option<CreditTier>,
+    }
+
+    #[ink(event)]
+    pub struct LoanPriced {
+        #[ink(topic)]
+        loan_id: u64,
+        #[ink(topic)]
+        pool_id: u64,
+        quoted_rate: u16,
+        interest_rate: u16,
+        risk_multiplier: u16,
+    }
+
+    #[ink(event)]
+    pub struct BasketCollateralAdded {
+        #[ink(topic)]
+        loan_id: u64,
+        collateral_type: CollateralType,
+        asset: Asset,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BasketCollateralReleased {
+        #[ink(topic)]
+        loan_id: u64,
+        asset: Asset,
+        recipient: AccountId,
         amount: Balance,
     }
 
+    #[ink(event)]
+    pub struct MarginCall {
+        #[ink(topic)]
+        loan_id: u64,
+        #[ink(topic)]
+        borrower: AccountId,
+        health_factor: u128,
+        maintenance_margin: u16,
+    }
+
     #[ink(event)]
     pub struct LoanEarlyRepaid {
         #[ink(topic)]
         loan_id: u64,
         borrower: AccountId,
+        payer: AccountId,
         original_amount: Balance,
         discounted_amount: Balance,
         discount_applied: Balance,
-        blocks_early: u64,
+        time_early: u64,
     }
 
     #[ink(event)]
@@ -176,7 +331,11 @@ pub mod lending_contract {
         #[ink(topic)]
         loan_id: u64,
         borrower: AccountId,
+        payer: AccountId,
         payment_amount: Balance,
+        late_fees_paid: Balance,
+        interest_paid: Balance,
+        principal_paid: Balance,
         remaining_balance: Balance,
         total_paid: Balance,
     }
@@ -198,7 +357,7 @@ pub mod lending_contract {
         #[ink(topic)]
         loan_id: u64,
         borrower: AccountId,
-        overdue_blocks: u64,
+        overdue_ms: u64,
         late_fees_added: Balance,
         total_late_fees: Balance,
         new_remaining_balance: Balance,
@@ -218,6 +377,201 @@ pub mod lending_contract {
         refinance_count: u32,
     }
 
+    #[ink(event)]
+    pub struct RefinanceProposed {
+        #[ink(topic)]
+        proposal_id: u64,
+        loan_id: u64,
+        new_lender: AccountId,
+        new_interest_rate: u16,
+        max_payoff: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanRefinancedByLender {
+        #[ink(topic)]
+        old_loan_id: u64,
+        new_loan_id: u64,
+        borrower: AccountId,
+        old_lender: AccountId,
+        new_lender: AccountId,
+        payoff_amount: Balance,
+        new_interest_rate: u16,
+    }
+
+    #[ink(event)]
+    pub struct LenderPositionTransferred {
+        #[ink(topic)]
+        loan_id: u64,
+        from: AccountId,
+        to: AccountId,
+        principal: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanListedForSale {
+        #[ink(topic)]
+        loan_id: u64,
+        seller: AccountId,
+        price: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanSold {
+        #[ink(topic)]
+        loan_id: u64,
+        seller: AccountId,
+        buyer: AccountId,
+        price: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanOfferCreated {
+        #[ink(topic)]
+        offer_id: u64,
+        lender: AccountId,
+        max_amount: Balance,
+        interest_rate: u16,
+    }
+
+    #[ink(event)]
+    pub struct LoanOfferAccepted {
+        #[ink(topic)]
+        offer_id: u64,
+        loan_id: u64,
+        borrower: AccountId,
+        amount: Balance,
+        remaining_liquidity: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanOfferCancelled {
+        #[ink(topic)]
+        offer_id: u64,
+        lender: AccountId,
+        refunded_amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CreditLineOpened {
+        #[ink(topic)]
+        line_id: u64,
+        #[ink(topic)]
+        borrower: AccountId,
+        lender: Option<AccountId>,
+        liquidity_pool_id: Option<u64>,
+        asset: Asset,
+        limit: Balance,
+        expires_at: u64,
+    }
+
+    #[ink(event)]
+    pub struct CreditLineDrawn {
+        #[ink(topic)]
+        line_id: u64,
+        amount: Balance,
+        drawn_principal: Balance,
+        balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CreditLineRepaid {
+        #[ink(topic)]
+        line_id: u64,
+        payer: AccountId,
+        amount: Balance,
+        late_fees_paid: Balance,
+        interest_paid: Balance,
+        principal_paid: Balance,
+        balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CreditLineLateFees {
+        #[ink(topic)]
+        line_id: u64,
+        overdue_ms: u64,
+        late_fees_added: Balance,
+        balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CreditLineGracePeriodGranted {
+        #[ink(topic)]
+        line_id: u64,
+        reason: GracePeriodReason,
+        duration: u64,
+        extension_number: u32,
+        total_grace_period: u64,
+    }
+
+    #[ink(event)]
+    pub struct CreditLineClosed {
+        #[ink(topic)]
+        line_id: u64,
+        closed_by: AccountId,
+        released_liquidity: Balance,
+        balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanLiquidated {
+        #[ink(topic)]
+        loan_id: u64,
+        borrower: AccountId,
+        liquidator: AccountId,
+        collateral_seized: Balance,
+        debt_repaid: Balance,
+        liquidator_bonus: Balance,
+        surplus_returned: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanDefaulted {
+        #[ink(topic)]
+        loan_id: u64,
+        borrower: AccountId,
+        lender: AccountId,
+        declared_by: AccountId,
+        guarantee_called: Balance,
+        collateral_transferred: Balance,
+        outstanding_balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct GuaranteeAdded {
+        #[ink(topic)]
+        loan_id: u64,
+        guarantor: AccountId,
+        stake: Balance,
+    }
+
+    #[ink(event)]
+    pub struct GuaranteeCalled {
+        #[ink(topic)]
+        loan_id: u64,
+        guarantor: AccountId,
+        amount_called: Balance,
+        recovery_claim: Balance,
+    }
+
+    #[ink(event)]
+    pub struct GuaranteeReleased {
+        #[ink(topic)]
+        loan_id: u64,
+        guarantor: AccountId,
+        stake_returned: Balance,
+    }
+
+    #[ink(event)]
+    pub struct GuarantorRepaid {
+        #[ink(topic)]
+        loan_id: u64,
+        guarantor: AccountId,
+        amount: Balance,
+        recovery_claim: Balance,
+    }
+
     #[ink(event)]
     pub struct InterestRateAdjusted {
         #[ink(topic)]
@@ -241,11 +595,31 @@ pub mod lending_contract {
         new_remaining_balance: Balance,
     }
 
+    #[ink(event)]
+    pub struct InstallmentPaid {
+        #[ink(topic)]
+        loan_id: u64,
+        period: u32,
+        amount_paid: Balance,
+        paid_late: bool,
+    }
+
+    #[ink(event)]
+    pub struct InstallmentMissed {
+        #[ink(topic)]
+        loan_id: u64,
+        borrower: AccountId,
+        period: u32,
+        due_date: u64,
+        amount_due: Balance,
+    }
+
     #[ink(event)]
     pub struct InterestOnlyPaymentMade {
         #[ink(topic)]
         loan_id: u64,
         borrower: AccountId,
+        payer: AccountId,
         payment_period: u32,
         interest_paid: Balance,
         principal_remaining: Balance,
@@ -481,6 +855,56 @@ pub mod lending_contract {
         callback_target: AccountId,
     }
 
+    #[ink(event)]
+    pub struct ProtocolFeesWithdrawn {
+        #[ink(topic)]
+        to: AccountId,
+        asset: Asset,
+        amount: Balance,
+        withdrawn_by: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct AssetRegistered {
+        #[ink(topic)]
+        asset: Asset,
+        decimals: u8,
+    }
+
+    #[ink(event)]
+    pub struct AssetStatusChanged {
+        #[ink(topic)]
+        asset: Asset,
+        enabled: bool,
+    }
+
+    #[ink(event)]
+    pub struct OracleUpdated {
+        #[ink(topic)]
+        asset: Asset,
+        reporter: AccountId,
+        price: Balance,
+        twap: Balance,
+        report_count: u32,
+    }
+
+    #[ink(event)]
+    pub struct InterestAccrued {
+        #[ink(topic)]
+        loan_id: u64,
+        interest_accrued: Balance,
+        borrow_index: u128,
+        remaining_balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LoanClockMigrated {
+        #[ink(topic)]
+        loan_id: u64,
+        block_time_ms: u64,
+        new_due_date: u64,
+    }
+
     #[ink(event)]
     pub struct FlashLoanRepaid {
         #[ink(topic)]
@@ -774,14 +1198,49 @@ pub mod lending_contract {
         
         #[ink(constructor)]
         pub fn new() -> Self {
-            Self {
+            let mut contract = Self {
                 owner: Self::env().caller(),
                 total_loans: 0,
                 loans: Mapping::default(),
                 user_profiles: Mapping::default(),
-                total_liquidity: 0,
+                assets: Mapping::default(),
+                registered_assets: Vec::new(),
+                asset_totals: Mapping::default(),
+                price_feeds: Mapping::default(),
                 protocol_fee: 50, // 0.5%
+                protocol_revenue: ProtocolRevenue::default(),
+                treasury_account: None,
                 min_collateral_ratio: 150, // 150%
+                escrowed_collateral: Mapping::default(),
+                liquidation_threshold: 8000, // 80%
+                liquidation_bonus: 500, // 5%
+                maintenance_margin: 12000, // 120%
+                collateral_factors: Mapping::default(),
+                collateral_baskets: Mapping::default(),
+                credit_tiers: Mapping::default(),
+                unsecured_principal: Mapping::default(),
+                unsecured_exposure_caps: Mapping::default(),
+                pricing_model: PricingModel {
+                    base_rate: 500, // 5%
+                    ltv_premium: 500, // 5% at 100% loan-to-value
+                    optimal_utilization: 8000, // 80%
+                    utilization_premium: 400, // 4% at optimal utilization
+                    excess_utilization_premium: 6000, // 60% more at full utilization
+                },
+                risk_multipliers: Mapping::default(),
+                default_delay_ms: MILLIS_PER_DAY, // Default: 1 day after the grace period
+                block_time_ms: DEFAULT_BLOCK_TIME_MS,
+                keepers: Mapping::default(),
+                total_defaults: 0,
+                loan_guarantees: Mapping::default(),
+                loan_listings: Mapping::default(),
+                refinance_proposals: Mapping::default(),
+                total_refinance_proposals: 0,
+                loan_offers: Mapping::default(),
+                total_loan_offers: 0,
+                offer_depth_levels: Vec::new(),
+                credit_lines: Mapping::default(),
+                total_credit_lines: 0,
                 total_pools: 0,
                 liquidity_pools: Mapping::default(),
                 pool_liquidity_providers: Mapping::default(),
@@ -871,15 +1330,34 @@ pub mod lending_contract {
                 optimization_queue: Vec::new(),
                 gas_usage_tracker: Mapping::default(),
                 storage_usage_tracker: Mapping::default(),
-            }
+            };
+
+            // The native currency is always available
+            let native = AssetConfig {
+                asset: Asset::Native,
+                decimals: NATIVE_DECIMALS,
+                enabled: true,
+                registered_at: contract.now(),
+            };
+            contract.assets.insert(Asset::Native, &native);
+            contract.registered_assets.push(Asset::Native);
+
+            // Default risk multipliers: better scores are quoted below the model rate
+            contract.risk_multipliers.insert(RiskLevel::Excellent, &800);
+            contract.risk_multipliers.insert(RiskLevel::Good, &900);
+            contract.risk_multipliers.insert(RiskLevel::Fair, &1000);
+            contract.risk_multipliers.insert(RiskLevel::Poor, &1250);
+            contract.risk_multipliers.insert(RiskLevel::VeryPoor, &1500);
+
+            contract
         }
 
         // ============================================================================
         // CORE LENDING OPERATIONS
         // ============================================================================
         
-        /// Create a new loan request
-        #[ink(message)]
+        /// Create a new loan request, escrowing the collateral sent with the call
+        #[ink(message, payable)]
         pub fn create_loan(
             &mut self,
             amount: Balance,
@@ -887,132 +1365,46 @@ pub mod lending_contract {
             duration: u64,
             collateral: Balance,
         ) -> Result<u64, LendingError> {
-            let caller = self.env().caller();
-            
-            // Validate parameters
-            if amount == 0 || interest_rate == 0 || duration == 0 {
-                return Err(LendingError::InvalidAmount);
-            }
-            
-            if interest_rate > 10000 { // Max 100%
-                return Err(LendingError::InvalidInterestRate);
-            }
-            
-            if duration > 1000000 { // Max ~1 year (assuming 6s blocks)
-                return Err(LendingError::InvalidDuration);
-            }
-
-            // Check if user is blacklisted and track new users
-            let is_new_user = !self.user_profiles.contains(caller);
-            let user_profile = self.get_or_create_user_profile(caller);
-            if user_profile.is_blacklisted {
-                return Err(LendingError::UserBlacklisted);
-            }
-            
-            // Increment user count for new users
-            if is_new_user {
-                self.total_users += 1;
-            }
-
-            // Validate collateral ratio
-            let required_collateral = (amount * self.min_collateral_ratio as u128) / 10000;
-            if collateral < required_collateral {
-                return Err(LendingError::InsufficientCollateral);
-            }
-
-            let loan_id = self.total_loans + 1;
-            let current_block = self.env().block_number() as u64;
-            
-            let loan = Loan {
-                id: loan_id,
-                borrower: caller,
-                lender: None,
+            self.create_loan_request(LoanRequest {
+                asset: Asset::Native,
+                collateral_asset: Asset::Native,
                 amount,
                 interest_rate,
                 duration,
                 collateral,
-                status: LoanStatus::Pending,
-                created_at: current_block,
-                due_date: current_block + duration,
-                early_repayment_discount: 200, // Default 2% discount for early repayment
-                total_paid: 0,
-                remaining_balance: 0, // Will be set when loan is funded
-                partial_payments: Vec::new(),
-                extension_count: 0,
-                max_extensions: 3, // Default maximum of 3 extensions
-                extension_fee_rate: 100, // Default 1% extension fee
-                late_fee_rate: 50, // Default 0.5% daily late fee
-                max_late_fee_rate: 1000, // Default 10% maximum late fee
-                total_late_fees: 0,
-                overdue_since: None,
-                grace_period: 100, // Default 100 blocks grace period (~10 minutes)
-                refinance_count: 0,
-                max_refinances: 2, // Default maximum of 2 refinances
-                refinance_fee_rate: 200, // Default 2% refinance fee
-                original_loan_id: None,
-                refinance_history: Vec::new(),
-                interest_rate_type: InterestRateType::Fixed, // Default to fixed rate
-                base_interest_rate: interest_rate, // Base rate same as initial rate
-                risk_multiplier: 1000, // Default 1.0x risk multiplier
-                interest_rate_adjustments: Vec::new(),
-                last_interest_update: current_block,
-                interest_update_frequency: 14400, // Default: daily updates (14400 blocks)
-                interest_type: InterestType::Simple, // Default to simple interest
-                compound_frequency: CompoundFrequency::Daily, // Default to daily compounding
-                last_compound_date: current_block,
-                compound_period_blocks: 14400, // Default: daily (14400 blocks)
-                accrued_interest: 0,
-                total_compounded_interest: 0,
-                payment_structure: PaymentStructure::PrincipalAndInterest, // Default to P&I
-                interest_only_periods: 0, // Default: no interest-only periods
-                current_payment_period: 0,
-                interest_only_periods_used: 0,
-                next_payment_due: current_block + 14400, // First payment due in 1 day
-                payment_period_blocks: 14400, // Default: daily payments (14400 blocks)
-                minimum_payment_amount: 0, // No minimum initially
-                grace_period_blocks: 100, // Default: 100 blocks grace period (~10 minutes)
-                grace_period_used: 0,
-                grace_period_extensions: 0,
-                max_grace_period_extensions: 2, // Default: maximum 2 grace period extensions
-                grace_period_reason: GracePeriodReason::None,
-                grace_period_history: Vec::new(),
-                liquidity_pool_id: None,
-                pool_share: 0,
-                liquidity_provider: None,
-                pool_rewards_earned: 0,
-                credit_score: None,
-                collateral_requirements: Vec::new(),
-                insurance_policies: Vec::new(),
-                fraud_flags: Vec::new(),
-                compliance_status: ComplianceStatus::Pending,
-            };
-
-            self.loans.insert(loan_id, &loan);
-            self.total_loans = loan_id;
-
-            // Update user profile
-            let mut profile = user_profile;
-            profile.active_loans.push(loan_id);
-            self.user_profiles.insert(caller, &profile);
+                basket: Vec::new(),
+            })
+        }
 
-            self.env().emit_event(LoanCreated {
-                loan_id,
-                borrower: caller,
+        /// Create a loan request denominated in registered assets; PSP22 collateral is pulled from the caller's allowance
+        #[ink(message, payable)]
+        pub fn create_asset_loan(
+            &mut self,
+            asset: Asset,
+            collateral_asset: Asset,
+            amount: Balance,
+            interest_rate: u16,
+            duration: u64,
+            collateral: Balance,
+        ) -> Result<u64, LendingError> {
+            self.create_loan_request(LoanRequest {
+                asset,
+                collateral_asset,
                 amount,
                 interest_rate,
                 duration,
-            });
-
-            Ok(loan_id)
+                collateral,
+                basket: Vec::new(),
+            })
         }
 
         /// Fund a pending loan
-        #[ink(message)]
+        #[ink(message, payable)]
         pub fn fund_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
             let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            if loan.status != LoanStatus::Pending {
+            if !loan.status.allows(&LoanAction::Fund) {
                 return Err(LendingError::LoanNotActive);
             }
 
@@ -1020,337 +1412,312 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
 
-            // Transfer funds from lender to contract
-            if self.env().transferred_value() != loan.amount {
-                return Err(LendingError::InvalidAmount);
+            // Partially syndicated loans must be completed through contributions
+            if loan.funded_amount > 0 {
+                return Err(LendingError::InvalidStatus);
             }
 
-            loan.lender = Some(caller);
-            loan.status = LoanStatus::Active;
-            
-            // Set initial remaining balance (principal + interest)
-            let total_repayment = loan.amount + ((loan.amount * loan.interest_rate as u128) / 10000);
-            loan.remaining_balance = total_repayment;
-            
-            self.loans.insert(loan_id, &loan);
-
-            // Update lender profile
-            let mut lender_profile = self.get_or_create_user_profile(caller);
-            lender_profile.total_lent += loan.amount;
-            lender_profile.active_loans.push(loan_id);
-            self.user_profiles.insert(caller, &lender_profile);
+            // Requests past their funding deadline can only be expired
+            if self.is_funding_deadline_passed(&loan) {
+                return Err(LendingError::InvalidStatus);
+            }
 
-            self.total_liquidity += loan.amount;
+            // Transfer funds from lender to contract
+            self.require_enabled_asset(loan.asset)?;
+            self.receive_asset(loan.asset, loan.amount)?;
 
-            self.env().emit_event(LoanFunded {
-                loan_id,
-                lender: caller,
-                amount: loan.amount,
-            });
+            let amount = loan.amount;
+            self.record_contribution(&mut loan, caller, amount)?;
+            self.activate_funded_loan(&mut loan)?;
 
             Ok(())
         }
 
-        /// Repay a loan in full
+        /// Contribute a tranche towards a pending loan; the loan activates once fully funded
+        #[ink(message, payable)]
+        pub fn contribute_to_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.require_native(loan.asset)?;
+            let contribution = self.env().transferred_value();
+            self.apply_contribution(loan, contribution)
+        }
+
+        /// Contribute an explicit amount towards a pending loan in any asset; PSP22 tranches are pulled from the caller's allowance
+        #[ink(message, payable)]
+        pub fn contribute_amount_to_loan(&mut self, loan_id: u64, amount: Balance) -> Result<(), LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.apply_contribution(loan, amount)
+        }
+
+        /// Get the lender contributions of a loan
         #[ink(message)]
-        pub fn repay_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
+        pub fn get_loan_contributions(&self, loan_id: u64) -> Result<Vec<LenderContribution>, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok(loan.lender_contributions)
+        }
+
+        /// Take a lender's tranche for a pending loan, activating it once fully funded
+        fn apply_contribution(&mut self, mut loan: Loan, contribution: Balance) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            
-            if loan.borrower != caller {
-                return Err(LendingError::Unauthorized);
-            }
+            let loan_id = loan.id;
             
-            if loan.status != LoanStatus::Active && loan.status != LoanStatus::PartiallyPaid && loan.status != LoanStatus::Overdue {
+            if !loan.status.allows(&LoanAction::Fund) {
                 return Err(LendingError::LoanNotActive);
             }
 
-            let repayment_amount = self.calculate_repayment_amount(loan_id)?;
-            
-            if self.env().transferred_value() != repayment_amount {
-                return Err(LendingError::InvalidAmount);
+            if caller == loan.borrower {
+                return Err(LendingError::Unauthorized);
             }
 
-            // Record the full payment
-            let current_block = self.env().block_number() as u64;
-            let full_payment = PartialPayment {
-                amount: repayment_amount,
-                timestamp: current_block,
-                payment_type: PaymentType::Full,
-            };
-
-            // Update loan payment tracking
-            loan.total_paid = repayment_amount;
-            loan.remaining_balance = 0;
-            loan.partial_payments.push(full_payment);
-            loan.status = LoanStatus::Repaid;
-
-            self.loans.insert(loan_id, &loan);
-
-            // Transfer repayment to lender
-            if let Some(lender) = loan.lender {
-                self.env().transfer(lender, repayment_amount)
-                    .map_err(|_| LendingError::TransferFailed)?;
+            if self.is_funding_deadline_passed(&loan) {
+                return Err(LendingError::InvalidStatus);
             }
 
-            // Update borrower profile
-            let mut borrower_profile = self.get_or_create_user_profile(caller);
-            borrower_profile.total_borrowed += loan.amount;
-            borrower_profile.active_loans.retain(|&id| id != loan_id);
-            self.user_profiles.insert(caller, &borrower_profile);
-
-            // Update lender profile
-            if let Some(lender) = loan.lender {
-                let mut lender_profile = self.get_or_create_user_profile(lender);
-                lender_profile.active_loans.retain(|&id| id != loan_id);
-                self.user_profiles.insert(lender, &lender_profile);
+            if contribution == 0 || contribution > loan.amount.safe_sub(loan.funded_amount)? {
+                return Err(LendingError::ContributionExceedsRequest);
             }
 
-            self.total_liquidity -= loan.amount;
+            self.require_enabled_asset(loan.asset)?;
+            self.receive_asset(loan.asset, contribution)?;
+            self.record_contribution(&mut loan, caller, contribution)?;
 
-            self.env().emit_event(LoanRepaid {
+            self.env().emit_event(LoanContributionReceived {
                 loan_id,
-                borrower: caller,
-                amount: repayment_amount,
+                lender: caller,
+                amount: contribution,
+                funded_amount: loan.funded_amount,
             });
 
+            if loan.funded_amount == loan.amount {
+                self.activate_funded_loan(&mut loan)?;
+            } else {
+                self.loans.insert(loan_id, &loan);
+            }
+
             Ok(())
         }
 
-        // ============================================================================
-        // ADVANCED LENDING OPERATIONS
-        // ============================================================================
-        
-        /// Repay a loan early with discount
+        /// Cancel a pending loan request and refund the escrowed collateral
         #[ink(message)]
-        pub fn early_repay_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
+        pub fn cancel_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
             let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
             if loan.borrower != caller {
                 return Err(LendingError::Unauthorized);
             }
-            
-            if loan.status != LoanStatus::Active {
-                return Err(LendingError::LoanNotActive);
-            }
-
-            let current_block = self.env().block_number() as u64;
-            if current_block >= loan.due_date {
-                return Err(LendingError::LoanNotActive); // Loan is already due, use regular repayment
-            }
 
-            // Calculate early repayment discount
-            let blocks_early = loan.due_date - current_block;
-            let discount_percentage = self.calculate_early_repayment_discount(blocks_early, loan.duration);
-            
-            let original_repayment = self.calculate_repayment_amount(loan_id)?;
-            let discount_amount = (original_repayment * discount_percentage as u128) / 10000;
-            let discounted_repayment = original_repayment - discount_amount;
-            
-            if self.env().transferred_value() != discounted_repayment {
-                return Err(LendingError::InvalidAmount);
+            if !loan.status.allows(&LoanAction::Cancel) {
+                return Err(LendingError::InvalidStatus);
             }
 
-            // Record the early payment
-            let current_block = self.env().block_number() as u64;
-            let early_payment = PartialPayment {
-                amount: discounted_repayment,
-                timestamp: current_block,
-                payment_type: PaymentType::Early,
-            };
-
-            // Update loan payment tracking
-            loan.total_paid = discounted_repayment;
-            loan.remaining_balance = 0;
-            loan.partial_payments.push(early_payment);
-            loan.status = LoanStatus::EarlyRepaid;
-
+            self.transition_loan(&mut loan, LoanStatus::Cancelled, LoanAction::Cancel)?;
+            self.refund_contributions(&mut loan)?;
             self.loans.insert(loan_id, &loan);
+            self.release_unsecured_exposure(&loan)?;
 
-            // Transfer discounted repayment to lender
-            if let Some(lender) = loan.lender {
-                self.env().transfer(lender, discounted_repayment)
-                    .map_err(|_| LendingError::TransferFailed)?;
-            }
-
-            // Update borrower profile
-            let mut borrower_profile = self.get_or_create_user_profile(caller);
-            borrower_profile.total_borrowed += loan.amount;
-            borrower_profile.active_loans.retain(|&id| id != loan_id);
-            self.user_profiles.insert(caller, &borrower_profile);
-
-            // Update lender profile
-            if let Some(lender) = loan.lender {
-                let mut lender_profile = self.get_or_create_user_profile(lender);
-                lender_profile.active_loans.retain(|&id| id != loan_id);
-                self.user_profiles.insert(lender, &lender_profile);
-            }
-
-            self.total_liquidity -= loan.amount;
+            self.remove_from_active_loans(&loan);
+            let collateral_refunded = self.release_collateral(&loan, loan.borrower)?;
 
-            self.env().emit_event(LoanEarlyRepaid {
+            self.env().emit_event(LoanCancelled {
                 loan_id,
-                borrower: caller,
-                original_amount: original_repayment,
-                discounted_amount: discounted_repayment,
-                discount_applied: discount_amount,
-                blocks_early,
+                borrower: loan.borrower,
+                collateral_refunded,
             });
 
             Ok(())
         }
 
-        /// Make a partial payment on a loan
+        /// Expire a pending loan request whose funding deadline has passed (callable by anyone)
         #[ink(message)]
-        pub fn partial_repay_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
+        pub fn expire_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
             let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            if loan.borrower != caller {
-                return Err(LendingError::Unauthorized);
-            }
-            
-            if loan.status != LoanStatus::Active && loan.status != LoanStatus::PartiallyPaid && loan.status != LoanStatus::Overdue {
-                return Err(LendingError::LoanNotActive);
-            }
-
-            let payment_amount = self.env().transferred_value();
-            if payment_amount == 0 {
-                return Err(LendingError::InvalidAmount);
-            }
-
-            if payment_amount >= loan.remaining_balance {
-                return Err(LendingError::InvalidAmount); // Use full repayment for full amounts
-            }
-
-            // Apply late fees if loan is overdue
-            if loan.status == LoanStatus::Overdue {
-                let current_block = self.env().block_number() as u64;
-                let grace_period_end = loan.due_date + loan.grace_period;
-                let overdue_blocks = current_block - grace_period_end;
-                let days_overdue = overdue_blocks / 14400;
-                let late_fee_rate = (loan.late_fee_rate * days_overdue as u16).min(loan.max_late_fee_rate);
-                let late_fees = (loan.remaining_balance * late_fee_rate as u128) / 10000;
-                
-                if late_fees > 0 {
-                    loan.total_late_fees += late_fees;
-                    loan.remaining_balance += late_fees;
-                }
+            if !loan.status.allows(&LoanAction::Expire) {
+                return Err(LendingError::InvalidStatus);
             }
 
-            // Record the partial payment
-            let current_block = self.env().block_number() as u64;
-            let partial_payment = PartialPayment {
-                amount: payment_amount,
-                timestamp: current_block,
-                payment_type: PaymentType::Partial,
-            };
-
-            // Update loan payment tracking
-            loan.total_paid += payment_amount;
-            loan.remaining_balance -= payment_amount;
-            loan.partial_payments.push(partial_payment);
-            
-            // Update loan status
-            if loan.remaining_balance > 0 {
-                if loan.status == LoanStatus::Overdue {
-                    loan.status = LoanStatus::Overdue; // Keep overdue status
-                } else {
-                    loan.status = LoanStatus::PartiallyPaid;
-                }
-            } else {
-                loan.status = LoanStatus::Repaid;
+            if !self.is_funding_deadline_passed(&loan) {
+                return Err(LendingError::FundingDeadlineNotReached);
             }
 
+            self.transition_loan(&mut loan, LoanStatus::Expired, LoanAction::Expire)?;
+            self.refund_contributions(&mut loan)?;
             self.loans.insert(loan_id, &loan);
+            self.release_unsecured_exposure(&loan)?;
 
-            // Transfer payment to lender
-            if let Some(lender) = loan.lender {
-                self.env().transfer(lender, payment_amount)
-                    .map_err(|_| LendingError::TransferFailed)?;
-            }
-
-            // Update borrower profile if loan is fully repaid
-            if loan.remaining_balance == 0 {
-                let mut borrower_profile = self.get_or_create_user_profile(caller);
-                borrower_profile.total_borrowed += loan.amount;
-                borrower_profile.active_loans.retain(|&id| id != loan_id);
-                self.user_profiles.insert(caller, &borrower_profile);
-
-                // Update lender profile
-                if let Some(lender) = loan.lender {
-                    let mut lender_profile = self.get_or_create_user_profile(lender);
-                    lender_profile.active_loans.retain(|&id| id != loan_id);
-                    self.user_profiles.insert(lender, &lender_profile);
-                }
-
-                self.total_liquidity -= loan.amount;
-            }
+            self.remove_from_active_loans(&loan);
+            let collateral_refunded = self.release_collateral(&loan, loan.borrower)?;
 
-            self.env().emit_event(LoanPartiallyPaid {
+            self.env().emit_event(LoanExpired {
                 loan_id,
-                borrower: caller,
-                payment_amount,
-                remaining_balance: loan.remaining_balance,
-                total_paid: loan.total_paid,
+                borrower: loan.borrower,
+                expired_by: caller,
+                collateral_refunded,
             });
 
             Ok(())
         }
 
-        /// Extend a loan's duration
+        /// Set or clear the funding deadline of a pending loan request (borrower only)
         #[ink(message)]
-        pub fn extend_loan(&mut self, loan_id: u64, extension_duration: u64) -> Result<(), LendingError> {
+        pub fn set_funding_deadline(&mut self, loan_id: u64, funding_deadline: Option<u64>) -> Result<(), LendingError> {
             let caller = self.env().caller();
             let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
             if loan.borrower != caller {
                 return Err(LendingError::Unauthorized);
             }
-            
-            if loan.status != LoanStatus::Active && loan.status != LoanStatus::PartiallyPaid {
-                return Err(LendingError::LoanNotActive);
+
+            if !loan.status.allows(&LoanAction::SetFundingDeadline) {
+                return Err(LendingError::InvalidStatus);
+            }
+
+            if let Some(deadline) = funding_deadline {
+                if deadline <= self.now() {
+                    return Err(LendingError::InvalidDuration);
+                }
+            }
+
+            loan.funding_deadline = funding_deadline;
+            self.loans.insert(loan_id, &loan);
+
+            Ok(())
+        }
+
+        /// Repay a loan in full
+        #[ink(message, payable)]
+        pub fn repay_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            if loan.borrower != caller {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            self.apply_full_repayment(loan, caller)
+        }
+
+        // ============================================================================
+        // ADVANCED LENDING OPERATIONS
+        // ============================================================================
+        
+        /// Repay a loan early with discount
+        #[ink(message, payable)]
+        pub fn early_repay_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            if loan.borrower != caller {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            self.apply_early_repayment(loan, caller)
+        }
+
+        /// Make a partial payment on a loan
+        #[ink(message, payable)]
+        pub fn partial_repay_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            if loan.borrower != caller {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            self.require_native(loan.asset)?;
+            let payment_amount = self.env().transferred_value();
+            self.apply_partial_payment(loan, caller, payment_amount)
+        }
+
+        /// Repay part or all of a loan on the borrower's behalf (employers, DAOs, guarantors)
+        #[ink(message, payable)]
+        pub fn repay_on_behalf(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.require_native(loan.asset)?;
+            let payment_amount = self.env().transferred_value();
+            self.apply_partial_payment(loan, caller, payment_amount)
+        }
+
+        /// Repay a loan in full on the borrower's behalf; collateral still returns to the borrower
+        #[ink(message, payable)]
+        pub fn repay_loan_on_behalf(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.apply_full_repayment(loan, caller)
+        }
+
+        /// Repay a loan early with discount on the borrower's behalf
+        #[ink(message, payable)]
+        pub fn early_repay_on_behalf(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.apply_early_repayment(loan, caller)
+        }
+
+        /// Repay an explicit amount of a loan in any asset, for the borrower or on their behalf; PSP22 payments are pulled from the caller's allowance
+        #[ink(message, payable)]
+        pub fn repay_loan_amount(&mut self, loan_id: u64, amount: Balance) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.apply_partial_payment(loan, caller, amount)
+        }
+
+        /// Extend a loan's duration
+        #[ink(message, payable)]
+        pub fn extend_loan(&mut self, loan_id: u64, extension_duration: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            if loan.borrower != caller {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if !loan.status.allows(&LoanAction::Extend) {
+                return Err(LendingError::LoanNotActive);
             }
 
             // Check if loan can still be extended
             if loan.extension_count >= loan.max_extensions {
-                return Err(LendingError::InvalidAmount); // Reuse error for max extensions reached
+                return Err(LendingError::MaxExtensionsReached);
             }
 
             // Validate extension duration
-            if extension_duration == 0 || extension_duration > 100000 { // Max ~1 year extension
+            if extension_duration == 0 || extension_duration > MILLIS_PER_YEAR { // Max 1 year extension
                 return Err(LendingError::InvalidDuration);
             }
 
-            let current_block = self.env().block_number() as u64;
-            if current_block >= loan.due_date {
-                return Err(LendingError::LoanNotActive); // Loan is already due
+            let current_time = self.now();
+            if current_time >= loan.due_date {
+                return Err(LendingError::LoanAlreadyDue);
             }
 
+            self.accrue_loan_interest(&mut loan)?;
+
             // Calculate extension fee
-            let extension_fee = (loan.remaining_balance * loan.extension_fee_rate as u128) / 10000;
+            let extension_fee = bps_mul(loan.remaining_balance, loan.extension_fee_rate as u128, Rounding::Up)?;
             
             // Check if extension fee is paid
-            if self.env().transferred_value() != extension_fee {
-                return Err(LendingError::InvalidAmount);
-            }
+            self.receive_asset(loan.asset, extension_fee)?;
 
             // Update loan extension details
             let old_due_date = loan.due_date;
-            loan.due_date += extension_duration;
-            loan.extension_count += 1;
+            loan.due_date = loan.due_date.safe_add(extension_duration)?;
+            loan.extension_count = loan.extension_count.safe_add(1)?;
 
-            // Update remaining balance to include extension fee
-            loan.remaining_balance += extension_fee;
+            // Final installment moves with the due date; the fee is paid up front so it
+            // is owed neither on the balance nor on the schedule
+            if let Some(last) = loan.amortization_schedule.last_mut() {
+                last.due_date = loan.due_date;
+            }
 
             self.loans.insert(loan_id, &loan);
 
-            // Transfer extension fee to lender
-            if let Some(lender) = loan.lender {
-                self.env().transfer(lender, extension_fee)
-                    .map_err(|_| LendingError::TransferFailed)?;
-            }
+            // Transfer extension fee to lenders pro-rata, net of the protocol fee
+            let protocol_fee = self.collect_protocol_fee(loan.asset, extension_fee, FeeSource::Extension)?;
+            self.distribute_to_lenders(&loan, extension_fee.safe_sub(protocol_fee)?)?;
 
             self.env().emit_event(LoanExtended {
                 loan_id,
@@ -1374,55 +1741,57 @@ pub mod lending_contract {
         pub fn apply_late_fees(&mut self, loan_id: u64) -> Result<(), LendingError> {
             let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            if loan.status != LoanStatus::Active && loan.status != LoanStatus::PartiallyPaid {
+            if !loan.status.allows(&LoanAction::ApplyLateFees) {
                 return Err(LendingError::LoanNotActive);
             }
 
-            let current_block = self.env().block_number() as u64;
-            let grace_period_end = loan.due_date + loan.grace_period;
+            self.accrue_loan_interest(&mut loan)?;
+            
+            let current_time = self.now();
+            let grace_period_end = loan.due_date.safe_add(loan.grace_period)?;
+
+            // Flag any installments that are past due
+            let newly_missed = self.mark_missed_installments(&mut loan, current_time)?;
             
             // Check if loan is overdue and grace period has ended
-            if current_block <= grace_period_end {
-                return Err(LendingError::InvalidAmount); // Reuse error for not overdue yet
+            if current_time <= grace_period_end {
+                if newly_missed > 0 {
+                    self.loans.insert(loan_id, &loan);
+                    return Ok(());
+                }
+                return Err(LendingError::LoanNotOverdue);
             }
 
-            // Calculate overdue blocks
-            let overdue_blocks = current_block - grace_period_end;
+            // Calculate overdue time
+            let overdue_ms = current_time.safe_sub(grace_period_end)?;
             
-            // Calculate late fees (daily compounding)
-            let days_overdue = overdue_blocks / 14400; // Assuming 14400 blocks per day (6s blocks)
-            let late_fee_rate = (loan.late_fee_rate * days_overdue as u16).min(loan.max_late_fee_rate);
-            
-            let late_fees = (loan.remaining_balance * late_fee_rate as u128) / 10000;
+            // Charge late fees for the days overdue so far
+            let late_fees = self.accrue_late_fees(&mut loan)?;
             
             if late_fees > 0 {
-                // Update loan with late fees
-                loan.total_late_fees += late_fees;
-                loan.remaining_balance += late_fees;
-                
-                // Set overdue status if not already set
-                if loan.status == LoanStatus::Active {
-                    loan.status = LoanStatus::Overdue;
-                    loan.overdue_since = Some(grace_period_end);
-                }
+                // Late fees mark the loan overdue
+                self.transition_loan(&mut loan, LoanStatus::Overdue, LoanAction::ApplyLateFees)?;
+                loan.overdue_since = Some(grace_period_end);
                 
                 self.loans.insert(loan_id, &loan);
 
                 self.env().emit_event(LateFeesAccumulated {
                     loan_id,
                     borrower: loan.borrower,
-                    overdue_blocks,
+                    overdue_ms,
                     late_fees_added: late_fees,
                     total_late_fees: loan.total_late_fees,
                     new_remaining_balance: loan.remaining_balance,
                 });
+            } else if newly_missed > 0 {
+                self.loans.insert(loan_id, &loan);
             }
 
             Ok(())
         }
 
         /// Refinance a loan with better terms
-        #[ink(message)]
+        #[ink(message, payable)]
         pub fn refinance_loan(&mut self, loan_id: u64, new_interest_rate: u16, new_duration: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
             let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
@@ -1431,13 +1800,13 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            if loan.status != LoanStatus::Active && loan.status != LoanStatus::PartiallyPaid {
+            if !loan.status.allows(&LoanAction::Refinance) {
                 return Err(LendingError::LoanNotActive);
             }
 
             // Check if loan can still be refinanced
             if loan.refinance_count >= loan.max_refinances {
-                return Err(LendingError::InvalidAmount); // Reuse error for max refinances reached
+                return Err(LendingError::MaxRefinancesReached);
             }
 
             // Validate new terms
@@ -1445,33 +1814,35 @@ pub mod lending_contract {
                 return Err(LendingError::InvalidInterestRate);
             }
 
-            if new_duration == 0 || new_duration > 1000000 {
+            if new_duration == 0 || new_duration > MILLIS_PER_YEAR {
                 return Err(LendingError::InvalidDuration);
             }
 
             // Check if new terms are actually better
             if new_interest_rate >= loan.interest_rate {
-                return Err(LendingError::InvalidInterestRate); // Reuse error for worse terms
+                return Err(LendingError::TermsNotImproved);
             }
 
-            let current_block = self.env().block_number() as u64;
-            if current_block >= loan.due_date {
-                return Err(LendingError::LoanNotActive); // Loan is already due
+            let current_time = self.now();
+            if current_time >= loan.due_date {
+                return Err(LendingError::LoanAlreadyDue);
             }
 
+            // Interest up to now is owed at the old rate
+            self.accrue_loan_interest(&mut loan)?;
+
             // Calculate refinance fee
-            let refinance_fee = (loan.remaining_balance * loan.refinance_fee_rate as u128) / 10000;
+            let refinance_fee = bps_mul(loan.remaining_balance, loan.refinance_fee_rate as u128, Rounding::Up)?;
             
             // Check if refinance fee is paid
-            if self.env().transferred_value() != refinance_fee {
-                return Err(LendingError::InvalidAmount);
-            }
+            self.receive_asset(loan.asset, refinance_fee)?;
 
-            // Record refinancing operation
+            // Record refinancing operation; terms are renegotiated with the existing lender
+            let lender = loan.lender.unwrap_or(AccountId::from([0; 32]));
             let refinance_record = RefinanceRecord {
-                timestamp: current_block,
-                old_lender: loan.lender.unwrap_or(AccountId::from([0; 32])),
-                new_lender: caller, // New lender is the caller
+                timestamp: current_time,
+                old_lender: lender,
+                new_lender: lender,
                 old_interest_rate: loan.interest_rate,
                 new_interest_rate,
                 refinance_fee,
@@ -1482,29 +1853,26 @@ pub mod lending_contract {
             let old_interest_rate = loan.interest_rate;
             loan.interest_rate = new_interest_rate;
             loan.duration = new_duration;
-            loan.due_date = current_block + new_duration;
-            loan.refinance_count += 1;
+            loan.due_date = current_time.safe_add(new_duration)?;
+            loan.refinance_count = loan.refinance_count.safe_add(1)?;
             loan.refinance_history.push(refinance_record.clone());
-            loan.status = LoanStatus::Refinanced;
 
-            // Recalculate remaining balance with new interest rate
-            let new_total_repayment = loan.amount + ((loan.amount * new_interest_rate as u128) / 10000);
-            // For refinancing, just set the new remaining balance
-            loan.remaining_balance = new_total_repayment;
+            // The new rate accrues on the outstanding principal from now on
+            let outstanding_principal = loan.amount.safe_sub(loan.principal_repaid)?;
+            let projected_interest = bps_mul(outstanding_principal, new_interest_rate as u128, Rounding::Up)?;
+            self.generate_amortization_schedule(&mut loan, current_time, outstanding_principal, projected_interest)?;
 
             self.loans.insert(loan_id, &loan);
 
-            // Transfer refinance fee to old lender
-            if let Some(old_lender) = loan.lender {
-                self.env().transfer(old_lender, refinance_fee)
-                    .map_err(|_| LendingError::TransferFailed)?;
-            }
+            // Transfer refinance fee to lenders pro-rata, net of the protocol fee
+            let protocol_fee = self.collect_protocol_fee(loan.asset, refinance_fee, FeeSource::Refinance)?;
+            self.distribute_to_lenders(&loan, refinance_fee.safe_sub(protocol_fee)?)?;
 
             self.env().emit_event(LoanRefinanced {
                 loan_id,
                 borrower: caller,
                 old_lender: refinance_record.old_lender,
-                new_lender: caller,
+                new_lender: refinance_record.new_lender,
                 old_interest_rate,
                 new_interest_rate,
                 refinance_fee,
@@ -1516,1171 +1884,5275 @@ pub mod lending_contract {
         }
 
         // ============================================================================
-        // VARIABLE INTEREST RATE MANAGEMENT
+        // THIRD-PARTY REFINANCING
         // ============================================================================
         
-        /// Adjust interest rate for a variable rate loan
-        #[ink(message)]
-        pub fn adjust_interest_rate(
+        /// Offer to refinance a loan on better terms, escrowing up to `max_payoff` of its balance
+        #[ink(message, payable)]
+        pub fn propose_refinance(
             &mut self,
             loan_id: u64,
-            new_base_rate: u16,
-            reason: RateAdjustmentReason,
-        ) -> Result<(), LendingError> {
+            new_interest_rate: u16,
+            new_duration: u64,
+            max_payoff: Balance,
+        ) -> Result<u64, LendingError> {
             let caller = self.env().caller();
-            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            // Only lender can adjust interest rates
-            if loan.lender != Some(caller) {
-                return Err(LendingError::Unauthorized);
+            if !loan.status.allows(&LoanAction::ThirdPartyRefinance) {
+                return Err(LendingError::LoanNotActive);
             }
             
-            // Check if loan supports variable rates
-            if loan.interest_rate_type != InterestRateType::Variable {
-                return Err(LendingError::InvalidAmount); // Reuse error for fixed rate loan
+            if caller == loan.borrower || loan.lender == Some(caller) {
+                return Err(LendingError::Unauthorized);
             }
-
-            // Validate new rate
-            if new_base_rate == 0 || new_base_rate > 10000 {
-                return Err(LendingError::InvalidInterestRate);
+            
+            if new_interest_rate == 0 || new_interest_rate >= loan.interest_rate {
+                return Err(LendingError::TermsNotImproved);
             }
-
-            let current_block = self.env().block_number() as u64;
             
-            // Check update frequency
-            if current_block < loan.last_interest_update + loan.interest_update_frequency {
-                return Err(LendingError::InvalidAmount); // Reuse error for too frequent updates
+            if new_duration == 0 || new_duration > MILLIS_PER_YEAR {
+                return Err(LendingError::InvalidDuration);
             }
-
-            let old_rate = loan.interest_rate;
-            let _old_base_rate = loan.base_interest_rate;
             
-            // Calculate new effective rate with risk multiplier
-            let new_effective_rate = ((new_base_rate as u32 * loan.risk_multiplier as u32) / 1000) as u16;
+            // The escrow must cover the balance as it stands now; interest accrued before
+            // acceptance is paid from the headroom and the rest is refunded
+            if max_payoff < self.current_debt(&loan)? {
+                return Err(LendingError::InvalidAmount);
+            }
+            self.receive_asset(loan.asset, max_payoff)?;
             
-            // Record the adjustment
-            let adjustment = InterestRateAdjustment {
-                timestamp: current_block,
-                old_rate: old_rate,
-                new_rate: new_effective_rate,
-                reason: reason.clone(),
-                risk_score_change: None, // Will be updated if risk score changes
+            let proposal_id = self.total_refinance_proposals.safe_add(1)?;
+            let proposal = RefinanceProposal {
+                id: proposal_id,
+                loan_id,
+                new_lender: caller,
+                new_interest_rate,
+                new_duration,
+                max_payoff,
+                created_at: self.now(),
+                status: RefinanceProposalStatus::Open,
             };
-
-            // Update loan with new rates
-            loan.base_interest_rate = new_base_rate;
-            loan.interest_rate = new_effective_rate;
-            loan.interest_rate_adjustments.push(adjustment);
-            loan.last_interest_update = current_block;
-
-            // Recalculate remaining balance with new interest rate
-            if loan.status == LoanStatus::Active || loan.status == LoanStatus::PartiallyPaid {
-                let new_total_repayment = loan.amount + ((loan.amount * new_effective_rate as u128) / 10000);
-                let principal_paid = loan.amount - (loan.remaining_balance - loan.total_late_fees);
-                let new_remaining_balance = new_total_repayment - principal_paid;
-                loan.remaining_balance = new_remaining_balance;
-            }
-
-            self.loans.insert(loan_id, &loan);
-
-            self.env().emit_event(InterestRateAdjusted {
+            
+            self.refinance_proposals.insert(proposal_id, &proposal);
+            self.total_refinance_proposals = proposal_id;
+            
+            self.env().emit_event(RefinanceProposed {
+                proposal_id,
                 loan_id,
-                borrower: loan.borrower,
-                old_rate,
-                new_rate: new_effective_rate,
-                reason,
-                risk_multiplier: loan.risk_multiplier,
-                effective_rate: new_effective_rate,
+                new_lender: caller,
+                new_interest_rate,
+                max_payoff,
             });
-
-            Ok(())
+            
+            Ok(proposal_id)
         }
 
-        /// Update risk multiplier for a loan
+        /// Accept a refinance proposal: the old lenders are paid off and a new loan is opened with the new lender
         #[ink(message)]
-        pub fn update_risk_multiplier(
-            &mut self,
-            loan_id: u64,
-            new_risk_multiplier: u16,
-        ) -> Result<(), LendingError> {
+        pub fn accept_refinance(&mut self, proposal_id: u64) -> Result<u64, LendingError> {
             let caller = self.env().caller();
-            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let mut proposal = self.refinance_proposals.get(proposal_id).ok_or(LendingError::RefinanceProposalNotFound)?;
+            let mut loan = self.loans.get(proposal.loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            // Only lender can update risk multipliers
-            if loan.lender != Some(caller) {
+            if loan.borrower != caller {
                 return Err(LendingError::Unauthorized);
             }
             
-            // Validate risk multiplier (0.5x to 3.0x)
-            if new_risk_multiplier < 500 || new_risk_multiplier > 3000 {
-                return Err(LendingError::InvalidAmount); // Reuse error for invalid multiplier
+            if proposal.status != RefinanceProposalStatus::Open {
+                return Err(LendingError::InvalidStatus);
             }
-
-            let old_multiplier = loan.risk_multiplier;
-            let old_rate = loan.interest_rate;
             
-            // Calculate new effective rate
-            let new_effective_rate = ((loan.base_interest_rate as u32 * new_risk_multiplier as u32) / 1000) as u16;
+            if !loan.status.allows(&LoanAction::ThirdPartyRefinance) {
+                return Err(LendingError::LoanNotActive);
+            }
             
-            // Record the adjustment
-            let adjustment = InterestRateAdjustment {
-                timestamp: self.env().block_number() as u64,
-                old_rate: old_rate,
-                new_rate: new_effective_rate,
-                reason: RateAdjustmentReason::RiskScoreChange,
-                risk_score_change: Some((new_risk_multiplier as i16) - (old_multiplier as i16)),
-            };
-
-            // Update loan
-            loan.risk_multiplier = new_risk_multiplier;
-            loan.interest_rate = new_effective_rate;
-            loan.interest_rate_adjustments.push(adjustment);
-            loan.last_interest_update = self.env().block_number() as u64;
-
-            // Recalculate remaining balance if loan is active
-            if loan.status == LoanStatus::Active || loan.status == LoanStatus::PartiallyPaid {
-                let new_total_repayment = loan.amount + ((loan.amount * new_effective_rate as u128) / 10000);
-                // For risk multiplier updates, just set the new remaining balance
-                loan.remaining_balance = new_total_repayment;
+            if loan.refinance_count >= loan.max_refinances {
+                return Err(LendingError::MaxRefinancesReached);
             }
-
-            self.loans.insert(loan_id, &loan);
-
-            self.env().emit_event(InterestRateAdjusted {
-                loan_id,
+            
+            // The debt is settled as it stands now; a proposal is stale once its escrow no longer covers it
+            self.accrue_loan_interest(&mut loan)?;
+            let payoff_amount = loan.remaining_balance;
+            if payoff_amount > proposal.max_payoff {
+                return Err(LendingError::RefinanceProposalStale);
+            }
+            
+            // Pay off the old loan
+            let current_time = self.now();
+            let old_lender = loan.lender.unwrap_or(AccountId::from([0; 32]));
+            let refinance_record = RefinanceRecord {
+                timestamp: current_time,
+                old_lender,
+                new_lender: proposal.new_lender,
+                old_interest_rate: loan.interest_rate,
+                new_interest_rate: proposal.new_interest_rate,
+                refinance_fee: 0,
+                remaining_balance: payoff_amount,
+            };
+            
+            let (late_fees_paid, interest_paid, principal_paid) = self.allocate_payment(&mut loan, payoff_amount)?;
+            loan.total_paid = loan.total_paid.safe_add(payoff_amount)?;
+            loan.remaining_balance = 0;
+            loan.partial_payments.push(PartialPayment {
+                amount: payoff_amount,
+                timestamp: current_time,
+                payment_type: PaymentType::Full,
+                payer: proposal.new_lender,
+                late_fees_paid,
+                interest_paid,
+                principal_paid,
+            });
+            self.settle_amortization_schedule(&mut loan, current_time)?;
+            loan.refinance_history.push(refinance_record);
+            self.transition_loan(&mut loan, LoanStatus::Refinanced, LoanAction::ThirdPartyRefinance)?;
+            self.loans.insert(loan.id, &loan);
+            
+            // Old lenders are paid off net of the protocol's share of the interest
+            let protocol_fee = self.collect_protocol_fee(loan.asset, interest_paid, FeeSource::Interest)?;
+            self.distribute_to_lenders(&loan, payoff_amount.safe_sub(protocol_fee)?)?;
+            self.remove_from_active_loans(&loan);
+            let unsecured = self.get_unsecured_principal(loan.id);
+            self.release_loan_liquidity(&loan)?;
+            
+            // Open the new loan for the outstanding balance, carrying over collateral and history
+            let new_loan_id = self.total_loans.safe_add(1)?;
+            let mut new_loan = self.build_loan(
+                new_loan_id,
+                loan.borrower,
+                payoff_amount,
+                proposal.new_interest_rate,
+                proposal.new_duration,
+                loan.collateral,
+            )?;
+            new_loan.asset = loan.asset;
+            new_loan.collateral_asset = loan.collateral_asset;
+            new_loan.original_loan_id = Some(loan.id);
+            new_loan.refinance_count = loan.refinance_count.safe_add(1)?;
+            new_loan.max_refinances = loan.max_refinances;
+            new_loan.refinance_history = loan.refinance_history.clone();
+            new_loan.collateral_requirements = loan.collateral_requirements.clone();
+            self.total_loans = new_loan_id;
+            
+            let collateral = self.escrowed_collateral.get(loan.id).unwrap_or(0);
+            self.escrowed_collateral.remove(loan.id);
+            self.escrowed_collateral.insert(new_loan_id, &collateral);
+            if let Some(basket) = self.collateral_baskets.get(loan.id) {
+                self.collateral_baskets.remove(loan.id);
+                self.collateral_baskets.insert(new_loan_id, &basket);
+            }
+            // The new loan keeps the exposure the old one was admitted with, even if the cap has since been lowered
+            self.record_unsecured_exposure(new_loan_id, loan.asset, unsecured.min(payoff_amount))?;
+            
+            self.record_contribution(&mut new_loan, proposal.new_lender, payoff_amount)?;
+            self.activate_funded_loan(&mut new_loan)?;
+            
+            // Escrow not needed for the payoff goes back to the new lender
+            let refund = proposal.max_payoff.safe_sub(payoff_amount)?;
+            if refund > 0 {
+                self.send_asset(loan.asset, proposal.new_lender, refund)?;
+            }
+            
+            let mut borrower_profile = self.get_or_create_user_profile(loan.borrower);
+            borrower_profile.active_loans.push(new_loan_id);
+            self.user_profiles.insert(loan.borrower, &borrower_profile);
+            
+            proposal.status = RefinanceProposalStatus::Accepted;
+            self.refinance_proposals.insert(proposal_id, &proposal);
+            
+            self.env().emit_event(LoanRefinancedByLender {
+                old_loan_id: loan.id,
+                new_loan_id,
                 borrower: loan.borrower,
-                old_rate,
-                new_rate: new_effective_rate,
-                reason: RateAdjustmentReason::RiskScoreChange,
-                risk_multiplier: new_risk_multiplier,
-                effective_rate: new_effective_rate,
+                old_lender,
+                new_lender: proposal.new_lender,
+                payoff_amount,
+                new_interest_rate: proposal.new_interest_rate,
             });
-
-            Ok(())
+            
+            Ok(new_loan_id)
         }
 
-        /// Convert a fixed rate loan to variable rate
+        /// Withdraw an open refinance proposal and refund its escrowed funds (proposer only)
         #[ink(message)]
-        pub fn convert_to_variable_rate(
-            &mut self,
-            loan_id: u64,
-            new_base_rate: u16,
-        ) -> Result<(), LendingError> {
+        pub fn withdraw_refinance_proposal(&mut self, proposal_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let mut proposal = self.refinance_proposals.get(proposal_id).ok_or(LendingError::RefinanceProposalNotFound)?;
             
-            // Only lender can convert loan types
-            if loan.lender != Some(caller) {
+            if proposal.new_lender != caller {
                 return Err(LendingError::Unauthorized);
             }
             
-            // Check if loan is already variable
-            if loan.interest_rate_type == InterestRateType::Variable {
-                return Err(LendingError::InvalidAmount); // Reuse error for already variable
-            }
-
-            // Validate new base rate
-            if new_base_rate == 0 || new_base_rate > 10000 {
-                return Err(LendingError::InvalidInterestRate);
+            if proposal.status != RefinanceProposalStatus::Open {
+                return Err(LendingError::InvalidStatus);
             }
-
-            let old_rate = loan.interest_rate;
-            let new_effective_rate = ((new_base_rate as u32 * loan.risk_multiplier as u32) / 1000) as u16;
             
-            // Record the conversion
-            let adjustment = InterestRateAdjustment {
-                timestamp: self.env().block_number() as u64,
-                old_rate: old_rate,
-                new_rate: new_effective_rate,
-                reason: RateAdjustmentReason::ManualAdjustment,
-                risk_score_change: None,
-            };
+            proposal.status = RefinanceProposalStatus::Withdrawn;
+            self.refinance_proposals.insert(proposal_id, &proposal);
+            
+            let loan = self.loans.get(proposal.loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.send_asset(loan.asset, caller, proposal.max_payoff)?;
+            
+            Ok(())
+        }
 
-            // Update loan
-            loan.interest_rate_type = InterestRateType::Variable;
-            loan.base_interest_rate = new_base_rate;
-            loan.interest_rate = new_effective_rate;
-            loan.interest_rate_adjustments.push(adjustment);
-            loan.last_interest_update = self.env().block_number() as u64;
-
-            // Recalculate remaining balance
-            if loan.status == LoanStatus::Active || loan.status == LoanStatus::PartiallyPaid {
-                let new_total_repayment = loan.amount + ((loan.amount * new_effective_rate as u128) / 10000);
-                // For conversion, just set the new remaining balance
-                loan.remaining_balance = new_total_repayment;
-            }
-
-            self.loans.insert(loan_id, &loan);
-
-            self.env().emit_event(InterestRateAdjusted {
-                loan_id,
-                borrower: loan.borrower,
-                old_rate,
-                new_rate: new_effective_rate,
-                reason: RateAdjustmentReason::ManualAdjustment,
-                risk_multiplier: loan.risk_multiplier,
-                effective_rate: new_effective_rate,
-            });
-
-            Ok(())
-        }
+        /// Get refinance proposal details
+        #[ink(message)]
+        pub fn get_refinance_proposal(&self, proposal_id: u64) -> Option<RefinanceProposal> {
+            self.refinance_proposals.get(proposal_id)
+        }
 
         // ============================================================================
-        // COMPOUND INTEREST CALCULATION
+        // LENDER POSITION TRANSFERS
         // ============================================================================
         
-        /// Calculate and apply compound interest for a loan
+        /// Transfer the caller's lender position, including future repayment rights, to a new lender
         #[ink(message)]
-        pub fn compound_interest(&mut self, loan_id: u64) -> Result<(), LendingError> {
+        pub fn transfer_lender_position(&mut self, loan_id: u64, new_lender: AccountId) -> Result<(), LendingError> {
             let caller = self.env().caller();
             let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            // Only lender or borrower can compound interest
-            if loan.lender != Some(caller) && loan.borrower != caller {
-                return Err(LendingError::Unauthorized);
-            }
+            self.move_lender_position(&mut loan, caller, new_lender)?;
             
-            // Check if loan supports compound interest
-            if loan.interest_type != InterestType::Compound {
-                return Err(LendingError::InvalidAmount); // Reuse error for simple interest loan
+            // A direct transfer withdraws any open listing by the same seller
+            if self.loan_listings.get(loan_id).is_some_and(|listing| listing.seller == caller) {
+                self.loan_listings.remove(loan_id);
             }
             
-            // Check if it's time to compound
-            let current_block = self.env().block_number() as u64;
-            if current_block < loan.last_compound_date + loan.compound_period_blocks {
-                return Err(LendingError::InvalidAmount); // Reuse error for too early to compound
-            }
+            Ok(())
+        }
+
+        /// List the caller's lender position for sale at a fixed price
+        #[ink(message)]
+        pub fn list_loan_for_sale(&mut self, loan_id: u64, price: Balance) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            // Calculate compound interest
-            let periods_since_last_compound = (current_block - loan.last_compound_date) / loan.compound_period_blocks;
-            if periods_since_last_compound == 0 {
-                return Err(LendingError::InvalidAmount); // Reuse error for no periods to compound
+            if !self.is_position_transferable(&loan) {
+                return Err(LendingError::LoanNotActive);
             }
             
-            // Calculate the new balance with compound interest
-            let principal = loan.amount;
-            let rate_per_period = loan.interest_rate as f64 / 10000.0; // Convert basis points to decimal
-            let periods = periods_since_last_compound as f64;
-            
-            // Compound interest formula: A = P(1 + r)^n
-            let compound_factor = (1.0 + rate_per_period).powf(periods);
-            let new_total = (principal as f64 * compound_factor) as u128;
+            if self.lender_principal(&loan, caller) == 0 {
+                return Err(LendingError::Unauthorized);
+            }
             
-            // Calculate interest accrued
-            let interest_accrued = new_total - principal;
+            if price == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
             
-            // Update loan with compound interest
-            let _old_remaining_balance = loan.remaining_balance;
-            loan.remaining_balance = new_total;
-            loan.accrued_interest = 0; // Reset accrued interest
-            loan.total_compounded_interest += interest_accrued;
-            loan.last_compound_date = current_block;
+            // One listing per loan; the seller may update their own price
+            if let Some(listing) = self.loan_listings.get(loan_id) {
+                if listing.seller != caller {
+                    return Err(LendingError::InvalidStatus);
+                }
+            }
             
-            self.loans.insert(loan_id, &loan);
+            let listing = LoanListing {
+                loan_id,
+                seller: caller,
+                price,
+                listed_at: self.now(),
+            };
+            self.loan_listings.insert(loan_id, &listing);
             
-            self.env().emit_event(InterestCompounded {
+            self.env().emit_event(LoanListedForSale {
                 loan_id,
-                borrower: loan.borrower,
-                compound_period: periods_since_last_compound,
-                interest_accrued,
-                total_compounded: loan.total_compounded_interest,
-                new_remaining_balance: loan.remaining_balance,
+                seller: caller,
+                price,
             });
             
             Ok(())
         }
-        
-        /// Switch loan from simple to compound interest
+
+        /// Withdraw a lender position listing (seller only)
         #[ink(message)]
-        pub fn convert_to_compound_interest(
-            &mut self,
-            loan_id: u64,
-            frequency: CompoundFrequency,
-        ) -> Result<(), LendingError> {
+        pub fn cancel_loan_listing(&mut self, loan_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let listing = self.loan_listings.get(loan_id).ok_or(LendingError::ListingNotFound)?;
             
-            // Only lender can convert interest types
-            if loan.lender != Some(caller) {
+            if listing.seller != caller {
                 return Err(LendingError::Unauthorized);
             }
             
-            // Check if loan is already compound
-            if loan.interest_type == InterestType::Compound {
-                return Err(LendingError::InvalidAmount); // Reuse error for already compound
-            }
+            self.loan_listings.remove(loan_id);
             
-            // Set compound frequency and calculate period blocks
-            let compound_period_blocks = match frequency {
-                CompoundFrequency::Daily => 14400,      // 14400 blocks per day
-                CompoundFrequency::Weekly => 100800,    // 100800 blocks per week
-                CompoundFrequency::Monthly => 432000,   // 432000 blocks per month
-                CompoundFrequency::Quarterly => 1296000, // 1296000 blocks per quarter
-                CompoundFrequency::Annually => 5184000,  // 5184000 blocks per year
-            };
+            Ok(())
+        }
+
+        /// Buy a listed lender position, paying the asking price to the seller
+        #[ink(message, payable)]
+        pub fn buy_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let listing = self.loan_listings.get(loan_id).ok_or(LendingError::ListingNotFound)?;
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            // Convert loan to compound interest
-            loan.interest_type = InterestType::Compound;
-            loan.compound_frequency = frequency;
-            loan.compound_period_blocks = compound_period_blocks;
-            loan.last_compound_date = self.env().block_number() as u64;
-            loan.accrued_interest = 0;
-            loan.total_compounded_interest = 0;
+            if caller == listing.seller || caller == loan.borrower {
+                return Err(LendingError::Unauthorized);
+            }
             
-            // Recalculate remaining balance for compound interest
-            let current_block = self.env().block_number() as u64;
-            let blocks_since_creation = current_block - loan.created_at;
-            let periods = blocks_since_creation / compound_period_blocks;
+            self.receive_asset(loan.asset, listing.price)?;
             
-            if periods > 0 {
-                let rate_per_period = loan.interest_rate as f64 / 10000.0;
-                let compound_factor = (1.0 + rate_per_period).powf(periods as f64);
-                let new_total = (loan.amount as f64 * compound_factor) as u128;
-                loan.remaining_balance = new_total;
-                loan.total_compounded_interest = new_total - loan.amount;
-            }
+            self.move_lender_position(&mut loan, listing.seller, caller)?;
+            self.loan_listings.remove(loan_id);
             
-            self.loans.insert(loan_id, &loan);
+            self.send_asset(loan.asset, listing.seller, listing.price)?;
+            
+            self.env().emit_event(LoanSold {
+                loan_id,
+                seller: listing.seller,
+                buyer: caller,
+                price: listing.price,
+            });
             
             Ok(())
         }
-        
-        /// Get compound interest information for a loan
-        #[ink(message)]
-        pub fn get_compound_interest_info(&self, loan_id: u64) -> Result<(InterestType, CompoundFrequency, u64, Balance, Balance), LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            Ok((
-                loan.interest_type.clone(),
-                loan.compound_frequency.clone(),
-                loan.compound_period_blocks,
-                loan.accrued_interest,
-                loan.total_compounded_interest,
-            ))
-        }
-        
-        /// Calculate accrued interest for a loan (without compounding)
+
+        /// Get the lender position listing of a loan
         #[ink(message)]
-        pub fn calculate_accrued_interest(&self, loan_id: u64) -> Result<Balance, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            
-            let current_block = self.env().block_number() as u64;
-            let blocks_since_last_compound = current_block - loan.last_compound_date;
-            
-            if loan.interest_type == InterestType::Simple {
-                // Simple interest: P × r × t
-                let time_factor = blocks_since_last_compound as f64 / 14400.0; // Convert to days
-                let rate = loan.interest_rate as f64 / 10000.0;
-                let accrued = (loan.amount as f64 * rate * time_factor) as u128;
-                Ok(accrued)
-            } else {
-                // Compound interest: calculate what would be accrued
-                let periods = blocks_since_last_compound / loan.compound_period_blocks;
-                if periods == 0 {
-                    Ok(0)
-                } else {
-                    let rate_per_period = loan.interest_rate as f64 / 10000.0;
-                    let compound_factor = (1.0 + rate_per_period).powf(periods as f64);
-                    let new_total = (loan.amount as f64 * compound_factor) as u128;
-                    Ok(new_total - loan.amount)
-                }
-            }
+        pub fn get_loan_listing(&self, loan_id: u64) -> Option<LoanListing> {
+            self.loan_listings.get(loan_id)
         }
 
         // ============================================================================
-        // INTEREST-ONLY PAYMENT PERIODS
+        // LENDER OFFERS
         // ============================================================================
         
-        /// Set loan to interest-only payment structure for a specified number of periods
-        #[ink(message)]
-        pub fn set_interest_only_periods(
+        /// Post a standing loan offer, escrowing the offered liquidity sent with the call
+        #[ink(message, payable)]
+        pub fn create_loan_offer(
             &mut self,
-            loan_id: u64,
-            periods: u32,
-            payment_period_blocks: u64,
-        ) -> Result<(), LendingError> {
+            min_duration: u64,
+            max_duration: u64,
+            interest_rate: u16,
+            min_credit_score: u16,
+            accepted_collateral_types: Vec<CollateralType>,
+        ) -> Result<u64, LendingError> {
             let caller = self.env().caller();
-            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let max_amount = self.env().transferred_value();
             
-            // Only lender can set payment structure
-            if loan.lender != Some(caller) {
-                return Err(LendingError::Unauthorized);
+            if max_amount == 0 || accepted_collateral_types.is_empty() {
+                return Err(LendingError::InvalidAmount);
             }
             
-            // Check if loan is active
-            if loan.status != LoanStatus::Active {
-                return Err(LendingError::LoanNotActive);
+            if interest_rate == 0 || interest_rate > 10000 {
+                return Err(LendingError::InvalidInterestRate);
             }
             
-            // Validate payment period blocks (minimum 1 day)
-            if payment_period_blocks < 14400 {
-                return Err(LendingError::InvalidAmount); // Reuse error for invalid period
+            if min_duration == 0 || min_duration > max_duration || max_duration > MILLIS_PER_YEAR {
+                return Err(LendingError::InvalidDuration);
+            }
+
+            let user_profile = self.get_or_create_user_profile(caller);
+            if user_profile.is_blacklisted {
+                return Err(LendingError::UserBlacklisted);
             }
             
-            // Set interest-only structure
-            loan.payment_structure = PaymentStructure::InterestOnly;
-            loan.interest_only_periods = periods;
-            loan.payment_period_blocks = payment_period_blocks;
-            loan.next_payment_due = self.env().block_number() as u64 + payment_period_blocks;
-            loan.minimum_payment_amount = self.calculate_interest_payment(loan_id)?;
+            let offer_id = self.total_loan_offers.safe_add(1)?;
+            let offer = LoanOffer {
+                id: offer_id,
+                lender: caller,
+                max_amount,
+                available_amount: max_amount,
+                min_duration,
+                max_duration,
+                min_interest_rate: interest_rate,
+                min_credit_score,
+                accepted_collateral_types,
+                status: OfferStatus::Active,
+                created_at: self.now(),
+            };
             
-            self.loans.insert(loan_id, &loan);
+            self.loan_offers.insert(offer_id, &offer);
+            self.total_loan_offers = offer_id;
+            self.update_offer_depth(interest_rate, max_amount as i128, 1);
             
-            Ok(())
+            self.env().emit_event(LoanOfferCreated {
+                offer_id,
+                lender: caller,
+                max_amount,
+                interest_rate,
+            });
+            
+            Ok(offer_id)
         }
-        
-        /// Make an interest-only payment
-        #[ink(message)]
-        pub fn make_interest_only_payment(&mut self, loan_id: u64) -> Result<(), LendingError> {
+
+        /// Accept a loan offer, escrowing the collateral sent with the call; the loan is funded immediately
+        #[ink(message, payable)]
+        pub fn accept_offer(
+            &mut self,
+            offer_id: u64,
+            amount: Balance,
+            duration: u64,
+            collateral: Balance,
+            collateral_type: CollateralType,
+        ) -> Result<u64, LendingError> {
             let caller = self.env().caller();
-            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let mut offer = self.loan_offers.get(offer_id).ok_or(LendingError::OfferNotFound)?;
             
-            // Check if caller is the borrower
-            if loan.borrower != caller {
+            if offer.status != OfferStatus::Active {
+                return Err(LendingError::InvalidStatus);
+            }
+            
+            if caller == offer.lender {
                 return Err(LendingError::Unauthorized);
             }
             
-            // Check if loan supports interest-only payments
-            if loan.payment_structure != PaymentStructure::InterestOnly {
-                return Err(LendingError::InvalidAmount); // Reuse error for wrong payment type
+            if amount == 0 || amount > offer.available_amount {
+                return Err(LendingError::InsufficientOfferLiquidity);
             }
             
-            // Check if it's time for payment
-            let current_block = self.env().block_number() as u64;
-            if current_block < loan.next_payment_due {
-                return Err(LendingError::InvalidAmount); // Reuse error for too early
+            if duration < offer.min_duration || duration > offer.max_duration {
+                return Err(LendingError::InvalidDuration);
             }
             
-            // Check if interest-only periods are available
-            if loan.interest_only_periods_used >= loan.interest_only_periods {
-                return Err(LendingError::InvalidAmount); // Reuse error for no more periods
+            if !offer.accepted_collateral_types.contains(&collateral_type) {
+                return Err(LendingError::CollateralTypeNotAccepted);
             }
             
-            // Calculate interest payment for this period
-            let interest_payment = self.calculate_interest_payment(loan_id)?;
+            // Borrowers without a recorded credit score only qualify for offers without a minimum
+            if offer.min_credit_score > 0 {
+                let score = self.credit_scores.get(caller).map(|c| c.score).unwrap_or(0);
+                if score < offer.min_credit_score {
+                    return Err(LendingError::Unauthorized);
+                }
+            }
             
-            // Update loan state
-            loan.current_payment_period += 1;
-            loan.interest_only_periods_used += 1;
-            loan.next_payment_due = current_block + loan.payment_period_blocks;
+            let loan_id = self.create_loan_request(LoanRequest {
+                asset: Asset::Native,
+                collateral_asset: Asset::Native,
+                amount,
+                interest_rate: offer.min_interest_rate,
+                duration,
+                collateral,
+                basket: Vec::new(),
+            })?;
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            // If this was the last interest-only period, switch to P&I
-            if loan.interest_only_periods_used >= loan.interest_only_periods {
-                loan.payment_structure = PaymentStructure::PrincipalAndInterest;
-                loan.minimum_payment_amount = self.calculate_minimum_payment(loan_id)?;
-            }
+            let current_time = self.now();
+            loan.collateral_requirements.push(CollateralRequirement {
+                collateral_type,
+                required_amount: collateral,
+                current_amount: collateral,
+                liquidation_threshold: 0, // Use the protocol default
+                maintenance_margin: 0,
+                last_updated: current_time,
+            });
             
-            self.loans.insert(loan_id, &loan);
+            // Fund the loan from the offer's escrowed liquidity
+            self.record_contribution(&mut loan, offer.lender, amount)?;
+            self.activate_funded_loan(&mut loan)?;
             
-            self.env().emit_event(InterestOnlyPaymentMade {
+            offer.available_amount = offer.available_amount.safe_sub(amount)?;
+            let filled = offer.available_amount == 0;
+            if filled {
+                offer.status = OfferStatus::Filled;
+            }
+            self.loan_offers.insert(offer_id, &offer);
+            self.update_offer_depth(offer.min_interest_rate, -(amount as i128), if filled { -1 } else { 0 });
+            
+            self.env().emit_event(LoanOfferAccepted {
+                offer_id,
                 loan_id,
-                borrower: loan.borrower,
-                payment_period: loan.current_payment_period,
-                interest_paid: interest_payment,
-                principal_remaining: loan.amount,
-                next_payment_due: loan.next_payment_due,
+                borrower: caller,
+                amount,
+                remaining_liquidity: offer.available_amount,
             });
             
-            Ok(())
+            Ok(loan_id)
         }
-        
-        /// Switch back to principal and interest payments
+
+        /// Cancel a loan offer and refund its remaining liquidity (lender only)
         #[ink(message)]
-        pub fn switch_to_principal_and_interest(&mut self, loan_id: u64) -> Result<(), LendingError> {
+        pub fn cancel_loan_offer(&mut self, offer_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let mut offer = self.loan_offers.get(offer_id).ok_or(LendingError::OfferNotFound)?;
             
-            // Only lender can change payment structure
-            if loan.lender != Some(caller) {
+            if offer.lender != caller {
                 return Err(LendingError::Unauthorized);
             }
             
-            // Check if loan is active
-            if loan.status != LoanStatus::Active {
-                return Err(LendingError::LoanNotActive);
+            if offer.status != OfferStatus::Active {
+                return Err(LendingError::InvalidStatus);
             }
             
-            // Switch to P&I structure
-            loan.payment_structure = PaymentStructure::PrincipalAndInterest;
-            loan.minimum_payment_amount = self.calculate_minimum_payment(loan_id)?;
+            let refunded_amount = offer.available_amount;
+            offer.available_amount = 0;
+            offer.status = OfferStatus::Cancelled;
+            self.loan_offers.insert(offer_id, &offer);
+            self.update_offer_depth(offer.min_interest_rate, -(refunded_amount as i128), -1);
             
-            self.loans.insert(loan_id, &loan);
+            if refunded_amount > 0 {
+                self.send_asset(Asset::Native, caller, refunded_amount)?;
+            }
+            
+            self.env().emit_event(LoanOfferCancelled {
+                offer_id,
+                lender: caller,
+                refunded_amount,
+            });
             
             Ok(())
         }
-        
-        /// Get payment structure information for a loan
+
+        /// Get loan offer details
         #[ink(message)]
-        pub fn get_payment_structure_info(&self, loan_id: u64) -> Result<(PaymentStructure, u32, u32, u32, u64, Balance), LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            Ok((
-                loan.payment_structure.clone(),
-                loan.interest_only_periods,
-                loan.interest_only_periods_used,
-                loan.current_payment_period,
-                loan.next_payment_due,
-                loan.minimum_payment_amount,
-            ))
-        }
-        
-        /// Calculate interest payment for current period
-        fn calculate_interest_payment(&self, loan_id: u64) -> Result<Balance, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            
-            let rate = loan.interest_rate as f64 / 10000.0; // Convert basis points to decimal
-            let time_factor = loan.payment_period_blocks as f64 / 5184000.0; // Convert to years
-            let interest = (loan.amount as f64 * rate * time_factor) as u128;
-            
-            Ok(interest)
+        pub fn get_loan_offer(&self, offer_id: u64) -> Option<LoanOffer> {
+            self.loan_offers.get(offer_id)
         }
-        
-        /// Calculate minimum payment for P&I structure
-        fn calculate_minimum_payment(&self, loan_id: u64) -> Result<Balance, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            
-            // Simple P&I calculation: (Principal × Rate × Time) + (Principal / Total Periods)
-            let rate = loan.interest_rate as f64 / 10000.0;
-            let time_factor = loan.payment_period_blocks as f64 / 5184000.0;
-            let interest = (loan.amount as f64 * rate * time_factor) as u128;
-            let principal = loan.amount / ((loan.duration / loan.payment_period_blocks) as u128);
-            
-            Ok(interest + principal)
+
+        /// Get market depth backed by active offers as (interest rate, available liquidity, offer count)
+        #[ink(message)]
+        pub fn get_offer_market_depth(&self) -> Vec<(u16, Balance, u32)> {
+            self.offer_depth_levels.iter()
+                .map(|level| (level.price_level, level.liquidity_available, level.order_count))
+                .collect()
         }
 
         // ============================================================================
-        // GRACE PERIOD MANAGEMENT
+        // COLLATERAL LIQUIDATION
         // ============================================================================
         
-        /// Grant or extend grace period for a loan
+        /// Liquidate an undercollateralized or overdue loan (callable by anyone)
         #[ink(message)]
-        pub fn grant_grace_period(
-            &mut self,
-            loan_id: u64,
-            duration: u64,
-            reason: GracePeriodReason,
-        ) -> Result<(), LendingError> {
+        pub fn liquidate_loan(&mut self, loan_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
             let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            // Only lender can grant grace periods
-            if loan.lender != Some(caller) {
+            if !loan.status.allows(&LoanAction::Liquidate) {
+                return Err(LendingError::LoanNotActive);
+            }
+
+            self.accrue_loan_interest(&mut loan)?;
+            if !self.is_liquidatable_loan(&loan)? {
+                return Err(LendingError::LoanHealthy);
+            }
+
+            // Guarantor covers the outstanding balance before collateral is seized
+            self.call_guarantee(&mut loan, 0)?;
+
+            // Seize escrowed collateral
+            let collateral_seized = self.escrowed_collateral.get(loan_id).unwrap_or(0);
+            self.escrowed_collateral.remove(loan_id);
+            self.update_asset_totals(loan.collateral_asset, |totals| {
+                totals.escrowed_collateral = totals.escrowed_collateral.safe_sub(collateral_seized)?;
+                Ok(())
+            })?;
+
+            // Split seized collateral: lender first, then liquidator bonus, then borrower surplus
+            let debt = loan.remaining_balance;
+            let (mut debt_repaid, collateral_to_lenders, liquidator_bonus, surplus_returned) =
+                self.split_seized_collateral(&loan, loan.collateral_asset, collateral_seized, debt)?;
+
+            // Basket positions cover what the primary collateral did not, in the order they were posted
+            let mut basket_splits = Vec::new();
+            for position in self.take_collateral_basket(loan_id)? {
+                let (repaid, to_lenders, bonus, surplus) =
+                    self.split_seized_collateral(&loan, position.asset, position.amount, debt.safe_sub(debt_repaid)?)?;
+                debt_repaid = debt_repaid.safe_add(repaid)?;
+                basket_splits.push((position.asset, to_lenders, bonus, surplus));
+            }
+
+            // Update loan state, keeping any unrecovered shortfall as remaining balance
+            self.allocate_payment(&mut loan, debt_repaid)?;
+            loan.total_paid = loan.total_paid.safe_add(debt_repaid)?;
+            loan.remaining_balance = debt.safe_sub(debt_repaid)?;
+            self.sync_collateral_requirements(&mut loan, 0);
+            self.transition_loan(&mut loan, LoanStatus::Liquidated, LoanAction::Liquidate)?;
+
+            self.loans.insert(loan_id, &loan);
+
+            self.distribute_asset_to_lenders(&loan, loan.collateral_asset, collateral_to_lenders)?;
+
+            if liquidator_bonus > 0 {
+                self.send_asset(loan.collateral_asset, caller, liquidator_bonus)?;
+            }
+
+            // Surplus collateral reimburses a called guarantor before it goes back to the borrower
+            let surplus_returned = self.reimburse_guarantor(&loan, loan.collateral_asset, surplus_returned)?;
+            if surplus_returned > 0 {
+                self.send_asset(loan.collateral_asset, loan.borrower, surplus_returned)?;
+            }
+
+            for (asset, to_lenders, bonus, surplus) in basket_splits {
+                self.distribute_asset_to_lenders(&loan, asset, to_lenders)?;
+                if bonus > 0 {
+                    self.send_asset(asset, caller, bonus)?;
+                }
+                let surplus = self.reimburse_guarantor(&loan, asset, surplus)?;
+                if surplus > 0 {
+                    self.send_asset(asset, loan.borrower, surplus)?;
+                }
+            }
+
+            self.remove_from_active_loans(&loan);
+            self.release_loan_liquidity(&loan)?;
+
+            self.env().emit_event(LoanLiquidated {
+                loan_id,
+                borrower: loan.borrower,
+                liquidator: caller,
+                collateral_seized,
+                debt_repaid,
+                liquidator_bonus,
+                surplus_returned,
+            });
+
+            Ok(())
+        }
+
+        /// Check if a loan can currently be liquidated
+        #[ink(message)]
+        pub fn can_liquidate_loan(&self, loan_id: u64) -> Result<bool, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            Ok(loan.status.allows(&LoanAction::Liquidate) && self.is_liquidatable_loan(&loan)?)
+        }
+
+        /// Set default liquidation threshold and liquidator bonus (admin only)
+        #[ink(message)]
+        pub fn set_liquidation_parameters(
+            &mut self,
+            liquidation_threshold: u16,
+            liquidation_bonus: u16,
+        ) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
                 return Err(LendingError::Unauthorized);
             }
             
-            // Check if loan is active
-            if loan.status != LoanStatus::Active {
-                return Err(LendingError::LoanNotActive);
+            // Validate parameters (threshold up to 100%, bonus up to 20%)
+            if liquidation_threshold == 0 || liquidation_threshold > 10000 || liquidation_bonus > 2000 {
+                return Err(LendingError::InvalidAmount);
             }
             
-            // Validate grace period duration (minimum 100 blocks, maximum 1 day)
-            if duration < 100 || duration > 14400 {
-                return Err(LendingError::InvalidAmount); // Reuse error for invalid duration
+            self.liquidation_threshold = liquidation_threshold;
+            self.liquidation_bonus = liquidation_bonus;
+            
+            Ok(())
+        }
+
+        /// Get default liquidation threshold and liquidator bonus
+        #[ink(message)]
+        pub fn get_liquidation_parameters(&self) -> (u16, u16) {
+            (self.liquidation_threshold, self.liquidation_bonus)
+        }
+
+        /// Split seized collateral of one asset into the debt it repays, the lenders' share, the liquidator bonus and the borrower's surplus
+        fn split_seized_collateral(
+            &self,
+            loan: &Loan,
+            collateral_asset: Asset,
+            seized: Balance,
+            debt: Balance,
+        ) -> Result<(Balance, Balance, Balance, Balance), LendingError> {
+            if debt == 0 {
+                return Ok((0, 0, 0, seized));
             }
             
-            // Check if grace period extensions are available
-            if loan.grace_period_extensions >= loan.max_grace_period_extensions {
-                return Err(LendingError::InvalidAmount); // Reuse error for no more extensions
+            let debt_repaid = debt.min(self.collateral_value(collateral_asset, loan.asset, seized)?);
+            let to_lenders = self.collateral_for_debt(collateral_asset, loan.asset, debt_repaid)?.min(seized);
+            let bonus = bps_mul(to_lenders, self.liquidation_bonus as u128, Rounding::Down)?
+                .min(seized.safe_sub(to_lenders)?);
+            let surplus = seized.safe_sub(to_lenders)?.safe_sub(bonus)?;
+            Ok((debt_repaid, to_lenders, bonus, surplus))
+        }
+
+        // ============================================================================
+        // COLLATERAL MANAGEMENT
+        // ============================================================================
+        
+        /// Add the transferred value to a loan's escrowed collateral (borrower only)
+        #[ink(message, payable)]
+        pub fn add_collateral(&mut self, loan_id: u64) -> Result<u128, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.require_native(loan.collateral_asset)?;
+            self.add_collateral_amount(loan_id, self.env().transferred_value())
+        }
+        
+        /// Add an amount of the loan's collateral asset to its escrow (borrower only); returns the new health factor
+        #[ink(message, payable)]
+        pub fn add_collateral_amount(&mut self, loan_id: u64, amount: Balance) -> Result<u128, LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            if loan.borrower != caller {
+                return Err(LendingError::Unauthorized);
             }
             
-            // Calculate new grace period
-            let new_grace_period = loan.grace_period_blocks + duration;
-            let extension_number = loan.grace_period_extensions + 1;
+            if !loan.status.allows(&LoanAction::AddCollateral) {
+                return Err(LendingError::LoanNotActive);
+            }
             
-            // Update loan grace period
-            loan.grace_period_blocks = new_grace_period;
-            loan.grace_period_extensions = extension_number;
-            loan.grace_period_reason = reason.clone();
+            if amount == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
             
-            // Record grace period history
-            let grace_record = GracePeriodRecord {
-                timestamp: self.env().block_number() as u64,
-                reason: reason.clone(),
-                duration,
-                extension_number,
-                granted_by: caller,
-            };
-            loan.grace_period_history.push(grace_record);
+            self.receive_asset(loan.collateral_asset, amount)?;
             
+            let collateral = self.escrowed_collateral.get(loan_id).unwrap_or(0).safe_add(amount)?;
+            self.escrowed_collateral.insert(loan_id, &collateral);
+            self.update_asset_totals(loan.collateral_asset, |totals| {
+                totals.escrowed_collateral = totals.escrowed_collateral.safe_add(amount)?;
+                Ok(())
+            })?;
+            
+            self.accrue_loan_interest(&mut loan)?;
+            self.sync_collateral_requirements(&mut loan, collateral);
+            let health_factor = self.update_margin_call(&mut loan)?;
             self.loans.insert(loan_id, &loan);
             
-            self.env().emit_event(GracePeriodGranted {
+            self.env().emit_event(CollateralAdded {
                 loan_id,
-                borrower: loan.borrower,
-                reason,
-                duration,
-                extension_number,
-                granted_by: caller,
-                total_grace_period: new_grace_period,
+                amount,
+                health_factor,
             });
             
-            Ok(())
+            Ok(health_factor)
         }
         
-        /// Check if loan is within grace period
+        /// Withdraw collateral the loan does not need to stay above its maintenance margin (borrower only)
         #[ink(message)]
-        pub fn is_within_grace_period(&self, loan_id: u64) -> Result<bool, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+        pub fn withdraw_excess_collateral(&mut self, loan_id: u64, amount: Balance) -> Result<u128, LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            let current_block = self.env().block_number() as u64;
-            let overdue_since = loan.overdue_since.unwrap_or(0);
+            if loan.borrower != caller {
+                return Err(LendingError::Unauthorized);
+            }
             
-            if overdue_since == 0 {
-                return Ok(false); // Not overdue
+            if !loan.status.allows(&LoanAction::WithdrawCollateral) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            if amount == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            // Health is checked against the debt including interest accrued so far
+            self.accrue_loan_interest(&mut loan)?;
+            let collateral = self.escrowed_collateral.get(loan_id).unwrap_or(0)
+                .checked_sub(amount)
+                .ok_or(LendingError::InsufficientCollateral)?;
+            let basket = self.collateral_baskets.get(loan_id).unwrap_or_default();
+            let health_factor = self.health_factor_with(&loan, collateral, &basket)?;
+            if health_factor < self.loan_maintenance_margin(&loan) as u128 {
+                return Err(LendingError::InsufficientCollateral);
             }
             
-            let grace_period_end = overdue_since + loan.grace_period_blocks;
-            Ok(current_block <= grace_period_end)
+            self.escrowed_collateral.insert(loan_id, &collateral);
+            self.update_asset_totals(loan.collateral_asset, |totals| {
+                totals.escrowed_collateral = totals.escrowed_collateral.safe_sub(amount)?;
+                Ok(())
+            })?;
+            self.sync_collateral_requirements(&mut loan, collateral);
+            loan.margin_call_at = None;
+            self.loans.insert(loan_id, &loan);
+            
+            self.send_asset(loan.collateral_asset, caller, amount)?;
+            
+            self.env().emit_event(CollateralReleased {
+                loan_id,
+                recipient: caller,
+                amount,
+            });
+            
+            Ok(health_factor)
         }
         
-        /// Get grace period information for a loan
+        /// Re-check a loan's health, issuing a margin call if it has fallen below the maintenance margin
         #[ink(message)]
-        pub fn get_grace_period_info(&self, loan_id: u64) -> Result<(u64, u64, u32, u32, GracePeriodReason, Vec<GracePeriodRecord>), LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            Ok((
-                loan.grace_period_blocks,
-                loan.grace_period_used,
-                loan.grace_period_extensions,
-                loan.max_grace_period_extensions,
-                loan.grace_period_reason.clone(),
-                loan.grace_period_history.clone(),
-            ))
+        pub fn check_margin_call(&mut self, loan_id: u64) -> Result<bool, LendingError> {
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            if !loan.status.is_open() {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            self.accrue_loan_interest(&mut loan)?;
+            self.update_margin_call(&mut loan)?;
+            self.loans.insert(loan_id, &loan);
+            
+            Ok(loan.margin_call_at.is_some())
         }
         
-        /// Calculate remaining grace period for an overdue loan
+        /// Get a loan's health factor in basis points: below 10000 it can be liquidated,
+        /// below the maintenance margin a margin call is due
         #[ink(message)]
-        pub fn calculate_remaining_grace_period(&self, loan_id: u64) -> Result<u64, LendingError> {
+        pub fn get_health_factor(&self, loan_id: u64) -> Result<u128, LendingError> {
             let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.calculate_health_factor(&loan)
+        }
+        
+        /// Set the default maintenance margin, a health factor of at least 100% (admin only)
+        #[ink(message)]
+        pub fn set_maintenance_margin(&mut self, maintenance_margin: u16) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
             
-            let current_block = self.env().block_number() as u64;
-            let overdue_since = loan.overdue_since.unwrap_or(0);
+            if maintenance_margin < 10000 {
+                return Err(LendingError::InvalidAmount);
+            }
             
-            if overdue_since == 0 {
-                return Ok(0); // Not overdue
+            self.maintenance_margin = maintenance_margin;
+            
+            Ok(())
+        }
+        
+        /// Get the default maintenance margin
+        #[ink(message)]
+        pub fn get_maintenance_margin(&self) -> u16 {
+            self.maintenance_margin
+        }
+        
+        /// Record a margin call the first time health drops below the maintenance margin, and clear it once restored
+        fn update_margin_call(&self, loan: &mut Loan) -> Result<u128, LendingError> {
+            let health_factor = self.calculate_health_factor(loan)?;
+            let maintenance_margin = self.loan_maintenance_margin(loan);
+            
+            if health_factor >= maintenance_margin as u128 {
+                loan.margin_call_at = None;
+            } else if loan.margin_call_at.is_none() {
+                loan.margin_call_at = Some(self.now());
+                self.env().emit_event(MarginCall {
+                    loan_id: loan.id,
+                    borrower: loan.borrower,
+                    health_factor,
+                    maintenance_margin,
+                });
             }
             
-            let grace_period_end = overdue_since + loan.grace_period_blocks;
-            if current_block > grace_period_end {
-                return Ok(0); // Grace period expired
+            Ok(health_factor)
+        }
+        
+        /// Record the escrowed collateral on each of a loan's collateral requirements
+        fn sync_collateral_requirements(&self, loan: &mut Loan, collateral: Balance) {
+            let current_time = self.now();
+            for requirement in loan.collateral_requirements.iter_mut() {
+                requirement.current_amount = collateral;
+                requirement.last_updated = current_time;
             }
+        }
+
+        // ============================================================================
+        // COLLATERAL BASKETS
+        // ============================================================================
+        
+        /// Set the share of a position's value that counts towards borrowing power, in basis points;
+        /// zero stops the collateral type and asset from being accepted (admin only)
+        #[ink(message)]
+        pub fn set_collateral_factor(
+            &mut self,
+            collateral_type: CollateralType,
+            asset: Asset,
+            collateral_factor: u16,
+        ) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if collateral_factor as u128 > BPS {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            if !self.assets.contains(asset) {
+                return Err(LendingError::AssetNotRegistered);
+            }
+            
+            if collateral_factor == 0 {
+                self.collateral_factors.remove((collateral_type.clone(), asset));
+            } else {
+                self.collateral_factors.insert((collateral_type.clone(), asset), &collateral_factor);
+            }
+            
+            self.env().emit_event(CollateralFactorSet {
+                asset,
+                collateral_type,
+                collateral_factor,
+            });
             
-            Ok(grace_period_end - current_block)
+            Ok(())
         }
         
-        /// Set custom grace period for a loan (lender only)
+        /// Get the collateral factor of a collateral type and asset, zero if not accepted
         #[ink(message)]
-        pub fn set_custom_grace_period(
+        pub fn get_collateral_factor(&self, collateral_type: CollateralType, asset: Asset) -> u16 {
+            self.collateral_factor(&collateral_type, asset)
+        }
+        
+        /// Create a loan request backed by a basket of collateral positions; native positions are paid
+        /// together as the transferred value and PSP22 positions are pulled from the caller's allowance
+        #[ink(message, payable)]
+        pub fn create_basket_loan(
+            &mut self,
+            asset: Asset,
+            amount: Balance,
+            interest_rate: u16,
+            duration: u64,
+            positions: Vec<CollateralPosition>,
+        ) -> Result<u64, LendingError> {
+            let mut basket = Vec::new();
+            for position in positions {
+                self.add_basket_position(&mut basket, position)?;
+            }
+            
+            if basket.is_empty() {
+                return Err(LendingError::InsufficientCollateral);
+            }
+            
+            self.create_loan_request(LoanRequest {
+                asset,
+                collateral_asset: asset,
+                amount,
+                interest_rate,
+                duration,
+                collateral: 0,
+                basket,
+            })
+        }
+        
+        /// Add a position to a loan's collateral basket (borrower only); returns the new health factor
+        #[ink(message, payable)]
+        pub fn add_basket_collateral(
             &mut self,
             loan_id: u64,
-            grace_period_blocks: u64,
-            max_extensions: u32,
-        ) -> Result<(), LendingError> {
+            collateral_type: CollateralType,
+            asset: Asset,
+            amount: Balance,
+        ) -> Result<u128, LendingError> {
             let caller = self.env().caller();
             let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            // Only lender can set custom grace periods
-            if loan.lender != Some(caller) {
+            if loan.borrower != caller {
                 return Err(LendingError::Unauthorized);
             }
             
-            // Check if loan is active
-            if loan.status != LoanStatus::Active {
+            if !loan.status.allows(&LoanAction::AddCollateral) {
                 return Err(LendingError::LoanNotActive);
             }
             
-            // Validate grace period (minimum 100 blocks, maximum 1 week)
-            if grace_period_blocks < 100 || grace_period_blocks > 100800 {
-                return Err(LendingError::InvalidAmount); // Reuse error for invalid duration
-            }
+            let mut basket = self.collateral_baskets.get(loan_id).unwrap_or_default();
+            self.add_basket_position(&mut basket, CollateralPosition {
+                collateral_type: collateral_type.clone(),
+                asset,
+                amount,
+            })?;
+            self.receive_asset(asset, amount)?;
             
-            // Update grace period settings
-            loan.grace_period_blocks = grace_period_blocks;
-            loan.max_grace_period_extensions = max_extensions;
+            self.collateral_baskets.insert(loan_id, &basket);
+            self.update_asset_totals(asset, |totals| {
+                totals.escrowed_collateral = totals.escrowed_collateral.safe_add(amount)?;
+                Ok(())
+            })?;
             
+            self.accrue_loan_interest(&mut loan)?;
+            let health_factor = self.update_margin_call(&mut loan)?;
             self.loans.insert(loan_id, &loan);
             
-            Ok(())
+            self.env().emit_event(BasketCollateralAdded {
+                loan_id,
+                collateral_type,
+                asset,
+                amount,
+            });
+            
+            Ok(health_factor)
         }
-
-        // ============================================================================
-        // LIQUIDITY POOL MANAGEMENT
-        // ============================================================================
         
-        /// Create a new liquidity pool
+        /// Withdraw part of a basket position the loan does not need to stay above its maintenance margin (borrower only)
         #[ink(message)]
-        pub fn create_liquidity_pool(
+        pub fn withdraw_basket_collateral(
             &mut self,
-            name: String,
-            initial_liquidity: Balance,
-            pool_fee_rate: u16,
-            reward_rate: u16,
-            min_liquidity: Balance,
-            max_liquidity: Balance,
-        ) -> Result<u64, LendingError> {
+            loan_id: u64,
+            collateral_type: CollateralType,
+            asset: Asset,
+            amount: Balance,
+        ) -> Result<u128, LendingError> {
             let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
             
-            // Validate parameters
-            if initial_liquidity == 0 || pool_fee_rate > 1000 || reward_rate > 1000 {
-                return Err(LendingError::InvalidAmount);
+            if loan.borrower != caller {
+                return Err(LendingError::Unauthorized);
             }
             
-            if min_liquidity >= max_liquidity {
+            if !loan.status.allows(&LoanAction::WithdrawCollateral) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            if amount == 0 {
                 return Err(LendingError::InvalidAmount);
             }
             
-            let pool_id = self.total_pools + 1;
-            let current_block = self.env().block_number() as u64;
+            let mut basket = self.collateral_baskets.get(loan_id).unwrap_or_default();
+            let index = basket.iter()
+                .position(|position| position.collateral_type == collateral_type && position.asset == asset)
+                .ok_or(LendingError::CollateralPositionNotFound)?;
+            basket[index].amount = basket[index].amount.checked_sub(amount).ok_or(LendingError::InsufficientCollateral)?;
+            if basket[index].amount == 0 {
+                basket.remove(index);
+            }
             
-            // Create liquidity pool
-            let pool = LiquidityPool {
-                id: pool_id,
-                name: name.clone(),
-                total_liquidity: initial_liquidity,
-                active_loans: 0,
-                total_volume: 0,
-                pool_fee_rate,
-                reward_rate,
-                min_liquidity,
-                max_liquidity,
-                created_at: current_block,
-                status: PoolStatus::Active,
-                liquidity_providers: Vec::new(),
-                total_rewards_distributed: 0,
-                performance_score: 5000, // Default: 50% performance score
-                last_rebalance: current_block,
-                rebalance_frequency: 14400, // Default: daily rebalancing (14400 blocks)
-                target_liquidity_ratio: 8000, // Default: 80% target liquidity ratio
-                current_liquidity_ratio: 10000, // Initial: 100% current ratio
-                rebalance_threshold: 500, // Default: 5% threshold for rebalancing
-                auto_rebalance_enabled: true, // Default: auto-rebalancing enabled
-                yield_farming_enabled: false, // Default: yield farming disabled
-                reward_tokens: Vec::new(), // No reward tokens initially
-                staking_requirements: StakingRequirements {
-                    min_stake_amount: 1000, // Minimum 1000 tokens to stake
-                    lock_period: 14400, // 1 day lock period
-                    early_unstake_penalty: 500, // 5% penalty for early unstaking
-                    max_stake_amount: 100000, // Maximum 100,000 tokens to stake
-                },
-                tier_multipliers: vec![
-                    TierMultiplier {
-                        tier_name: "Bronze".to_string(),
-                        min_stake_amount: 1000,
-                        multiplier: 1000, // 1x multiplier
-                        bonus_rewards: 0,
-                    },
-                    TierMultiplier {
-                        tier_name: "Silver".to_string(),
-                        min_stake_amount: 5000,
-                        multiplier: 1200, // 1.2x multiplier
-                        bonus_rewards: 100,
-                    },
-                    TierMultiplier {
-                        tier_name: "Gold".to_string(),
-                        min_stake_amount: 20000,
-                        multiplier: 1500, // 1.5x multiplier
-                        bonus_rewards: 300,
-                    },
-                    TierMultiplier {
-                        tier_name: "Platinum".to_string(),
-                        min_stake_amount: 50000,
-                        multiplier: 2000, // 2x multiplier
-                        bonus_rewards: 500,
-                    },
-                ],
-                total_staked_tokens: 0,
-                market_depth_levels: vec![
-                    MarketDepthLevel {
-                        price_level: 950, // 95% price level
-                        liquidity_available: 0,
-                        order_count: 0,
-                        last_updated: current_block,
-                    },
-                    MarketDepthLevel {
-                        price_level: 1000, // 100% price level
-                        liquidity_available: 0,
-                        order_count: 0,
-                        last_updated: current_block,
-                    },
-                    MarketDepthLevel {
-                        price_level: 1050, // 105% price level
-                        liquidity_available: 0,
-                        order_count: 0,
-                        last_updated: current_block,
-                    },
-                ],
-                optimal_distribution: OptimalDistribution {
-                    target_depth_spread: 200, // 2% spread across levels
-                    min_depth_per_level: 1000, // Minimum 1,000 per level
-                    max_depth_per_level: 50000, // Maximum 50,000 per level
-                    rebalancing_threshold: 300, // 3% threshold for rebalancing
-                    auto_optimization_enabled: true,
-                },
-                depth_based_pricing: false, // Default: disabled
-                concentration_limits: ConcentrationLimits {
-                    max_single_pool_concentration: 8000, // Max 80% in single pool
-                    max_provider_concentration: 5000, // Max 50% per provider
-                    min_pool_diversity: 2, // Minimum 2 pools
-                    concentration_check_frequency: 14400, // Check daily
-                },
-            };
-            
-            // Add creator as first liquidity provider
-            let creator_share = 10000; // 100% initially
-            let creator_provider = LiquidityProvider {
-                account: caller,
-                liquidity_provided: initial_liquidity,
-                pool_share: creator_share,
-                rewards_earned: 0,
-                joined_at: current_block,
-                last_reward_claim: current_block,
-            };
+            // Health is checked against the debt including interest accrued so far
+            self.accrue_loan_interest(&mut loan)?;
+            let collateral = self.escrowed_collateral.get(loan_id).unwrap_or(0);
+            let health_factor = self.health_factor_with(&loan, collateral, &basket)?;
+            if health_factor < self.loan_maintenance_margin(&loan) as u128 {
+                return Err(LendingError::InsufficientCollateral);
+            }
             
-            let mut pool_with_provider = pool.clone();
-            pool_with_provider.liquidity_providers.push(creator_provider);
+            if basket.is_empty() {
+                self.collateral_baskets.remove(loan_id);
+            } else {
+                self.collateral_baskets.insert(loan_id, &basket);
+            }
+            self.update_asset_totals(asset, |totals| {
+                totals.escrowed_collateral = totals.escrowed_collateral.safe_sub(amount)?;
+                Ok(())
+            })?;
+            loan.margin_call_at = None;
+            self.loans.insert(loan_id, &loan);
             
-            // Store pool and update state
-            self.liquidity_pools.insert(pool_id, &pool_with_provider);
-            self.pool_liquidity_providers.insert(pool_id, &vec![caller]);
-            self.total_pools = pool_id;
+            self.send_asset(asset, caller, amount)?;
             
-            self.env().emit_event(LiquidityPoolCreated {
-                pool_id,
-                name,
-                creator: caller,
-                initial_liquidity,
-                pool_fee_rate,
-                reward_rate,
+            self.env().emit_event(BasketCollateralReleased {
+                loan_id,
+                asset,
+                recipient: caller,
+                amount,
             });
             
-            Ok(pool_id)
+            Ok(health_factor)
         }
         
-        /// Provide liquidity to a pool
+        /// Get the positions in a loan's collateral basket
         #[ink(message)]
-        pub fn provide_liquidity(&mut self, pool_id: u64, amount: Balance) -> Result<(), LendingError> {
-            let caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+        pub fn get_collateral_basket(&self, loan_id: u64) -> Vec<CollateralPosition> {
+            self.collateral_baskets.get(loan_id).unwrap_or_default()
+        }
+        
+        /// Get a loan's borrowing power in units of the loan asset: the primary collateral at the loan's
+        /// liquidation threshold plus each basket position at its collateral factor
+        #[ink(message)]
+        pub fn get_borrowing_power(&self, loan_id: u64) -> Result<Balance, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let collateral = self.escrowed_collateral.get(loan_id).unwrap_or(0);
+            let basket = self.collateral_baskets.get(loan_id).unwrap_or_default();
             
-            // Check if pool is active
-            if pool.status != PoolStatus::Active {
-                return Err(LendingError::LoanNotActive);
+            let valued_collateral = self.collateral_value(loan.collateral_asset, loan.asset, collateral)?;
+            let threshold = self.loan_liquidation_threshold(&loan);
+            bps_mul(valued_collateral, threshold as u128, Rounding::Down)?
+                .safe_add(self.basket_borrowing_power(loan.asset, &basket)?)
+        }
+        
+        /// Collateral factor of a collateral type and asset, zero if not accepted
+        fn collateral_factor(&self, collateral_type: &CollateralType, asset: Asset) -> u16 {
+            self.collateral_factors.get((collateral_type.clone(), asset)).unwrap_or(0)
+        }
+        
+        /// Haircut-weighted oracle value of a basket in units of the loan asset
+        fn basket_borrowing_power(&self, asset: Asset, basket: &[CollateralPosition]) -> Result<Balance, LendingError> {
+            let mut borrowing_power: Balance = 0;
+            for position in basket {
+                let collateral_factor = self.collateral_factor(&position.collateral_type, position.asset);
+                if collateral_factor == 0 {
+                    continue;
+                }
+                let value = self.collateral_value(position.asset, asset, position.amount)?;
+                borrowing_power = borrowing_power.safe_add(bps_mul(value, collateral_factor as u128, Rounding::Down)?)?;
             }
-            
-            // Validate amount
-            if amount == 0 || amount < pool.min_liquidity || pool.total_liquidity + amount > pool.max_liquidity {
+            Ok(borrowing_power)
+        }
+        
+        /// Validate a position and merge it into a basket, keeping one position per collateral type and asset
+        fn add_basket_position(&self, basket: &mut Vec<CollateralPosition>, position: CollateralPosition) -> Result<(), LendingError> {
+            if position.amount == 0 {
                 return Err(LendingError::InvalidAmount);
             }
             
-            // Calculate new pool share
-            let new_total_liquidity = pool.total_liquidity + amount;
-            let new_provider_share = ((amount as u32 * 10000) / new_total_liquidity as u32) as u16;
-            
-            // Check if provider already exists
-            let existing_provider_index = pool.liquidity_providers.iter().position(|p| p.account == caller);
-            
-            if let Some(index) = existing_provider_index {
-                // Update existing provider
-                let mut provider = pool.liquidity_providers[index].clone();
-                provider.liquidity_provided += amount;
-                provider.pool_share = ((provider.liquidity_provided as u32 * 10000) / new_total_liquidity as u32) as u16;
-                pool.liquidity_providers[index] = provider;
-            } else {
-                // Add new provider
-                let new_provider = LiquidityProvider {
-                    account: caller,
-                    liquidity_provided: amount,
-                    pool_share: new_provider_share,
-                    rewards_earned: 0,
-                    joined_at: self.env().block_number() as u64,
-                    last_reward_claim: self.env().block_number() as u64,
-                };
-                pool.liquidity_providers.push(new_provider);
+            self.require_enabled_asset(position.asset)?;
+            if self.collateral_factor(&position.collateral_type, position.asset) == 0 {
+                return Err(LendingError::CollateralTypeNotAccepted);
             }
             
-            // Update pool state
-            pool.total_liquidity = new_total_liquidity;
-            
-            // Update provider shares for all providers
-            for provider in &mut pool.liquidity_providers {
-                provider.pool_share = ((provider.liquidity_provided as u32 * 10000) / new_total_liquidity as u32) as u16;
+            let existing = basket.iter_mut()
+                .find(|held| held.collateral_type == position.collateral_type && held.asset == position.asset);
+            match existing {
+                Some(held) => held.amount = held.amount.safe_add(position.amount)?,
+                None => {
+                    if basket.len() >= MAX_BASKET_POSITIONS {
+                        return Err(LendingError::CollateralBasketFull);
+                    }
+                    basket.push(position);
+                }
+            }
+            Ok(())
+        }
+        
+        /// Take a basket from the caller: native positions must add up to the transferred value
+        fn receive_basket(&self, basket: &[CollateralPosition]) -> Result<(), LendingError> {
+            let mut native_total: Balance = 0;
+            for position in basket.iter().filter(|position| position.asset == Asset::Native) {
+                native_total = native_total.safe_add(position.amount)?;
+            }
+            if self.env().transferred_value() != native_total {
+                return Err(LendingError::InvalidAmount);
             }
             
-            self.liquidity_pools.insert(pool_id, &pool);
-            
-            self.env().emit_event(LiquidityProvided {
-                pool_id,
-                provider: caller,
-                amount,
-                pool_share: new_provider_share,
-                total_pool_liquidity: new_total_liquidity,
-            });
-            
+            for position in basket {
+                if let Asset::Psp22(token) = position.asset {
+                    self.pull_psp22(token, position.amount)?;
+                }
+            }
             Ok(())
         }
         
-        /// Claim rewards from a liquidity pool
+        /// Remove a loan's basket from escrow, returning its positions to be paid out
+        fn take_collateral_basket(&mut self, loan_id: u64) -> Result<Vec<CollateralPosition>, LendingError> {
+            let basket = self.collateral_baskets.get(loan_id).unwrap_or_default();
+            self.collateral_baskets.remove(loan_id);
+            for position in basket.iter() {
+                self.update_asset_totals(position.asset, |totals| {
+                    totals.escrowed_collateral = totals.escrowed_collateral.safe_sub(position.amount)?;
+                    Ok(())
+                })?;
+            }
+            Ok(basket)
+        }
+
+        // ============================================================================
+        // CREDIT TIERS
+        // ============================================================================
+        
+        /// Set the origination terms for borrowers of a risk level, optionally only for KYC-verified borrowers;
+        /// `None` removes the tier so the global minimum collateral ratio applies again (admin only)
         #[ink(message)]
-        pub fn claim_pool_rewards(&mut self, pool_id: u64) -> Result<Balance, LendingError> {
-            let caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
-            
-            // Check if pool is active
-            if pool.status != PoolStatus::Active {
-                return Err(LendingError::LoanNotActive);
+        pub fn set_credit_tier(
+            &mut self,
+            risk_level: RiskLevel,
+            kyc_verified: bool,
+            tier: Option<CreditTier>,
+        ) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
             }
             
-            // Find the provider
-            let provider_index = pool.liquidity_providers.iter().position(|p| p.account == caller)
-                .ok_or(LendingError::Unauthorized)?;
-            
-            let mut provider = pool.liquidity_providers[provider_index].clone();
-            let current_block = self.env().block_number() as u64;
-            
-            // Calculate rewards based on time and pool share
-            let blocks_since_last_claim = current_block - provider.last_reward_claim;
-            let reward_rate_per_block = pool.reward_rate as f64 / 10000.0 / 5184000.0; // Convert to per-block rate
-            let rewards = (provider.liquidity_provided as f64 * reward_rate_per_block * blocks_since_last_claim as f64) as u128;
-            
-            if rewards == 0 {
-                return Err(LendingError::InvalidAmount); // No rewards to claim
+            match tier.as_ref() {
+                Some(tier) => {
+                    if tier.max_loan_value == 0 {
+                        return Err(LendingError::InvalidAmount);
+                    }
+                    self.credit_tiers.insert((risk_level.clone(), kyc_verified), tier);
+                }
+                None => self.credit_tiers.remove((risk_level.clone(), kyc_verified)),
             }
             
-            // Update provider state
-            provider.rewards_earned += rewards;
-            provider.last_reward_claim = current_block;
-            
-            // Update pool state
-            pool.total_rewards_distributed += rewards;
-            pool.liquidity_providers[provider_index] = provider.clone();
-            
-            self.liquidity_pools.insert(pool_id, &pool);
-            
-            self.env().emit_event(RewardsDistributed {
-                pool_id,
-                provider: caller,
-                amount: rewards,
-                pool_share: provider.pool_share,
-                total_rewards: pool.total_rewards_distributed,
+            self.env().emit_event(CreditTierSet {
+                risk_level,
+                kyc_verified,
+                tier,
             });
             
-            Ok(rewards)
+            Ok(())
         }
         
-        /// Get liquidity pool information
+        /// Get the origination terms set for a risk level and KYC status
         #[ink(message)]
-        pub fn get_liquidity_pool_info(&self, pool_id: u64) -> Result<(String, Balance, u32, Balance, u16, u16, PoolStatus), LendingError> {
-            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
-            Ok((
-                pool.name.clone(),
-                pool.total_liquidity,
-                pool.active_loans,
-                pool.total_volume,
-                pool.pool_fee_rate,
-                pool.reward_rate,
-                pool.status.clone(),
-            ))
+        pub fn get_credit_tier(&self, risk_level: RiskLevel, kyc_verified: bool) -> Option<CreditTier> {
+            self.credit_tiers.get((risk_level, kyc_verified))
         }
         
-        /// Get liquidity provider information
+        /// Get the credit tier a borrower's new loans are originated under, `None` if the global minimum collateral ratio applies
         #[ink(message)]
-        pub fn get_liquidity_provider_info(&self, pool_id: u64, provider: AccountId) -> Result<(Balance, u16, Balance, u64), LendingError> {
-            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+        pub fn get_borrower_credit_tier(&self, borrower: AccountId) -> Option<CreditTier> {
+            self.borrower_credit_tier(borrower)
+        }
+        
+        /// Set the most principal in an asset that may be lent below the standard collateral ratio (admin only)
+        #[ink(message)]
+        pub fn set_unsecured_exposure_cap(&mut self, asset: Asset, cap: Balance) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
             
-            let provider_info = pool.liquidity_providers.iter()
-                .find(|p| p.account == provider)
-                .ok_or(LendingError::Unauthorized)?;
+            if !self.assets.contains(asset) {
+                return Err(LendingError::AssetNotRegistered);
+            }
             
-            Ok((
-                provider_info.liquidity_provided,
-                provider_info.pool_share,
-                provider_info.rewards_earned,
-                provider_info.last_reward_claim,
-            ))
+            self.unsecured_exposure_caps.insert(asset, &cap);
+            
+            Ok(())
         }
-
-        // ============================================================================
-        // QUERY OPERATIONS
-        // ============================================================================
         
-        /// Get loan information
+        /// Get the unsecured exposure cap of an asset, zero if no undercollateralized lending is allowed
+        #[ink(message)]
+        pub fn get_unsecured_exposure_cap(&self, asset: Asset) -> Balance {
+            self.unsecured_exposure_caps.get(asset).unwrap_or(0)
+        }
+        
+        /// Get the principal of a loan its collateral does not cover at the standard collateral ratio
+        #[ink(message)]
+        pub fn get_unsecured_principal(&self, loan_id: u64) -> Balance {
+            self.unsecured_principal.get(loan_id).unwrap_or(0)
+        }
+        
+        /// Tier for a borrower's scored risk level, preferring terms for KYC-verified borrowers when they qualify
+        fn borrower_credit_tier(&self, borrower: AccountId) -> Option<CreditTier> {
+            let risk_level = self.credit_scores.get(borrower)?.risk_level;
+            if self.has_verified_kyc(borrower) {
+                if let Some(tier) = self.credit_tiers.get((risk_level.clone(), true)) {
+                    return Some(tier);
+                }
+            }
+            self.credit_tiers.get((risk_level, false))
+        }
+        
+        /// Whether the user's latest KYC record is verified and not yet expired
+        fn has_verified_kyc(&self, user: AccountId) -> bool {
+            self.compliance_records.get(user).unwrap_or_default().iter().rev()
+                .find(|record| record.compliance_type == ComplianceType::KYC)
+                .is_some_and(|record| record.status == ComplianceStatus::Verified && record.expiry_date > self.now())
+        }
+        
+        /// Principal of a loan its collateral value does not cover at the standard collateral ratio
+        fn uncovered_principal(&self, amount: Balance, collateral_value: Balance) -> Result<Balance, LendingError> {
+            if collateral_value >= bps_mul(amount, self.min_collateral_ratio as u128, Rounding::Up)? {
+                return Ok(0);
+            }
+            let covered = mul_div(collateral_value, BPS, self.min_collateral_ratio as u128, Rounding::Down)?;
+            Ok(amount.saturating_sub(covered))
+        }
+        
+        /// Count a new loan's unsecured principal towards the asset's exposure, rejecting it past the cap
+        fn reserve_unsecured_exposure(&mut self, loan_id: u64, asset: Asset, unsecured: Balance) -> Result<(), LendingError> {
+            if unsecured == 0 {
+                return Ok(());
+            }
+            
+            let exposure = self.get_asset_totals(asset).unsecured_exposure.safe_add(unsecured)?;
+            if exposure > self.get_unsecured_exposure_cap(asset) {
+                return Err(LendingError::UnsecuredExposureCapExceeded);
+            }
+            
+            self.record_unsecured_exposure(loan_id, asset, unsecured)
+        }
+        
+        /// Record a loan's unsecured principal and add it to the asset's exposure
+        fn record_unsecured_exposure(&mut self, loan_id: u64, asset: Asset, unsecured: Balance) -> Result<(), LendingError> {
+            if unsecured == 0 {
+                return Ok(());
+            }
+            
+            self.unsecured_principal.insert(loan_id, &unsecured);
+            self.update_asset_totals(asset, |totals| {
+                totals.unsecured_exposure = totals.unsecured_exposure.safe_add(unsecured)?;
+                Ok(())
+            })
+        }
+        
+        /// Stop counting a closed loan's unsecured principal towards the asset's exposure
+        fn release_unsecured_exposure(&mut self, loan: &Loan) -> Result<(), LendingError> {
+            let unsecured = self.unsecured_principal.get(loan.id).unwrap_or(0);
+            if unsecured == 0 {
+                return Ok(());
+            }
+            
+            self.unsecured_principal.remove(loan.id);
+            self.update_asset_totals(loan.asset, |totals| {
+                totals.unsecured_exposure = totals.unsecured_exposure.safe_sub(unsecured)?;
+                Ok(())
+            })
+        }
+
+        // ============================================================================
+        // RISK-BASED PRICING
+        // ============================================================================
+        
+        /// Set the inputs of the minimum rate quoted for pool-funded loans (admin only)
+        #[ink(message)]
+        pub fn set_pricing_model(&mut self, model: PricingModel) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            let rates = [model.base_rate, model.ltv_premium, model.utilization_premium, model.excess_utilization_premium];
+            if model.optimal_utilization == 0 || rates.iter().chain([&model.optimal_utilization]).any(|&rate| rate as u128 > BPS) {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            self.pricing_model = model;
+            
+            Ok(())
+        }
+        
+        /// Get the inputs of the minimum rate quoted for pool-funded loans
+        #[ink(message)]
+        pub fn get_pricing_model(&self) -> PricingModel {
+            self.pricing_model.clone()
+        }
+        
+        /// Set the multiplier applied to rates quoted for borrowers of a risk level (admin only)
+        #[ink(message)]
+        pub fn set_risk_level_multiplier(&mut self, risk_level: RiskLevel, risk_multiplier: u16) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // Same bounds as per-loan risk multipliers (0.5x to 3.0x)
+            if !(500..=3000).contains(&risk_multiplier) {
+                return Err(LendingError::InvalidRiskMultiplier);
+            }
+            
+            self.risk_multipliers.insert(risk_level, &risk_multiplier);
+            
+            Ok(())
+        }
+        
+        /// Get the multiplier applied to rates quoted for borrowers of a risk level
+        #[ink(message)]
+        pub fn get_risk_level_multiplier(&self, risk_level: RiskLevel) -> u16 {
+            self.risk_multipliers.get(risk_level).unwrap_or(1000)
+        }
+        
+        /// Quote the caller's minimum rate over the term for a native loan, at the utilization of all native pools
+        #[ink(message)]
+        pub fn quote_loan(&self, amount: Balance, duration: u64, collateral: Balance) -> Result<u16, LendingError> {
+            let totals = self.get_asset_totals(Asset::Native);
+            self.quote_rate(self.env().caller(), amount, duration, collateral, totals.pool_borrowed, totals.pool_liquidity)
+        }
+        
+        /// Quote the caller's minimum rate over the term for a loan from a pool, with collateral in the pool's asset
+        #[ink(message)]
+        pub fn quote_pool_loan(&self, pool_id: u64, amount: Balance, duration: u64, collateral: Balance) -> Result<u16, LendingError> {
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            self.quote_rate(self.env().caller(), amount, duration, collateral, pool.total_borrowed, pool.total_liquidity)
+        }
+        
+        /// Borrow from a pool at a rate no lower than the quote, escrowing collateral in the pool's asset;
+        /// the pool funds the loan at once and repayments are credited back to it
+        #[ink(message, payable)]
+        pub fn create_pool_loan(
+            &mut self,
+            pool_id: u64,
+            amount: Balance,
+            interest_rate: u16,
+            duration: u64,
+            collateral: Balance,
+        ) -> Result<u64, LendingError> {
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            if pool.status != PoolStatus::Active {
+                return Err(LendingError::PoolNotActive);
+            }
+            
+            let quoted_rate = self.quote_pool_loan(pool_id, amount, duration, collateral)?;
+            if interest_rate < quoted_rate {
+                return Err(LendingError::InterestRateBelowQuote);
+            }
+            
+            let loan_id = self.create_loan_request(LoanRequest {
+                asset: pool.asset,
+                collateral_asset: pool.asset,
+                amount,
+                interest_rate,
+                duration,
+                collateral,
+                basket: Vec::new(),
+            })?;
+            self.lend_from_pool(pool_id, amount)?;
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            pool.active_loans = pool.active_loans.safe_add(1)?;
+            self.liquidity_pools.insert(pool_id, &pool);
+            
+            // The contract lends on the pool's behalf, so lender payouts are credited to the pool
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            loan.liquidity_pool_id = Some(pool_id);
+            self.record_contribution(&mut loan, self.env().account_id(), amount)?;
+            self.activate_funded_loan(&mut loan)?;
+            
+            self.env().emit_event(LoanPriced {
+                loan_id,
+                pool_id,
+                quoted_rate,
+                interest_rate,
+                risk_multiplier: loan.risk_multiplier,
+            });
+            
+            Ok(loan_id)
+        }
+        
+        /// Risk multiplier for a borrower's scored risk level, 1.0x for borrowers without a score
+        fn borrower_risk_multiplier(&self, borrower: AccountId) -> u16 {
+            match self.credit_scores.get(borrower) {
+                Some(credit_score) => self.get_risk_level_multiplier(credit_score.risk_level),
+                None => 1000,
+            }
+        }
+        
+        /// Minimum rate over the term: the model's annual rate at the loan's loan-to-value and the pool utilization
+        /// it would leave, scaled by the borrower's risk multiplier and prorated over the duration
+        fn quote_rate(
+            &self,
+            borrower: AccountId,
+            amount: Balance,
+            duration: u64,
+            collateral_value: Balance,
+            borrowed: Balance,
+            available: Balance,
+        ) -> Result<u16, LendingError> {
+            if amount == 0 || duration == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            let model = &self.pricing_model;
+            
+            // Loans at or above 100% loan-to-value, including unsecured ones, carry the full premium
+            let ltv = if collateral_value == 0 { BPS } else { bps_ratio(amount, collateral_value, Rounding::Up)?.min(BPS) };
+            let ltv_premium = mul_div(model.ltv_premium as u128, ltv, BPS, Rounding::Up)?;
+            
+            // Utilization is measured after the loan is lent out; a pool with no liquidity counts as fully utilized
+            let pool_size = borrowed.safe_add(available)?;
+            let utilization = if pool_size == 0 {
+                BPS
+            } else {
+                bps_ratio(borrowed.safe_add(amount)?, pool_size, Rounding::Up)?.min(BPS)
+            };
+            let optimal = model.optimal_utilization as u128;
+            let utilization_premium = if utilization <= optimal {
+                mul_div(model.utilization_premium as u128, utilization, optimal, Rounding::Up)?
+            } else {
+                (model.utilization_premium as u128)
+                    .safe_add(mul_div(model.excess_utilization_premium as u128, utilization.safe_sub(optimal)?, BPS.safe_sub(optimal)?, Rounding::Up)?)?
+            };
+            
+            let annual_rate = (model.base_rate as u128).safe_add(ltv_premium)?.safe_add(utilization_premium)?;
+            let annual_rate = mul_div(annual_rate, self.borrower_risk_multiplier(borrower) as u128, 1000, Rounding::Up)?;
+            let term_rate = mul_div(annual_rate, duration as u128, MILLIS_PER_YEAR as u128, Rounding::Up)?;
+            u16::try_from(term_rate).map_err(|_| LendingError::ArithmeticOverflow)
+        }
+
+        // ============================================================================
+        // DEFAULT DECLARATION
+        // ============================================================================
+        
+        /// Declare a loan in default once the default delay after its grace period has passed
+        #[ink(message)]
+        pub fn declare_default(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only a participating lender or a registered keeper can declare a default
+            let is_lender = loan.lender == Some(caller) || loan.lender_contributions.iter().any(|c| c.lender == caller);
+            if !is_lender && !self.keepers.get(caller).unwrap_or(false) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if !loan.status.allows(&LoanAction::DeclareDefault) {
+                return Err(LendingError::LoanNotActive);
+            }
+
+            let current_time = self.now();
+            let default_time = loan.due_date.safe_add(loan.grace_period)?.safe_add(self.default_delay_ms)?;
+            if current_time <= default_time {
+                return Err(LendingError::DefaultDelayNotElapsed);
+            }
+
+            self.accrue_loan_interest(&mut loan)?;
+
+            let lender = loan.lender.ok_or(LendingError::LoanNotActive)?;
+
+            let collateral_seized = self.escrowed_collateral.get(loan_id).unwrap_or(0);
+            self.escrowed_collateral.remove(loan_id);
+            self.update_asset_totals(loan.collateral_asset, |totals| {
+                totals.escrowed_collateral = totals.escrowed_collateral.safe_sub(collateral_seized)?;
+                Ok(())
+            })?;
+
+            // Guarantor covers the outstanding balance before collateral is seized
+            let (guarantee_called, guarantor_reimbursed) = self.call_guarantee(&mut loan, collateral_seized)?;
+
+            // Remaining escrowed collateral goes to the lender
+            let collateral_transferred = collateral_seized.safe_sub(guarantor_reimbursed)?;
+            let mut recovered_value = self.collateral_value(loan.collateral_asset, loan.asset, collateral_transferred)?;
+
+            // Basket positions go to the lenders as well
+            let basket = self.take_collateral_basket(loan_id)?;
+            for position in basket.iter() {
+                recovered_value = recovered_value.safe_add(self.collateral_value(position.asset, loan.asset, position.amount)?)?;
+            }
+            let collateral_applied = recovered_value.min(loan.remaining_balance);
+            self.allocate_payment(&mut loan, collateral_applied)?;
+            loan.remaining_balance = loan.remaining_balance.safe_sub(collateral_applied)?;
+            self.sync_collateral_requirements(&mut loan, 0);
+            self.transition_loan(&mut loan, LoanStatus::Defaulted, LoanAction::DeclareDefault)?;
+
+            self.loans.insert(loan_id, &loan);
+
+            self.distribute_asset_to_lenders(&loan, loan.collateral_asset, collateral_transferred)?;
+            for position in basket {
+                self.distribute_asset_to_lenders(&loan, position.asset, position.amount)?;
+            }
+
+            self.remove_from_active_loans(&loan);
+
+            // Record the default against the borrower
+            let mut borrower_profile = self.get_or_create_user_profile(loan.borrower);
+            borrower_profile.default_count = borrower_profile.default_count.safe_add(1)?;
+            self.user_profiles.insert(loan.borrower, &borrower_profile);
+
+            self.total_defaults = self.total_defaults.safe_add(1)?;
+            self.release_loan_liquidity(&loan)?;
+
+            self.env().emit_event(LoanDefaulted {
+                loan_id,
+                borrower: loan.borrower,
+                lender,
+                declared_by: caller,
+                guarantee_called,
+                collateral_transferred,
+                outstanding_balance: loan.remaining_balance,
+            });
+
+            Ok(())
+        }
+
+        /// Register or remove a keeper allowed to declare defaults (admin only)
+        #[ink(message)]
+        pub fn set_keeper(&mut self, keeper: AccountId, enabled: bool) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if enabled {
+                self.keepers.insert(keeper, &true);
+            } else {
+                self.keepers.remove(keeper);
+            }
+            
+            Ok(())
+        }
+
+        /// Set the time in ms after the grace period before a default can be declared (admin only)
+        #[ink(message)]
+        pub fn set_default_delay(&mut self, default_delay_ms: u64) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            self.default_delay_ms = default_delay_ms;
+            
+            Ok(())
+        }
+
+        /// Get the default delay in ms
+        #[ink(message)]
+        pub fn get_default_delay(&self) -> u64 {
+            self.default_delay_ms
+        }
+
+        /// Get total number of defaulted loans
+        #[ink(message)]
+        pub fn get_total_defaults(&self) -> u64 {
+            self.total_defaults
+        }
+
+        // ============================================================================
+        // GUARANTORS
+        // ============================================================================
+        
+        /// Lock a guarantor stake sent with the call against a loan
+        #[ink(message, payable)]
+        pub fn add_guarantee(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            if caller == loan.borrower {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if !loan.status.allows(&LoanAction::AddGuarantee) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            // Stakes are posted in the native currency and pay the loan down directly when called
+            self.require_native(loan.asset)?;
+            self.require_native(loan.collateral_asset)?;
+            
+            let stake = self.env().transferred_value();
+            if stake == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            // One guarantor per loan; the same guarantor may top up their stake
+            let guarantee = match self.loan_guarantees.get(loan_id) {
+                Some(mut existing) if existing.guarantor == caller && existing.status == GuaranteeStatus::Active => {
+                    existing.stake = existing.stake.safe_add(stake)?;
+                    existing
+                }
+                Some(_) => return Err(LendingError::InvalidStatus),
+                None => Guarantee {
+                    loan_id,
+                    guarantor: caller,
+                    stake,
+                    amount_called: 0,
+                    recovery_claim: 0,
+                    status: GuaranteeStatus::Active,
+                    created_at: self.now(),
+                },
+            };
+            self.loan_guarantees.insert(loan_id, &guarantee);
+            
+            self.env().emit_event(GuaranteeAdded {
+                loan_id,
+                guarantor: caller,
+                stake: guarantee.stake,
+            });
+            
+            Ok(())
+        }
+
+        /// Return a guarantor's stake once the loan has closed without default (guarantor only)
+        #[ink(message)]
+        pub fn release_guarantee(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let mut guarantee = self.loan_guarantees.get(loan_id).ok_or(LendingError::GuaranteeNotFound)?;
+            
+            if guarantee.guarantor != caller {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if guarantee.status != GuaranteeStatus::Active {
+                return Err(LendingError::InvalidStatus);
+            }
+            
+            if !loan.status.allows(&LoanAction::ReleaseGuarantee) {
+                return Err(LendingError::LoanStillOpen);
+            }
+            
+            let stake_returned = guarantee.stake;
+            guarantee.status = GuaranteeStatus::Released;
+            self.loan_guarantees.insert(loan_id, &guarantee);
+            
+            self.send_asset(Asset::Native, caller, stake_returned)?;
+            
+            self.env().emit_event(GuaranteeReleased {
+                loan_id,
+                guarantor: caller,
+                stake_returned,
+            });
+            
+            Ok(())
+        }
+
+        /// Repay a guarantor's recovery claim against the borrower of a defaulted loan
+        #[ink(message, payable)]
+        pub fn repay_guarantor(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let mut guarantee = self.loan_guarantees.get(loan_id).ok_or(LendingError::GuaranteeNotFound)?;
+            
+            if guarantee.status != GuaranteeStatus::Called {
+                return Err(LendingError::InvalidStatus);
+            }
+            
+            let amount = self.env().transferred_value();
+            if amount == 0 || amount > guarantee.recovery_claim {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            guarantee.recovery_claim = guarantee.recovery_claim.safe_sub(amount)?;
+            self.loan_guarantees.insert(loan_id, &guarantee);
+            
+            self.send_asset(Asset::Native, guarantee.guarantor, amount)?;
+            
+            self.env().emit_event(GuarantorRepaid {
+                loan_id,
+                guarantor: guarantee.guarantor,
+                amount,
+                recovery_claim: guarantee.recovery_claim,
+            });
+            
+            Ok(())
+        }
+
+        /// Get the guarantee posted for a loan
+        #[ink(message)]
+        pub fn get_loan_guarantee(&self, loan_id: u64) -> Option<Guarantee> {
+            self.loan_guarantees.get(loan_id)
+        }
+
+        // ============================================================================
+        // VARIABLE INTEREST RATE MANAGEMENT
+        // ============================================================================
+        
+        /// Adjust interest rate for a variable rate loan
+        #[ink(message)]
+        pub fn adjust_interest_rate(
+            &mut self,
+            loan_id: u64,
+            new_base_rate: u16,
+            reason: RateAdjustmentReason,
+        ) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only lender can adjust interest rates
+            if loan.lender != Some(caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // Check if loan supports variable rates
+            if loan.interest_rate_type != InterestRateType::Variable {
+                return Err(LendingError::NotVariableRate);
+            }
+
+            // Validate new rate
+            if new_base_rate == 0 || new_base_rate > 10000 {
+                return Err(LendingError::InvalidInterestRate);
+            }
+
+            let current_time = self.now();
+            
+            // Check update frequency
+            if current_time < loan.last_interest_update.safe_add(loan.interest_update_frequency)? {
+                return Err(LendingError::RateUpdateTooFrequent);
+            }
+
+            // Interest up to now accrues at the old rate
+            self.accrue_loan_interest(&mut loan)?;
+
+            let old_rate = loan.interest_rate;
+            let _old_base_rate = loan.base_interest_rate;
+            
+            // Calculate new effective rate with risk multiplier
+            let new_effective_rate = u16::try_from(mul_div(new_base_rate as u128, loan.risk_multiplier as u128, 1000, Rounding::Up)?).map_err(|_| LendingError::ArithmeticOverflow)?;
+            
+            // Record the adjustment
+            let adjustment = InterestRateAdjustment {
+                timestamp: current_time,
+                old_rate: old_rate,
+                new_rate: new_effective_rate,
+                reason: reason.clone(),
+                risk_score_change: None, // Will be updated if risk score changes
+            };
+
+            // Update loan with new rates
+            loan.base_interest_rate = new_base_rate;
+            loan.interest_rate = new_effective_rate;
+            loan.interest_rate_adjustments.push(adjustment);
+            loan.last_interest_update = current_time;
+
+            self.loans.insert(loan_id, &loan);
+
+            self.env().emit_event(InterestRateAdjusted {
+                loan_id,
+                borrower: loan.borrower,
+                old_rate,
+                new_rate: new_effective_rate,
+                reason,
+                risk_multiplier: loan.risk_multiplier,
+                effective_rate: new_effective_rate,
+            });
+
+            Ok(())
+        }
+
+        /// Update risk multiplier for a loan
+        #[ink(message)]
+        pub fn update_risk_multiplier(
+            &mut self,
+            loan_id: u64,
+            new_risk_multiplier: u16,
+        ) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only lender can update risk multipliers
+            if loan.lender != Some(caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // Validate risk multiplier (0.5x to 3.0x)
+            if new_risk_multiplier < 500 || new_risk_multiplier > 3000 {
+                return Err(LendingError::InvalidRiskMultiplier);
+            }
+
+            // Interest up to now accrues at the old rate
+            self.accrue_loan_interest(&mut loan)?;
+
+            let old_multiplier = loan.risk_multiplier;
+            let old_rate = loan.interest_rate;
+            
+            // Calculate new effective rate
+            let new_effective_rate = u16::try_from(mul_div(loan.base_interest_rate as u128, new_risk_multiplier as u128, 1000, Rounding::Up)?).map_err(|_| LendingError::ArithmeticOverflow)?;
+            
+            // Record the adjustment
+            let adjustment = InterestRateAdjustment {
+                timestamp: self.now(),
+                old_rate: old_rate,
+                new_rate: new_effective_rate,
+                reason: RateAdjustmentReason::RiskScoreChange,
+                risk_score_change: Some((new_risk_multiplier as i16).saturating_sub(old_multiplier as i16)),
+            };
+
+            // Update loan
+            loan.risk_multiplier = new_risk_multiplier;
+            loan.interest_rate = new_effective_rate;
+            loan.interest_rate_adjustments.push(adjustment);
+            loan.last_interest_update = self.now();
+
+            self.loans.insert(loan_id, &loan);
+
+            self.env().emit_event(InterestRateAdjusted {
+                loan_id,
+                borrower: loan.borrower,
+                old_rate,
+                new_rate: new_effective_rate,
+                reason: RateAdjustmentReason::RiskScoreChange,
+                risk_multiplier: new_risk_multiplier,
+                effective_rate: new_effective_rate,
+            });
+
+            Ok(())
+        }
+
+        /// Convert a fixed rate loan to variable rate
+        #[ink(message)]
+        pub fn convert_to_variable_rate(
+            &mut self,
+            loan_id: u64,
+            new_base_rate: u16,
+        ) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only lender can convert loan types
+            if loan.lender != Some(caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if !loan.status.allows(&LoanAction::ModifyTerms) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            // Check if loan is already variable
+            if loan.interest_rate_type == InterestRateType::Variable {
+                return Err(LendingError::AlreadyVariableRate);
+            }
+
+            // Validate new base rate
+            if new_base_rate == 0 || new_base_rate > 10000 {
+                return Err(LendingError::InvalidInterestRate);
+            }
+
+            // Interest up to now accrues at the old rate
+            self.accrue_loan_interest(&mut loan)?;
+
+            let old_rate = loan.interest_rate;
+            let new_effective_rate = u16::try_from(mul_div(new_base_rate as u128, loan.risk_multiplier as u128, 1000, Rounding::Up)?).map_err(|_| LendingError::ArithmeticOverflow)?;
+            
+            // Record the conversion
+            let adjustment = InterestRateAdjustment {
+                timestamp: self.now(),
+                old_rate: old_rate,
+                new_rate: new_effective_rate,
+                reason: RateAdjustmentReason::ManualAdjustment,
+                risk_score_change: None,
+            };
+
+            // Update loan
+            loan.interest_rate_type = InterestRateType::Variable;
+            loan.base_interest_rate = new_base_rate;
+            loan.interest_rate = new_effective_rate;
+            loan.interest_rate_adjustments.push(adjustment);
+            loan.last_interest_update = self.now();
+
+            self.loans.insert(loan_id, &loan);
+
+            self.env().emit_event(InterestRateAdjusted {
+                loan_id,
+                borrower: loan.borrower,
+                old_rate,
+                new_rate: new_effective_rate,
+                reason: RateAdjustmentReason::ManualAdjustment,
+                risk_multiplier: loan.risk_multiplier,
+                effective_rate: new_effective_rate,
+            });
+
+            Ok(())
+        }
+
+        // ============================================================================
+        // COMPOUND INTEREST CALCULATION
+        // ============================================================================
+        
+        /// Calculate and apply compound interest for a loan
+        #[ink(message)]
+        pub fn compound_interest(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only lender or borrower can compound interest
+            if loan.lender != Some(caller) && loan.borrower != caller {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // Check if loan supports compound interest
+            if loan.interest_type != InterestType::Compound {
+                return Err(LendingError::NotCompoundInterest);
+            }
+            
+            // Check if it's time to compound
+            let current_time = self.now();
+            if current_time < loan.last_compound_date.safe_add(loan.compound_period_ms)? {
+                return Err(LendingError::CompoundingTooEarly);
+            }
+            
+            // Calculate compound interest
+            let periods_since_last_compound = current_time.safe_sub(loan.last_compound_date)?.safe_div(loan.compound_period_ms)?;
+            if periods_since_last_compound == 0 {
+                return Err(LendingError::NothingToCompound);
+            }
+            
+            // Bring the balance up to date through the borrow index and capitalize the interest
+            self.accrue_loan_interest(&mut loan)?;
+            let interest_accrued = loan.accrued_interest;
+            loan.accrued_interest = 0; // Reset accrued interest
+            loan.total_compounded_interest = loan.total_compounded_interest.safe_add(interest_accrued)?;
+            loan.last_compound_date = current_time;
+            
+            self.loans.insert(loan_id, &loan);
+            
+            self.env().emit_event(InterestCompounded {
+                loan_id,
+                borrower: loan.borrower,
+                compound_period: periods_since_last_compound,
+                interest_accrued,
+                total_compounded: loan.total_compounded_interest,
+                new_remaining_balance: loan.remaining_balance,
+            });
+            
+            Ok(())
+        }
+        
+        /// Switch loan from simple to compound interest
+        #[ink(message)]
+        pub fn convert_to_compound_interest(
+            &mut self,
+            loan_id: u64,
+            frequency: CompoundFrequency,
+        ) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only lender can convert interest types
+            if loan.lender != Some(caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if !loan.status.allows(&LoanAction::ModifyTerms) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            // Check if loan is already compound
+            if loan.interest_type == InterestType::Compound {
+                return Err(LendingError::AlreadyCompoundInterest);
+            }
+            
+            // Set compound frequency and derive the period length
+            let compound_period_ms = frequency.period_ms();
+            
+            // Simple interest accrues up to the conversion, compounding applies from then on
+            self.accrue_loan_interest(&mut loan)?;
+            
+            // Convert loan to compound interest
+            loan.interest_type = InterestType::Compound;
+            loan.compound_frequency = frequency;
+            loan.compound_period_ms = compound_period_ms;
+            loan.last_compound_date = self.now();
+            loan.accrued_interest = 0;
+            loan.total_compounded_interest = 0;
+            
+            self.loans.insert(loan_id, &loan);
+            
+            Ok(())
+        }
+        
+        /// Get compound interest information for a loan
+        #[ink(message)]
+        pub fn get_compound_interest_info(&self, loan_id: u64) -> Result<(InterestType, CompoundFrequency, u64, Balance, Balance), LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok((
+                loan.interest_type.clone(),
+                loan.compound_frequency.clone(),
+                loan.compound_period_ms,
+                loan.accrued_interest,
+                loan.total_compounded_interest,
+            ))
+        }
+        
+        /// Calculate interest accrued since the last compounding, up to the current time
+        #[ink(message)]
+        pub fn calculate_accrued_interest(&self, loan_id: u64) -> Result<Balance, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let (pending_interest, _) = self.pending_interest(&loan)?;
+            loan.accrued_interest.safe_add(pending_interest)
+        }
+
+        // ============================================================================
+        // INTEREST ACCRUAL
+        // ============================================================================
+        
+        /// Accrue a loan's interest up to the current time (callable by anyone)
+        #[ink(message)]
+        pub fn accrue_interest(&mut self, loan_id: u64) -> Result<Balance, LendingError> {
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            if !loan.status.allows(&LoanAction::AccrueInterest) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            let interest = self.accrue_loan_interest(&mut loan)?;
+            self.loans.insert(loan_id, &loan);
+            
+            Ok(interest)
+        }
+        
+        /// Get a loan's borrow index as of the current time
+        #[ink(message)]
+        pub fn get_borrow_index(&self, loan_id: u64) -> Result<u128, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let (_, borrow_index) = self.pending_interest(&loan)?;
+            Ok(borrow_index)
+        }
+        
+        /// Get a loan's outstanding debt as of the current time
+        #[ink(message)]
+        pub fn get_current_debt(&self, loan_id: u64) -> Result<Balance, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.current_debt(&loan)
+        }
+        
+        /// Growth of one unit of debt over `elapsed` ms at a per-ms rate, and the borrow index it carries `borrow_index` to
+        fn interest_growth(
+            interest_type: &InterestType,
+            rate_per_ms: Ray,
+            elapsed: u64,
+            compound_period_ms: u64,
+            borrow_index: u128,
+        ) -> Result<(Ray, u128), LendingError> {
+            let borrow_index = Wad(borrow_index);
+            match interest_type {
+                InterestType::Simple => {
+                    let growth = rate_per_ms.checked_mul_int(elapsed as u128)?;
+                    Ok((growth, borrow_index.checked_add(growth.to_wad(Rounding::Up)?)?.0))
+                }
+                // Compounds once per full period, with simple growth over the partial period
+                InterestType::Compound => {
+                    let period = compound_period_ms.max(1);
+                    let period_rate = rate_per_ms.checked_mul_int(period as u128)?;
+                    let partial_rate = rate_per_ms.checked_mul_int((elapsed % period) as u128)?;
+                    let factor = Ray::ONE.checked_add(period_rate)?
+                        .checked_pow(elapsed / period, Rounding::Up)?
+                        .checked_mul(Ray::ONE.checked_add(partial_rate)?, Rounding::Up)?;
+                    let borrow_index = borrow_index.checked_mul(factor.to_wad(Rounding::Up)?, Rounding::Up)?;
+                    Ok((factor.checked_sub(Ray::ONE)?, borrow_index.0))
+                }
+            }
+        }
+        
+        /// Interest accrued since the last accrual and the borrow index it brings the loan to
+        fn pending_interest(&self, loan: &Loan) -> Result<(Balance, u128), LendingError> {
+            let is_accruing = loan.status.allows(&LoanAction::AccrueInterest);
+            let accrual_end = self.accrual_end(loan);
+            if !is_accruing || accrual_end <= loan.last_accrual || loan.duration == 0 {
+                return Ok((0, loan.borrow_index));
+            }
+            
+            // The interest rate applies over the loan term, spread evenly across its duration
+            let elapsed = accrual_end.safe_sub(loan.last_accrual)?;
+            let rate_per_ms = Ray::from_ratio(loan.interest_rate as u128, BPS * loan.duration as u128, Rounding::Up)?;
+            let (growth, borrow_index) = Self::interest_growth(&loan.interest_type, rate_per_ms, elapsed, loan.compound_period_ms, loan.borrow_index)?;
+            
+            let interest_bearing = match loan.interest_type {
+                // Simple interest accrues on the outstanding principal only
+                InterestType::Simple => loan.amount.safe_sub(loan.principal_repaid)?,
+                // Compound interest also accrues on unpaid interest, once per compounding period
+                InterestType::Compound => loan.remaining_balance.safe_sub(loan.total_late_fees.safe_sub(loan.late_fees_repaid)?)?,
+            };
+            
+            let interest = growth.mul_amount(interest_bearing, Rounding::Up)?;
+            Ok((interest, borrow_index))
+        }
+        
+        /// Book interest accrued since the last accrual into the loan's balance
+        fn accrue_loan_interest(&self, loan: &mut Loan) -> Result<Balance, LendingError> {
+            let (interest, borrow_index) = self.pending_interest(loan)?;
+            if interest == 0 {
+                return Ok(0); // Keep the accrual point so rounding does not drop interest
+            }
+            
+            loan.borrow_index = borrow_index;
+            loan.last_accrual = self.accrual_end(loan);
+            loan.remaining_balance = loan.remaining_balance.safe_add(interest)?;
+            loan.accrued_interest = loan.accrued_interest.safe_add(interest)?;
+            
+            self.env().emit_event(InterestAccrued {
+                loan_id: loan.id,
+                interest_accrued: interest,
+                borrow_index,
+                remaining_balance: loan.remaining_balance,
+            });
+            
+            Ok(interest)
+        }
+        
+        /// Interest accrues until the due date; overdue debt is charged late fees instead
+        fn accrual_end(&self, loan: &Loan) -> u64 {
+            self.now().min(loan.due_date)
+        }
+        
+        /// Outstanding debt including interest accrued up to the current time
+        fn current_debt(&self, loan: &Loan) -> Result<Balance, LendingError> {
+            let (interest, _) = self.pending_interest(loan)?;
+            loan.remaining_balance.safe_add(interest)
+        }
+
+        // ============================================================================
+        // INTEREST-ONLY PAYMENT PERIODS
+        // ============================================================================
+        
+        /// Set loan to interest-only payment structure for a specified number of periods
+        #[ink(message)]
+        pub fn set_interest_only_periods(
+            &mut self,
+            loan_id: u64,
+            periods: u32,
+            payment_period_ms: u64,
+        ) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only lender can set payment structure
+            if loan.lender != Some(caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // Check if loan is active
+            if !loan.status.allows(&LoanAction::ModifyTerms) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            // Validate payment period (minimum 1 day)
+            if payment_period_ms < MILLIS_PER_DAY {
+                return Err(LendingError::InvalidPaymentPeriod);
+            }
+            
+            // Set interest-only structure
+            loan.payment_structure = PaymentStructure::InterestOnly;
+            loan.interest_only_periods = periods;
+            loan.payment_period_ms = payment_period_ms;
+            loan.next_payment_due = self.now().safe_add(payment_period_ms)?;
+            loan.minimum_payment_amount = self.calculate_interest_payment(loan_id)?;
+            
+            self.loans.insert(loan_id, &loan);
+            
+            Ok(())
+        }
+        
+        /// Make an interest-only payment
+        #[ink(message, payable)]
+        pub fn make_interest_only_payment(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Check if caller is the borrower
+            if loan.borrower != caller {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            self.apply_interest_only_payment(loan, caller)
+        }
+        
+        /// Make an interest-only payment on the borrower's behalf
+        #[ink(message, payable)]
+        pub fn make_interest_only_payment_on_behalf(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            self.apply_interest_only_payment(loan, caller)
+        }
+        
+        /// Switch back to principal and interest payments
+        #[ink(message)]
+        pub fn switch_to_principal_and_interest(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only lender can change payment structure
+            if loan.lender != Some(caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // Check if loan is active
+            if !loan.status.allows(&LoanAction::ModifyTerms) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            // Switch to P&I structure
+            loan.payment_structure = PaymentStructure::PrincipalAndInterest;
+            loan.minimum_payment_amount = self.calculate_minimum_payment(loan_id)?;
+            
+            self.loans.insert(loan_id, &loan);
+            
+            Ok(())
+        }
+        
+        /// Get payment structure information for a loan
+        #[ink(message)]
+        pub fn get_payment_structure_info(&self, loan_id: u64) -> Result<(PaymentStructure, u32, u32, u32, u64, Balance), LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok((
+                loan.payment_structure.clone(),
+                loan.interest_only_periods,
+                loan.interest_only_periods_used,
+                loan.current_payment_period,
+                loan.next_payment_due,
+                loan.minimum_payment_amount,
+            ))
+        }
+        
+        /// Calculate interest payment for current period
+        fn calculate_interest_payment(&self, loan_id: u64) -> Result<Balance, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Principal × annual rate × fraction of a year
+            let interest = mul_div(
+                loan.amount,
+                loan.interest_rate as u128 * loan.payment_period_ms as u128,
+                BPS * MILLIS_PER_YEAR as u128,
+                Rounding::Up,
+            )?;
+            
+            Ok(interest)
+        }
+        
+        /// Calculate minimum payment for P&I structure
+        fn calculate_minimum_payment(&self, loan_id: u64) -> Result<Balance, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Simple P&I calculation: (Principal × Rate × Time) + (Principal / Total Periods)
+            let interest = mul_div(
+                loan.amount,
+                loan.interest_rate as u128 * loan.payment_period_ms as u128,
+                BPS * MILLIS_PER_YEAR as u128,
+                Rounding::Up,
+            )?;
+            let principal = loan.amount.safe_div(loan.duration.safe_div(loan.payment_period_ms)? as u128)?;
+            
+            interest.safe_add(principal)
+        }
+
+        // ============================================================================
+        // AMORTIZATION SCHEDULE
+        // ============================================================================
+        
+        /// Get the amortization schedule of a loan
+        #[ink(message)]
+        pub fn get_amortization_schedule(&self, loan_id: u64) -> Result<Vec<Installment>, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok(loan.amortization_schedule)
+        }
+        
+        /// Build equal installments from the start time to the due date covering the principal and the interest projected to accrue by then
+        fn generate_amortization_schedule(&self, loan: &mut Loan, start_time: u64, principal: Balance, total_interest: Balance) -> Result<(), LendingError> {
+            loan.amortization_schedule.clear();
+            
+            let term = loan.due_date.saturating_sub(start_time).max(1);
+            let periods = term.div_ceil(loan.payment_period_ms).max(1) as u32;
+            
+            let principal_per_period = principal.safe_div(periods as u128)?;
+            let interest_per_period = total_interest.safe_div(periods as u128)?;
+            let mut remaining_balance = loan.remaining_balance.safe_add(total_interest)?;
+            
+            for period in 1..=periods {
+                // Last installment absorbs rounding and falls on the due date
+                let (installment_principal, interest, due_date) = if period == periods {
+                    let earlier_periods = (periods - 1) as u128;
+                    (
+                        principal.safe_sub(principal_per_period.safe_mul(earlier_periods)?)?,
+                        total_interest.safe_sub(interest_per_period.safe_mul(earlier_periods)?)?,
+                        loan.due_date,
+                    )
+                } else {
+                    (principal_per_period, interest_per_period, start_time.safe_add((period as u64).safe_mul(loan.payment_period_ms)?)?)
+                };
+                remaining_balance = remaining_balance.saturating_sub(installment_principal.safe_add(interest)?);
+                
+                loan.amortization_schedule.push(Installment {
+                    period,
+                    due_date,
+                    principal: installment_principal,
+                    interest,
+                    remaining_balance,
+                    amount_paid: 0,
+                    status: InstallmentStatus::Pending,
+                });
+            }
+            
+            loan.current_payment_period = 0;
+            self.update_next_installment(loan)
+        }
+        
+        /// Mark pending installments past their due date plus grace period as missed
+        fn mark_missed_installments(&self, loan: &mut Loan, current_time: u64) -> Result<u32, LendingError> {
+            if loan.payment_structure != PaymentStructure::PrincipalAndInterest {
+                return Ok(0);
+            }
+            
+            let mut newly_missed: u32 = 0;
+            for installment in loan.amortization_schedule.iter_mut() {
+                if installment.status == InstallmentStatus::Pending && current_time > installment.due_date.safe_add(loan.grace_period)? {
+                    installment.status = InstallmentStatus::Missed;
+                    newly_missed = newly_missed.safe_add(1)?;
+                    
+                    self.env().emit_event(InstallmentMissed {
+                        loan_id: loan.id,
+                        borrower: loan.borrower,
+                        period: installment.period,
+                        due_date: installment.due_date,
+                        amount_due: Self::installment_amount_due(installment)?,
+                    });
+                }
+            }
+            
+            Ok(newly_missed)
+        }
+        
+        /// Apply a payment to unpaid installments in order
+        fn apply_payment_to_schedule(&self, loan: &mut Loan, payment_amount: Balance, current_time: u64) -> Result<(), LendingError> {
+            if loan.payment_structure != PaymentStructure::PrincipalAndInterest {
+                return Ok(());
+            }
+            
+            let mut remaining_payment = payment_amount;
+            for installment in loan.amortization_schedule.iter_mut() {
+                if remaining_payment == 0 {
+                    break;
+                }
+                if installment.status == InstallmentStatus::Paid || installment.status == InstallmentStatus::PaidLate {
+                    continue;
+                }
+                
+                let amount_due = Self::installment_amount_due(installment)?;
+                let applied = remaining_payment.min(amount_due);
+                installment.amount_paid = installment.amount_paid.safe_add(applied)?;
+                remaining_payment = remaining_payment.safe_sub(applied)?;
+                
+                if applied == amount_due {
+                    let paid_late = installment.status == InstallmentStatus::Missed || current_time > installment.due_date.safe_add(loan.grace_period)?;
+                    installment.status = if paid_late { InstallmentStatus::PaidLate } else { InstallmentStatus::Paid };
+                    
+                    self.env().emit_event(InstallmentPaid {
+                        loan_id: loan.id,
+                        period: installment.period,
+                        amount_paid: installment.amount_paid,
+                        paid_late,
+                    });
+                }
+            }
+            
+            self.update_next_installment(loan)
+        }
+        
+        /// Mark every outstanding installment paid when the loan is repaid in full
+        fn settle_amortization_schedule(&self, loan: &mut Loan, current_time: u64) -> Result<(), LendingError> {
+            let mut outstanding: Balance = 0;
+            for installment in loan.amortization_schedule.iter() {
+                outstanding = outstanding.safe_add(Self::installment_amount_due(installment)?)?;
+            }
+            self.apply_payment_to_schedule(loan, outstanding, current_time)
+        }
+        
+        /// Unpaid part of an installment
+        fn installment_amount_due(installment: &Installment) -> Result<Balance, LendingError> {
+            installment.principal.safe_add(installment.interest)?.safe_sub(installment.amount_paid)
+        }
+        
+        /// Advance the current payment period, next due date and minimum payment to the first unpaid installment
+        fn update_next_installment(&self, loan: &mut Loan) -> Result<(), LendingError> {
+            if loan.payment_structure != PaymentStructure::PrincipalAndInterest {
+                return Ok(());
+            }
+            
+            let completed = loan.amortization_schedule.iter()
+                .take_while(|i| i.status == InstallmentStatus::Paid || i.status == InstallmentStatus::PaidLate)
+                .count();
+            loan.current_payment_period = completed as u32;
+            
+            match loan.amortization_schedule.get(completed) {
+                Some(next) => {
+                    loan.next_payment_due = next.due_date;
+                    loan.minimum_payment_amount = Self::installment_amount_due(next)?;
+                }
+                None => loan.minimum_payment_amount = 0,
+            }
+            
+            Ok(())
+        }
+
+        // ============================================================================
+        // GRACE PERIOD MANAGEMENT
+        // ============================================================================
+        
+        /// Grant or extend grace period for a loan
+        #[ink(message)]
+        pub fn grant_grace_period(
+            &mut self,
+            loan_id: u64,
+            duration: u64,
+            reason: GracePeriodReason,
+        ) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only lender can grant grace periods
+            if loan.lender != Some(caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // Check if loan is active
+            if !loan.status.allows(&LoanAction::ModifyTerms) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            let grace_record = self.grace_period_record(duration, reason.clone(), loan.grace_period_extensions, loan.max_grace_period_extensions)?;
+            
+            // Calculate new grace period
+            let new_grace_period = loan.grace_period_ms.safe_add(duration)?;
+            let extension_number = grace_record.extension_number;
+            
+            // Update loan grace period
+            loan.grace_period_ms = new_grace_period;
+            loan.grace_period_extensions = extension_number;
+            loan.grace_period_reason = reason.clone();
+            
+            // Record grace period history
+            loan.grace_period_history.push(grace_record);
+            
+            self.loans.insert(loan_id, &loan);
+            
+            self.env().emit_event(GracePeriodGranted {
+                loan_id,
+                borrower: loan.borrower,
+                reason,
+                duration,
+                extension_number,
+                granted_by: caller,
+                total_grace_period: new_grace_period,
+            });
+            
+            Ok(())
+        }
+        
+        /// Validate a grace period extension and build its history record
+        fn grace_period_record(
+            &self,
+            duration: u64,
+            reason: GracePeriodReason,
+            extensions: u32,
+            max_extensions: u32,
+        ) -> Result<GracePeriodRecord, LendingError> {
+            // Validate grace period duration (minimum 10 minutes, maximum 1 day)
+            if duration < 600_000 || duration > MILLIS_PER_DAY {
+                return Err(LendingError::InvalidGracePeriod);
+            }
+            
+            // Check if grace period extensions are available
+            if extensions >= max_extensions {
+                return Err(LendingError::MaxGracePeriodsReached);
+            }
+            
+            Ok(GracePeriodRecord {
+                timestamp: self.now(),
+                reason,
+                duration,
+                extension_number: extensions.safe_add(1)?,
+                granted_by: self.env().caller(),
+            })
+        }
+        
+        /// Check if loan is within grace period
+        #[ink(message)]
+        pub fn is_within_grace_period(&self, loan_id: u64) -> Result<bool, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            let current_time = self.now();
+            let overdue_since = loan.overdue_since.unwrap_or(0);
+            
+            if overdue_since == 0 {
+                return Ok(false); // Not overdue
+            }
+            
+            let grace_period_end = overdue_since.safe_add(loan.grace_period_ms)?;
+            Ok(current_time <= grace_period_end)
+        }
+        
+        /// Get grace period information for a loan
+        #[ink(message)]
+        pub fn get_grace_period_info(&self, loan_id: u64) -> Result<(u64, u64, u32, u32, GracePeriodReason, Vec<GracePeriodRecord>), LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok((
+                loan.grace_period_ms,
+                loan.grace_period_used,
+                loan.grace_period_extensions,
+                loan.max_grace_period_extensions,
+                loan.grace_period_reason.clone(),
+                loan.grace_period_history.clone(),
+            ))
+        }
+        
+        /// Calculate remaining grace period for an overdue loan
+        #[ink(message)]
+        pub fn calculate_remaining_grace_period(&self, loan_id: u64) -> Result<u64, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            let current_time = self.now();
+            let overdue_since = loan.overdue_since.unwrap_or(0);
+            
+            if overdue_since == 0 {
+                return Ok(0); // Not overdue
+            }
+            
+            let grace_period_end = overdue_since.safe_add(loan.grace_period_ms)?;
+            if current_time > grace_period_end {
+                return Ok(0); // Grace period expired
+            }
+            
+            grace_period_end.safe_sub(current_time)
+        }
+        
+        /// Set custom grace period for a loan (lender only)
+        #[ink(message)]
+        pub fn set_custom_grace_period(
+            &mut self,
+            loan_id: u64,
+            grace_period_ms: u64,
+            max_extensions: u32,
+        ) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            
+            // Only lender can set custom grace periods
+            if loan.lender != Some(caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // Check if loan is active
+            if !loan.status.allows(&LoanAction::ModifyTerms) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            // Validate grace period (minimum 10 minutes, maximum 1 week)
+            if grace_period_ms < 600_000 || grace_period_ms > MILLIS_PER_WEEK {
+                return Err(LendingError::InvalidGracePeriod);
+            }
+            
+            // Update grace period settings
+            loan.grace_period_ms = grace_period_ms;
+            loan.max_grace_period_extensions = max_extensions;
+            
+            self.loans.insert(loan_id, &loan);
+            
+            Ok(())
+        }
+
+        // ============================================================================
+        // REVOLVING CREDIT LINES
+        // ============================================================================
+        
+        /// Open a credit line for a borrower, escrowing the whole limit from the caller as lender
+        #[ink(message, payable)]
+        pub fn open_credit_line(
+            &mut self,
+            borrower: AccountId,
+            asset: Asset,
+            limit: Balance,
+            terms: CreditLineTerms,
+        ) -> Result<u64, LendingError> {
+            let caller = self.env().caller();
+            if caller == borrower {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            let line = self.build_credit_line(borrower, asset, limit, terms)?;
+            
+            // The lender's limit is held in escrow and paid out as the borrower draws
+            self.receive_asset(asset, limit)?;
+            
+            Ok(self.store_credit_line(CreditLine { lender: Some(caller), ..line }))
+        }
+        
+        /// Open a credit line that draws from a liquidity pool (owner only)
+        #[ink(message)]
+        pub fn open_pool_credit_line(
+            &mut self,
+            pool_id: u64,
+            borrower: AccountId,
+            limit: Balance,
+            terms: CreditLineTerms,
+        ) -> Result<u64, LendingError> {
+            if self.env().caller() != self.owner {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            if pool.status != PoolStatus::Active {
+                return Err(LendingError::PoolNotActive);
+            }
+            
+            let line = self.build_credit_line(borrower, pool.asset, limit, terms)?;
+            
+            Ok(self.store_credit_line(CreditLine { liquidity_pool_id: Some(pool_id), ..line }))
+        }
+        
+        /// Draw funds from a credit line up to its limit (borrower only)
+        #[ink(message)]
+        pub fn draw(&mut self, line_id: u64, amount: Balance) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut line = self.credit_lines.get(line_id).ok_or(LendingError::CreditLineNotFound)?;
+            
+            if line.borrower != caller {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if line.status != CreditLineStatus::Active {
+                return Err(LendingError::CreditLineNotActive);
+            }
+            
+            if self.now() >= line.expires_at {
+                return Err(LendingError::CreditLineExpired);
+            }
+            
+            if amount == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            if line.drawn_principal.safe_add(amount)? > line.limit {
+                return Err(LendingError::CreditLimitExceeded);
+            }
+            
+            // Interest and commitment fees up to now are charged at the old drawn balance
+            self.accrue_line_charges(&mut line)?;
+            
+            // Pool-funded lines draw against the pool's free liquidity
+            if let Some(pool_id) = line.liquidity_pool_id {
+                self.lend_from_pool(pool_id, amount)?;
+            }
+            
+            line.drawn_principal = line.drawn_principal.safe_add(amount)?;
+            line.balance = line.balance.safe_add(amount)?;
+            line.total_drawn = line.total_drawn.safe_add(amount)?;
+            self.credit_lines.insert(line_id, &line);
+            
+            self.update_asset_totals(line.asset, |totals| {
+                totals.liquidity = totals.liquidity.safe_add(amount)?;
+                Ok(())
+            })?;
+            
+            self.send_asset(line.asset, caller, amount)?;
+            
+            self.env().emit_event(CreditLineDrawn {
+                line_id,
+                amount,
+                drawn_principal: line.drawn_principal,
+                balance: line.balance,
+            });
+            
+            Ok(())
+        }
+        
+        /// Repay part or all of a native credit line's balance with the value sent, for the borrower or on their behalf
+        #[ink(message, payable)]
+        pub fn repay_line(&mut self, line_id: u64) -> Result<(), LendingError> {
+            let line = self.credit_lines.get(line_id).ok_or(LendingError::CreditLineNotFound)?;
+            self.require_native(line.asset)?;
+            let amount = self.env().transferred_value();
+            self.apply_line_payment(line, amount)
+        }
+        
+        /// Repay an explicit amount of a credit line in any asset; PSP22 payments are pulled from the caller's allowance
+        #[ink(message, payable)]
+        pub fn repay_line_amount(&mut self, line_id: u64, amount: Balance) -> Result<(), LendingError> {
+            let line = self.credit_lines.get(line_id).ok_or(LendingError::CreditLineNotFound)?;
+            self.apply_line_payment(line, amount)
+        }
+        
+        /// Apply late fees to a credit line left unpaid past its expiry and grace period (callable by anyone)
+        #[ink(message)]
+        pub fn apply_line_late_fees(&mut self, line_id: u64) -> Result<(), LendingError> {
+            let mut line = self.credit_lines.get(line_id).ok_or(LendingError::CreditLineNotFound)?;
+            
+            self.accrue_line_charges(&mut line)?;
+            
+            let current_time = self.now();
+            let grace_period_end = line.expires_at.safe_add(line.grace_period_ms)?;
+            if line.balance == 0 || current_time <= grace_period_end {
+                return Err(LendingError::LoanNotOverdue);
+            }
+            
+            let overdue_ms = current_time.safe_sub(grace_period_end)?;
+            let late_fees = Self::late_fee(line.balance, overdue_ms, line.late_fee_rate, line.max_late_fee_rate)?;
+            
+            if late_fees > 0 {
+                line.total_late_fees = line.total_late_fees.safe_add(late_fees)?;
+                line.balance = line.balance.safe_add(late_fees)?;
+                
+                // An unpaid expired line stops any remaining draws
+                line.status = CreditLineStatus::Overdue;
+                line.overdue_since = Some(grace_period_end);
+                
+                self.env().emit_event(CreditLineLateFees {
+                    line_id,
+                    overdue_ms,
+                    late_fees_added: late_fees,
+                    balance: line.balance,
+                });
+            }
+            
+            self.credit_lines.insert(line_id, &line);
+            
+            Ok(())
+        }
+        
+        /// Extend the grace period after a credit line's expiry (lender, or owner for pool lines)
+        #[ink(message)]
+        pub fn grant_line_grace_period(
+            &mut self,
+            line_id: u64,
+            duration: u64,
+            reason: GracePeriodReason,
+        ) -> Result<(), LendingError> {
+            let mut line = self.credit_lines.get(line_id).ok_or(LendingError::CreditLineNotFound)?;
+            
+            if !self.is_line_funder(&line, self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            let grace_record = self.grace_period_record(duration, reason.clone(), line.grace_period_extensions, line.max_grace_period_extensions)?;
+            let extension_number = grace_record.extension_number;
+            
+            line.grace_period_ms = line.grace_period_ms.safe_add(duration)?;
+            line.grace_period_extensions = extension_number;
+            line.grace_period_history.push(grace_record);
+            
+            self.credit_lines.insert(line_id, &line);
+            
+            self.env().emit_event(CreditLineGracePeriodGranted {
+                line_id,
+                reason,
+                duration,
+                extension_number,
+                total_grace_period: line.grace_period_ms,
+            });
+            
+            Ok(())
+        }
+        
+        /// Close a credit line to further draws, returning the undrawn limit to its lender (borrower, lender or owner for pool lines)
+        #[ink(message)]
+        pub fn close_credit_line(&mut self, line_id: u64) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut line = self.credit_lines.get(line_id).ok_or(LendingError::CreditLineNotFound)?;
+            
+            if caller != line.borrower && !self.is_line_funder(&line, caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if line.status == CreditLineStatus::Closed {
+                return Err(LendingError::CreditLineNotActive);
+            }
+            
+            self.accrue_line_charges(&mut line)?;
+            line.status = CreditLineStatus::Closed;
+            self.credit_lines.insert(line_id, &line);
+            
+            // Repaid principal stays in escrow while the line revolves; only the undrawn part goes back on close
+            let released_liquidity = line.limit.safe_sub(line.drawn_principal)?;
+            if let Some(lender) = line.lender {
+                self.send_asset(line.asset, lender, released_liquidity)?;
+            }
+            
+            self.env().emit_event(CreditLineClosed {
+                line_id,
+                closed_by: caller,
+                released_liquidity,
+                balance: line.balance,
+            });
+            
+            Ok(())
+        }
+        
+        /// Get a credit line
+        #[ink(message)]
+        pub fn get_credit_line(&self, line_id: u64) -> Option<CreditLine> {
+            self.credit_lines.get(line_id)
+        }
+        
+        /// Get a credit line's outstanding balance including interest and commitment fees up to the current time
+        #[ink(message)]
+        pub fn get_credit_line_debt(&self, line_id: u64) -> Result<Balance, LendingError> {
+            let line = self.credit_lines.get(line_id).ok_or(LendingError::CreditLineNotFound)?;
+            let (interest, commitment_fee, _) = self.pending_line_charges(&line)?;
+            line.balance.safe_add(interest)?.safe_add(commitment_fee)
+        }
+        
+        /// Get the total number of credit lines opened
+        #[ink(message)]
+        pub fn get_total_credit_lines(&self) -> u64 {
+            self.total_credit_lines
+        }
+        
+        /// Validate terms and build a new credit line
+        fn build_credit_line(&self, borrower: AccountId, asset: Asset, limit: Balance, terms: CreditLineTerms) -> Result<CreditLine, LendingError> {
+            if limit == 0 || terms.duration == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            if terms.interest_rate > 10000 || terms.commitment_fee_rate > terms.interest_rate { // Max 100%, fee below the drawn rate
+                return Err(LendingError::InvalidInterestRate);
+            }
+            
+            if terms.duration > MILLIS_PER_YEAR { // Max 1 year
+                return Err(LendingError::InvalidDuration);
+            }
+            
+            if terms.late_fee_rate > terms.max_late_fee_rate || terms.max_late_fee_rate > 10000 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            // Same bounds as a granted grace period (minimum 10 minutes, maximum 1 day)
+            if terms.grace_period_ms < 600_000 || terms.grace_period_ms > MILLIS_PER_DAY {
+                return Err(LendingError::InvalidGracePeriod);
+            }
+            
+            self.require_enabled_asset(asset)?;
+            if self.get_or_create_user_profile(borrower).is_blacklisted {
+                return Err(LendingError::UserBlacklisted);
+            }
+            
+            let current_time = self.now();
+            Ok(CreditLine {
+                id: self.total_credit_lines.safe_add(1)?,
+                borrower,
+                lender: None,
+                liquidity_pool_id: None,
+                asset,
+                limit,
+                drawn_principal: 0,
+                balance: 0,
+                interest_rate: terms.interest_rate,
+                interest_type: terms.interest_type,
+                compound_frequency: CompoundFrequency::Daily, // Default to daily compounding
+                compound_period_ms: MILLIS_PER_DAY,
+                borrow_index: BORROW_INDEX_SCALE,
+                commitment_fee_rate: terms.commitment_fee_rate,
+                last_accrual: current_time,
+                total_drawn: 0,
+                total_interest: 0,
+                total_commitment_fees: 0,
+                late_fee_rate: terms.late_fee_rate,
+                max_late_fee_rate: terms.max_late_fee_rate,
+                total_late_fees: 0,
+                late_fees_repaid: 0,
+                grace_period_ms: terms.grace_period_ms,
+                grace_period_extensions: 0,
+                max_grace_period_extensions: terms.max_grace_period_extensions,
+                grace_period_history: Vec::new(),
+                opened_at: current_time,
+                expires_at: current_time.safe_add(terms.duration)?,
+                overdue_since: None,
+                status: CreditLineStatus::Active,
+            })
+        }
+        
+        /// Store a newly opened credit line and return its id
+        fn store_credit_line(&mut self, line: CreditLine) -> u64 {
+            self.credit_lines.insert(line.id, &line);
+            self.total_credit_lines = line.id;
+            
+            self.env().emit_event(CreditLineOpened {
+                line_id: line.id,
+                borrower: line.borrower,
+                lender: line.lender,
+                liquidity_pool_id: line.liquidity_pool_id,
+                asset: line.asset,
+                limit: line.limit,
+                expires_at: line.expires_at,
+            });
+            
+            line.id
+        }
+        
+        /// Whether an account funds a credit line: its lender, or the owner for pool-funded lines
+        fn is_line_funder(&self, line: &CreditLine, account: AccountId) -> bool {
+            match line.lender {
+                Some(lender) => lender == account,
+                None => account == self.owner,
+            }
+        }
+        
+        /// Interest on the drawn balance and commitment fee on the undrawn limit since the last accrual, and the borrow index it brings the line to
+        fn pending_line_charges(&self, line: &CreditLine) -> Result<(Balance, Balance, u128), LendingError> {
+            // Like loans, the line stops accruing at expiry; unpaid balances are charged late fees instead
+            let accrual_end = self.now().min(line.expires_at);
+            if line.status == CreditLineStatus::Overdue || accrual_end <= line.last_accrual {
+                return Ok((0, 0, line.borrow_index));
+            }
+            
+            // Rates are annual on credit lines since a line has no fixed term to spread them over
+            let elapsed = accrual_end.safe_sub(line.last_accrual)?;
+            let rate_per_ms = Ray::from_ratio(line.interest_rate as u128, BPS * MILLIS_PER_YEAR as u128, Rounding::Up)?;
+            let (growth, borrow_index) = Self::interest_growth(&line.interest_type, rate_per_ms, elapsed, line.compound_period_ms, line.borrow_index)?;
+            
+            let interest_bearing = match line.interest_type {
+                InterestType::Simple => line.drawn_principal,
+                InterestType::Compound => line.balance.safe_sub(line.total_late_fees.safe_sub(line.late_fees_repaid)?)?,
+            };
+            let interest = growth.mul_amount(interest_bearing, Rounding::Up)?;
+            
+            // Closed lines no longer commit the undrawn limit
+            let commitment_fee = if line.status == CreditLineStatus::Active {
+                let undrawn = line.limit.safe_sub(line.drawn_principal)?;
+                mul_div(undrawn, line.commitment_fee_rate as u128 * elapsed as u128, BPS * MILLIS_PER_YEAR as u128, Rounding::Up)?
+            } else {
+                0
+            };
+            
+            Ok((interest, commitment_fee, borrow_index))
+        }
+        
+        /// Book interest and commitment fees accrued since the last accrual into the line's balance
+        fn accrue_line_charges(&self, line: &mut CreditLine) -> Result<(), LendingError> {
+            let (interest, commitment_fee, borrow_index) = self.pending_line_charges(line)?;
+            if interest == 0 && commitment_fee == 0 {
+                return Ok(()); // Keep the accrual point so rounding does not drop charges
+            }
+            
+            line.borrow_index = borrow_index;
+            line.last_accrual = self.now().min(line.expires_at);
+            line.balance = line.balance.safe_add(interest)?.safe_add(commitment_fee)?;
+            line.total_interest = line.total_interest.safe_add(interest)?;
+            line.total_commitment_fees = line.total_commitment_fees.safe_add(commitment_fee)?;
+            
+            Ok(())
+        }
+        
+        /// Take a payment on a credit line through the late fee, interest and principal waterfall; repaid principal can be drawn again
+        fn apply_line_payment(&mut self, mut line: CreditLine, amount: Balance) -> Result<(), LendingError> {
+            let payer = self.env().caller();
+            let line_id = line.id;
+            
+            if amount == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            self.accrue_line_charges(&mut line)?;
+            if amount > line.balance {
+                return Err(LendingError::PaymentExceedsBalance);
+            }
+            self.receive_asset(line.asset, amount)?;
+            
+            // Interest here covers both drawn interest and commitment fees
+            let late_fees_due = line.total_late_fees.safe_sub(line.late_fees_repaid)?;
+            let interest_due = line.balance.safe_sub(line.drawn_principal)?.safe_sub(late_fees_due)?;
+            let late_fees_paid = amount.min(late_fees_due);
+            let interest_paid = amount.safe_sub(late_fees_paid)?.min(interest_due);
+            let principal_paid = amount.safe_sub(late_fees_paid)?.safe_sub(interest_paid)?;
+            
+            line.late_fees_repaid = line.late_fees_repaid.safe_add(late_fees_paid)?;
+            line.drawn_principal = line.drawn_principal.safe_sub(principal_paid)?;
+            line.balance = line.balance.safe_sub(amount)?;
+            self.credit_lines.insert(line_id, &line);
+            
+            self.update_asset_totals(line.asset, |totals| {
+                totals.liquidity = totals.liquidity.safe_sub(principal_paid)?;
+                Ok(())
+            })?;
+            
+            // Fees and interest go to the funder net of the protocol fee; principal refills the escrow until the line is closed
+            let protocol_fee = self.collect_protocol_fee(line.asset, interest_paid, FeeSource::Interest)?;
+            let earnings = amount.safe_sub(principal_paid)?.safe_sub(protocol_fee)?;
+            match (line.lender, line.liquidity_pool_id) {
+                (Some(lender), _) => {
+                    let returned_principal = if line.status == CreditLineStatus::Closed { principal_paid } else { 0 };
+                    self.send_asset(line.asset, lender, earnings.safe_add(returned_principal)?)?;
+                }
+                (None, Some(pool_id)) => {
+                    self.credit_pool(pool_id, earnings.safe_add(principal_paid)?, principal_paid)?;
+                }
+                (None, None) => {}
+            }
+            
+            self.env().emit_event(CreditLineRepaid {
+                line_id,
+                payer,
+                amount,
+                late_fees_paid,
+                interest_paid,
+                principal_paid,
+                balance: line.balance,
+            });
+            
+            Ok(())
+        }
+
+        // ============================================================================
+        // LIQUIDITY POOL MANAGEMENT
+        // ============================================================================
+        
+        /// Create a new liquidity pool in the native currency
+        #[ink(message)]
+        pub fn create_liquidity_pool(
+            &mut self,
+            name: String,
+            initial_liquidity: Balance,
+            pool_fee_rate: u16,
+            reward_rate: u16,
+            min_liquidity: Balance,
+            max_liquidity: Balance,
+        ) -> Result<u64, LendingError> {
+            self.create_asset_liquidity_pool(Asset::Native, PoolConfig {
+                name,
+                initial_liquidity,
+                pool_fee_rate,
+                reward_rate,
+                min_liquidity,
+                max_liquidity,
+            })
+        }
+        
+        /// Create a new liquidity pool denominated in a registered asset
+        #[ink(message)]
+        pub fn create_asset_liquidity_pool(&mut self, asset: Asset, config: PoolConfig) -> Result<u64, LendingError> {
+            let caller = self.env().caller();
+            let PoolConfig { name, initial_liquidity, pool_fee_rate, reward_rate, min_liquidity, max_liquidity } = config;
+            
+            // Validate parameters
+            if initial_liquidity == 0 || pool_fee_rate > 1000 || reward_rate > 1000 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            if min_liquidity >= max_liquidity {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            self.require_enabled_asset(asset)?;
+            
+            let pool_id = self.total_pools.safe_add(1)?;
+            let current_time = self.now();
+            
+            // Create liquidity pool
+            let pool = LiquidityPool {
+                id: pool_id,
+                name: name.clone(),
+                asset,
+                total_liquidity: initial_liquidity,
+                total_borrowed: 0,
+                active_loans: 0,
+                total_volume: 0,
+                pool_fee_rate,
+                reward_rate,
+                min_liquidity,
+                max_liquidity,
+                created_at: current_time,
+                status: PoolStatus::Active,
+                liquidity_providers: Vec::new(),
+                total_rewards_distributed: 0,
+                performance_score: 5000, // Default: 50% performance score
+                last_rebalance: current_time,
+                rebalance_frequency: MILLIS_PER_DAY, // Default: daily rebalancing
+                target_liquidity_ratio: 8000, // Default: 80% target liquidity ratio
+                current_liquidity_ratio: 10000, // Initial: 100% current ratio
+                rebalance_threshold: 500, // Default: 5% threshold for rebalancing
+                auto_rebalance_enabled: true, // Default: auto-rebalancing enabled
+                yield_farming_enabled: false, // Default: yield farming disabled
+                reward_tokens: Vec::new(), // No reward tokens initially
+                staking_requirements: StakingRequirements {
+                    min_stake_amount: 1000, // Minimum 1000 tokens to stake
+                    lock_period: MILLIS_PER_DAY, // 1 day lock period
+                    early_unstake_penalty: 500, // 5% penalty for early unstaking
+                    max_stake_amount: 100000, // Maximum 100,000 tokens to stake
+                },
+                tier_multipliers: vec![
+                    TierMultiplier {
+                        tier_name: "Bronze".to_string(),
+                        min_stake_amount: 1000,
+                        multiplier: 1000, // 1x multiplier
+                        bonus_rewards: 0,
+                    },
+                    TierMultiplier {
+                        tier_name: "Silver".to_string(),
+                        min_stake_amount: 5000,
+                        multiplier: 1200, // 1.2x multiplier
+                        bonus_rewards: 100,
+                    },
+                    TierMultiplier {
+                        tier_name: "Gold".to_string(),
+                        min_stake_amount: 20000,
+                        multiplier: 1500, // 1.5x multiplier
+                        bonus_rewards: 300,
+                    },
+                    TierMultiplier {
+                        tier_name: "Platinum".to_string(),
+                        min_stake_amount: 50000,
+                        multiplier: 2000, // 2x multiplier
+                        bonus_rewards: 500,
+                    },
+                ],
+                total_staked_tokens: 0,
+                market_depth_levels: vec![
+                    MarketDepthLevel {
+                        price_level: 950, // 95% price level
+                        liquidity_available: 0,
+                        order_count: 0,
+                        last_updated: current_time,
+                    },
+                    MarketDepthLevel {
+                        price_level: 1000, // 100% price level
+                        liquidity_available: 0,
+                        order_count: 0,
+                        last_updated: current_time,
+                    },
+                    MarketDepthLevel {
+                        price_level: 1050, // 105% price level
+                        liquidity_available: 0,
+                        order_count: 0,
+                        last_updated: current_time,
+                    },
+                ],
+                optimal_distribution: OptimalDistribution {
+                    target_depth_spread: 200, // 2% spread across levels
+                    min_depth_per_level: 1000, // Minimum 1,000 per level
+                    max_depth_per_level: 50000, // Maximum 50,000 per level
+                    rebalancing_threshold: 300, // 3% threshold for rebalancing
+                    auto_optimization_enabled: true,
+                },
+                depth_based_pricing: false, // Default: disabled
+                concentration_limits: ConcentrationLimits {
+                    max_single_pool_concentration: 8000, // Max 80% in single pool
+                    max_provider_concentration: 5000, // Max 50% per provider
+                    min_pool_diversity: 2, // Minimum 2 pools
+                    concentration_check_frequency: MILLIS_PER_DAY, // Check daily
+                },
+            };
+            
+            // Add creator as first liquidity provider
+            let creator_share = 10000; // 100% initially
+            let creator_provider = LiquidityProvider {
+                account: caller,
+                liquidity_provided: initial_liquidity,
+                pool_share: creator_share,
+                rewards_earned: 0,
+                joined_at: current_time,
+                last_reward_claim: current_time,
+            };
+            
+            let mut pool_with_provider = pool.clone();
+            pool_with_provider.liquidity_providers.push(creator_provider);
+            
+            // Store pool and update state
+            self.liquidity_pools.insert(pool_id, &pool_with_provider);
+            self.pool_liquidity_providers.insert(pool_id, &vec![caller]);
+            self.total_pools = pool_id;
+            self.update_asset_totals(asset, |totals| {
+                totals.pool_liquidity = totals.pool_liquidity.safe_add(initial_liquidity)?;
+                Ok(())
+            })?;
+            
+            self.env().emit_event(LiquidityPoolCreated {
+                pool_id,
+                name,
+                creator: caller,
+                initial_liquidity,
+                pool_fee_rate,
+                reward_rate,
+            });
+            
+            Ok(pool_id)
+        }
+        
+        /// Provide liquidity to a pool
+        #[ink(message)]
+        pub fn provide_liquidity(&mut self, pool_id: u64, amount: Balance) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            
+            // Check if pool is active
+            if pool.status != PoolStatus::Active {
+                return Err(LendingError::PoolNotActive);
+            }
+            
+            // Validate amount
+            if amount == 0 || amount < pool.min_liquidity || pool.total_liquidity.safe_add(amount)? > pool.max_liquidity {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            self.require_enabled_asset(pool.asset)?;
+            
+            // Calculate new pool share
+            let new_total_liquidity = pool.total_liquidity.safe_add(amount)?;
+            let new_provider_share = u16::try_from(bps_ratio(amount, new_total_liquidity, Rounding::Down)?).map_err(|_| LendingError::ArithmeticOverflow)?;
+            
+            // Check if provider already exists
+            let existing_provider_index = pool.liquidity_providers.iter().position(|p| p.account == caller);
+            
+            if let Some(index) = existing_provider_index {
+                // Update existing provider
+                let mut provider = pool.liquidity_providers[index].clone();
+                provider.liquidity_provided = provider.liquidity_provided.safe_add(amount)?;
+                provider.pool_share = u16::try_from(bps_ratio(provider.liquidity_provided, new_total_liquidity, Rounding::Down)?).map_err(|_| LendingError::ArithmeticOverflow)?;
+                pool.liquidity_providers[index] = provider;
+            } else {
+                // Add new provider
+                let new_provider = LiquidityProvider {
+                    account: caller,
+                    liquidity_provided: amount,
+                    pool_share: new_provider_share,
+                    rewards_earned: 0,
+                    joined_at: self.now(),
+                    last_reward_claim: self.now(),
+                };
+                pool.liquidity_providers.push(new_provider);
+            }
+            
+            // Update pool state
+            pool.total_liquidity = new_total_liquidity;
+            self.update_asset_totals(pool.asset, |totals| {
+                totals.pool_liquidity = totals.pool_liquidity.safe_add(amount)?;
+                Ok(())
+            })?;
+            
+            // Update provider shares for all providers
+            for provider in &mut pool.liquidity_providers {
+                provider.pool_share = u16::try_from(bps_ratio(provider.liquidity_provided, new_total_liquidity, Rounding::Down)?).map_err(|_| LendingError::ArithmeticOverflow)?;
+            }
+            
+            self.liquidity_pools.insert(pool_id, &pool);
+            
+            self.env().emit_event(LiquidityProvided {
+                pool_id,
+                provider: caller,
+                amount,
+                pool_share: new_provider_share,
+                total_pool_liquidity: new_total_liquidity,
+            });
+            
+            Ok(())
+        }
+        
+        /// Claim rewards from a liquidity pool
+        #[ink(message)]
+        pub fn claim_pool_rewards(&mut self, pool_id: u64) -> Result<Balance, LendingError> {
+            let caller = self.env().caller();
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            
+            // Check if pool is active
+            if pool.status != PoolStatus::Active {
+                return Err(LendingError::PoolNotActive);
+            }
+            
+            // Find the provider
+            let provider_index = pool.liquidity_providers.iter().position(|p| p.account == caller)
+                .ok_or(LendingError::ProviderNotFound)?;
+            
+            let mut provider = pool.liquidity_providers[provider_index].clone();
+            let current_time = self.now();
+            
+            // Calculate rewards based on time and pool share
+            let time_since_last_claim = current_time.safe_sub(provider.last_reward_claim)?;
+            let reward_rate_per_ms = Ray::from_ratio(pool.reward_rate as u128, BPS * MILLIS_PER_YEAR as u128, Rounding::Down)?; // Convert annual rate to per-ms rate
+            let rewards = reward_rate_per_ms
+                .checked_mul_int(time_since_last_claim as u128)?
+                .mul_amount(provider.liquidity_provided, Rounding::Down)?;
+            
+            if rewards == 0 {
+                return Err(LendingError::NoRewardsToClaim);
+            }
+            
+            // Update provider state
+            provider.rewards_earned = provider.rewards_earned.safe_add(rewards)?;
+            provider.last_reward_claim = current_time;
+            
+            // Update pool state
+            pool.total_rewards_distributed = pool.total_rewards_distributed.safe_add(rewards)?;
+            pool.liquidity_providers[provider_index] = provider.clone();
+            
+            self.liquidity_pools.insert(pool_id, &pool);
+            
+            self.env().emit_event(RewardsDistributed {
+                pool_id,
+                provider: caller,
+                amount: rewards,
+                pool_share: provider.pool_share,
+                total_rewards: pool.total_rewards_distributed,
+            });
+            
+            Ok(rewards)
+        }
+        
+        /// Get liquidity pool information
+        #[ink(message)]
+        pub fn get_liquidity_pool_info(&self, pool_id: u64) -> Result<(String, Balance, u32, Balance, u16, u16, PoolStatus), LendingError> {
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            Ok((
+                pool.name.clone(),
+                pool.total_liquidity,
+                pool.active_loans,
+                pool.total_volume,
+                pool.pool_fee_rate,
+                pool.reward_rate,
+                pool.status.clone(),
+            ))
+        }
+        
+        /// Get liquidity provider information
+        #[ink(message)]
+        pub fn get_liquidity_provider_info(&self, pool_id: u64, provider: AccountId) -> Result<(Balance, u16, Balance, u64), LendingError> {
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            
+            let provider_info = pool.liquidity_providers.iter()
+                .find(|p| p.account == provider)
+                .ok_or(LendingError::ProviderNotFound)?;
+            
+            Ok((
+                provider_info.liquidity_provided,
+                provider_info.pool_share,
+                provider_info.rewards_earned,
+                provider_info.last_reward_claim,
+            ))
+        }
+
+        /// Lend an amount out of a pool's free liquidity
+        fn lend_from_pool(&mut self, pool_id: u64, amount: Balance) -> Result<(), LendingError> {
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            if pool.status != PoolStatus::Active {
+                return Err(LendingError::PoolNotActive);
+            }
+            if amount > pool.total_liquidity {
+                return Err(LendingError::InsufficientLiquidity);
+            }
+            
+            pool.total_liquidity = pool.total_liquidity.safe_sub(amount)?;
+            pool.total_borrowed = pool.total_borrowed.safe_add(amount)?;
+            pool.total_volume = pool.total_volume.safe_add(amount)?;
+            self.liquidity_pools.insert(pool_id, &pool);
+            self.update_asset_totals(pool.asset, |totals| {
+                totals.pool_liquidity = totals.pool_liquidity.safe_sub(amount)?;
+                totals.pool_borrowed = totals.pool_borrowed.safe_add(amount)?;
+                Ok(())
+            })
+        }
+        
+        /// Credit payments back to a pool's free liquidity and stop counting returned principal as borrowed
+        fn credit_pool(&mut self, pool_id: u64, credited: Balance, returned_principal: Balance) -> Result<(), LendingError> {
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+            pool.total_liquidity = pool.total_liquidity.safe_add(credited)?;
+            pool.total_borrowed = pool.total_borrowed.safe_sub(returned_principal)?;
+            self.liquidity_pools.insert(pool_id, &pool);
+            self.update_asset_totals(pool.asset, |totals| {
+                totals.pool_liquidity = totals.pool_liquidity.safe_add(credited)?;
+                totals.pool_borrowed = totals.pool_borrowed.safe_sub(returned_principal)?;
+                Ok(())
+            })
+        }
+
+        // ============================================================================
+        // QUERY OPERATIONS
+        // ============================================================================
+        
+        /// Get loan information
         #[ink(message)]
         pub fn get_loan(&self, loan_id: u64) -> Option<Loan> {
             self.loans.get(loan_id)
         }
 
-        /// Get user profile information
-        #[ink(message)]
-        pub fn get_user_profile(&self, user: AccountId) -> Option<UserProfile> {
-            self.user_profiles.get(user)
+        /// Get the actions a loan's current status permits
+        #[ink(message)]
+        pub fn get_allowed_actions(&self, loan_id: u64) -> Result<Vec<LoanAction>, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok(LoanAction::ALL.into_iter().filter(|action| loan.status.allows(action)).collect())
+        }
+
+        /// Get user profile information
+        #[ink(message)]
+        pub fn get_user_profile(&self, user: AccountId) -> Option<UserProfile> {
+            self.user_profiles.get(user)
+        }
+
+        /// Get total number of loans
+        #[ink(message)]
+        pub fn get_total_loans(&self) -> u64 {
+            self.total_loans
+        }
+
+        /// Get native-currency principal currently lent out
+        #[ink(message)]
+        pub fn get_total_liquidity(&self) -> Balance {
+            self.asset_totals.get(Asset::Native).unwrap_or_default().liquidity
+        }
+
+        /// Get collateral currently held in escrow for a loan
+        #[ink(message)]
+        pub fn get_escrowed_collateral(&self, loan_id: u64) -> Balance {
+            self.escrowed_collateral.get(loan_id).unwrap_or(0)
+        }
+
+        /// Get native-currency collateral held in escrow across all loans
+        #[ink(message)]
+        pub fn get_total_escrowed_collateral(&self) -> Balance {
+            self.asset_totals.get(Asset::Native).unwrap_or_default().escrowed_collateral
+        }
+
+        /// Get early repayment discount for a loan
+        #[ink(message)]
+        pub fn get_early_repayment_discount(&self, loan_id: u64) -> Result<u16, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let current_time = self.now();
+            
+            if current_time >= loan.due_date {
+                return Ok(0); // No discount if loan is already due
+            }
+            
+            let time_early = loan.due_date.safe_sub(current_time)?;
+            self.calculate_early_repayment_discount(time_early, loan.duration)
+        }
+
+        /// Get loan payment information
+        #[ink(message)]
+        pub fn get_loan_payment_info(&self, loan_id: u64) -> Result<(Balance, Balance, Vec<PartialPayment>), LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok((loan.total_paid, loan.remaining_balance, loan.partial_payments.clone()))
+        }
+
+        /// Get partial payment count for a loan
+        #[ink(message)]
+        pub fn get_partial_payment_count(&self, loan_id: u64) -> Result<u32, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok(loan.partial_payments.len() as u32)
+        }
+
+        /// Get loan extension information
+        #[ink(message)]
+        pub fn get_loan_extension_info(&self, loan_id: u64) -> Result<(u32, u32, u16), LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok((loan.extension_count, loan.max_extensions, loan.extension_fee_rate))
+        }
+
+        /// Check if a loan can be extended
+        #[ink(message)]
+        pub fn can_extend_loan(&self, loan_id: u64) -> Result<bool, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let current_time = self.now();
+            
+            let can_extend = loan.extension_count < loan.max_extensions && 
+                           current_time < loan.due_date &&
+                           loan.status.allows(&LoanAction::Extend);
+            
+            Ok(can_extend)
+        }
+
+        /// Calculate extension fee for a loan
+        #[ink(message)]
+        pub fn calculate_extension_fee(&self, loan_id: u64) -> Result<Balance, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let extension_fee = bps_mul(loan.remaining_balance, loan.extension_fee_rate as u128, Rounding::Up)?;
+            Ok(extension_fee)
+        }
+
+        /// Get late fee information for a loan
+        #[ink(message)]
+        pub fn get_late_fee_info(&self, loan_id: u64) -> Result<(Balance, u16, u16, Option<u64>), LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok((loan.total_late_fees, loan.late_fee_rate, loan.max_late_fee_rate, loan.overdue_since))
+        }
+
+        /// Calculate current late fees for a loan
+        #[ink(message)]
+        pub fn calculate_current_late_fees(&self, loan_id: u64) -> Result<Balance, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let current_time = self.now();
+            let grace_period_end = loan.due_date.safe_add(loan.grace_period)?;
+            
+            if current_time <= grace_period_end {
+                return Ok(0); // No late fees yet
+            }
+            
+            let overdue_ms = current_time.safe_sub(grace_period_end)?;
+            Self::late_fee(loan.remaining_balance, overdue_ms, loan.late_fee_rate, loan.max_late_fee_rate)
+        }
+
+        /// Check if a loan is overdue
+        #[ink(message)]
+        pub fn is_loan_overdue(&self, loan_id: u64) -> Result<bool, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let current_time = self.now();
+            let grace_period_end = loan.due_date.safe_add(loan.grace_period)?;
+            Ok(current_time > grace_period_end)
+        }
+
+        /// Get loan refinance information
+        #[ink(message)]
+        pub fn get_loan_refinance_info(&self, loan_id: u64) -> Result<(u32, u32, u16), LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok((loan.refinance_count, loan.max_refinances, loan.refinance_fee_rate))
+        }
+
+        /// Check if a loan can be refinanced
+        #[ink(message)]
+        pub fn can_refinance_loan(&self, loan_id: u64) -> Result<bool, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let current_time = self.now();
+            
+            let can_refinance = loan.refinance_count < loan.max_refinances && 
+                              current_time < loan.due_date &&
+                              loan.status.allows(&LoanAction::Refinance);
+            
+            Ok(can_refinance)
+        }
+
+        /// Calculate refinance fee for a loan
+        #[ink(message)]
+        pub fn calculate_refinance_fee(&self, loan_id: u64) -> Result<Balance, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            let refinance_fee = bps_mul(loan.remaining_balance, loan.refinance_fee_rate as u128, Rounding::Up)?;
+            Ok(refinance_fee)
+        }
+
+        /// Get refinance history for a loan
+        #[ink(message)]
+        pub fn get_refinance_history(&self, loan_id: u64) -> Result<Vec<RefinanceRecord>, LendingError> {
+            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
+            Ok(loan.refinance_history.clone())
+        }
+
+        // ============================================================================
+        // PROTOCOL FEE VAULT
+        // ============================================================================
+        
+        /// Withdraw collected native-currency protocol fees (owner or treasury only)
+        #[ink(message)]
+        pub fn withdraw_protocol_fees(&mut self, to: AccountId, amount: Balance) -> Result<(), LendingError> {
+            self.withdraw_asset_fees(Asset::Native, to, amount)
+        }
+        
+        /// Withdraw protocol fees collected in an asset (owner or treasury only)
+        #[ink(message)]
+        pub fn withdraw_asset_fees(&mut self, asset: Asset, to: AccountId, amount: Balance) -> Result<(), LendingError> {
+            let caller = self.env().caller();
+            if caller != self.owner && self.treasury_account != Some(caller) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if amount == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            if amount > self.asset_totals.get(asset).unwrap_or_default().protocol_fees {
+                return Err(LendingError::InsufficientBalance);
+            }
+            
+            self.update_asset_totals(asset, |totals| {
+                totals.protocol_fees = totals.protocol_fees.safe_sub(amount)?;
+                Ok(())
+            })?;
+            if asset == Asset::Native {
+                self.protocol_revenue.total_withdrawn = self.protocol_revenue.total_withdrawn.safe_add(amount)?;
+            }
+            
+            self.send_asset(asset, to, amount)?;
+            
+            self.env().emit_event(ProtocolFeesWithdrawn {
+                to,
+                asset,
+                amount,
+                withdrawn_by: caller,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set the treasury account allowed to withdraw protocol fees (owner only)
+        #[ink(message)]
+        pub fn set_treasury_account(&mut self, treasury_account: Option<AccountId>) -> Result<(), LendingError> {
+            if self.env().caller() != self.owner {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            self.treasury_account = treasury_account;
+            
+            Ok(())
+        }
+        
+        /// Get native-currency protocol revenue broken down by fee source
+        #[ink(message)]
+        pub fn get_protocol_revenue(&self) -> ProtocolRevenue {
+            self.protocol_revenue.clone()
+        }
+        
+        /// Get native-currency protocol fees available for withdrawal
+        #[ink(message)]
+        pub fn get_protocol_fee_vault(&self) -> Balance {
+            self.asset_totals.get(Asset::Native).unwrap_or_default().protocol_fees
+        }
+        
+        /// Take the protocol fee from a fee-bearing amount into the asset's vault, returning the fee taken
+        fn collect_protocol_fee(&mut self, asset: Asset, fee_base: Balance, source: FeeSource) -> Result<Balance, LendingError> {
+            let fee = bps_mul(fee_base, self.protocol_fee as u128, Rounding::Up)?;
+            if fee == 0 {
+                return Ok(0);
+            }
+            
+            self.update_asset_totals(asset, |totals| {
+                totals.protocol_fees = totals.protocol_fees.safe_add(fee)?;
+                Ok(())
+            })?;
+            
+            // Revenue by source is reported in the native currency only
+            if asset != Asset::Native {
+                return Ok(fee);
+            }
+            match source {
+                FeeSource::Interest => self.protocol_revenue.interest_fees = self.protocol_revenue.interest_fees.safe_add(fee)?,
+                FeeSource::FlashLoan => self.protocol_revenue.flash_loan_fees = self.protocol_revenue.flash_loan_fees.safe_add(fee)?,
+                FeeSource::Extension => self.protocol_revenue.extension_fees = self.protocol_revenue.extension_fees.safe_add(fee)?,
+                FeeSource::Refinance => self.protocol_revenue.refinance_fees = self.protocol_revenue.refinance_fees.safe_add(fee)?,
+            }
+            
+            Ok(fee)
+        }
+
+        // ============================================================================
+        // ASSET REGISTRY
+        // ============================================================================
+        
+        /// Register a PSP22 token that loans and pools can be denominated in (owner only)
+        #[ink(message)]
+        pub fn register_asset(&mut self, asset: Asset, decimals: u8) -> Result<(), LendingError> {
+            if self.env().caller() != self.owner {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // NFT collections are only priced, loans cannot be denominated in them
+            if matches!(asset, Asset::Psp34(_)) {
+                return Err(LendingError::AssetMismatch);
+            }
+            
+            if self.assets.contains(asset) {
+                return Err(LendingError::AssetAlreadyRegistered);
+            }
+            
+            let config = AssetConfig {
+                asset,
+                decimals,
+                enabled: true,
+                registered_at: self.now(),
+            };
+            self.assets.insert(asset, &config);
+            self.registered_assets.push(asset);
+            
+            self.env().emit_event(AssetRegistered {
+                asset,
+                decimals,
+            });
+            
+            Ok(())
+        }
+        
+        /// Enable or disable an asset for new loans and pools (owner only)
+        #[ink(message)]
+        pub fn set_asset_enabled(&mut self, asset: Asset, enabled: bool) -> Result<(), LendingError> {
+            if self.env().caller() != self.owner {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            let mut config = self.assets.get(asset).ok_or(LendingError::AssetNotRegistered)?;
+            config.enabled = enabled;
+            self.assets.insert(asset, &config);
+            
+            self.env().emit_event(AssetStatusChanged {
+                asset,
+                enabled,
+            });
+            
+            Ok(())
+        }
+        
+        /// Get the registry entry of an asset
+        #[ink(message)]
+        pub fn get_asset_config(&self, asset: Asset) -> Option<AssetConfig> {
+            self.assets.get(asset)
+        }
+        
+        /// Get the registry entries of all registered assets
+        #[ink(message)]
+        pub fn get_registered_assets(&self) -> Vec<AssetConfig> {
+            self.registered_assets.iter()
+                .filter_map(|asset| self.assets.get(asset))
+                .collect()
+        }
+        
+        /// Get the liquidity, escrow and fee totals held in an asset
+        #[ink(message)]
+        pub fn get_asset_totals(&self, asset: Asset) -> AssetTotals {
+            self.asset_totals.get(asset).unwrap_or_default()
+        }
+        
+        /// Get the registry entry of an asset that new loans and pools can use
+        fn require_enabled_asset(&self, asset: Asset) -> Result<AssetConfig, LendingError> {
+            let config = self.assets.get(asset).ok_or(LendingError::AssetNotRegistered)?;
+            if !config.enabled {
+                return Err(LendingError::AssetDisabled);
+            }
+            Ok(config)
+        }
+        
+        /// Reject operations that take an amount from the call's transferred value on token loans
+        fn require_native(&self, asset: Asset) -> Result<(), LendingError> {
+            if asset != Asset::Native {
+                return Err(LendingError::NativeAssetOnly);
+            }
+            Ok(())
+        }
+        
+        /// Apply a checked change to an asset's running totals
+        fn update_asset_totals(
+            &mut self,
+            asset: Asset,
+            update: impl FnOnce(&mut AssetTotals) -> Result<(), LendingError>,
+        ) -> Result<(), LendingError> {
+            let mut totals = self.asset_totals.get(asset).unwrap_or_default();
+            update(&mut totals)?;
+            self.asset_totals.insert(asset, &totals);
+            Ok(())
+        }
+        
+        /// Stop counting a closed loan's principal as lent out, borrowed from its pool or as unsecured exposure
+        fn release_loan_liquidity(&mut self, loan: &Loan) -> Result<(), LendingError> {
+            self.update_asset_totals(loan.asset, |totals| {
+                totals.liquidity = totals.liquidity.safe_sub(loan.amount)?;
+                Ok(())
+            })?;
+            
+            // Repayments were already credited to the pool as they came in
+            if let Some(pool_id) = loan.liquidity_pool_id {
+                self.credit_pool(pool_id, 0, loan.amount)?;
+                let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+                pool.active_loans = pool.active_loans.saturating_sub(1);
+                self.liquidity_pools.insert(pool_id, &pool);
+            }
+            
+            self.release_unsecured_exposure(loan)
+        }
+        
+        /// Take an exact amount of an asset from the caller: native value must be sent with the call,
+        /// PSP22 tokens are pulled with `transfer_from` against the caller's allowance
+        fn receive_asset(&self, asset: Asset, amount: Balance) -> Result<(), LendingError> {
+            match asset {
+                Asset::Native => {
+                    if self.env().transferred_value() != amount {
+                        return Err(LendingError::InvalidAmount);
+                    }
+                    Ok(())
+                }
+                Asset::Psp22(token) => {
+                    if self.env().transferred_value() != 0 {
+                        return Err(LendingError::InvalidAmount);
+                    }
+                    self.pull_psp22(token, amount)
+                }
+                Asset::Psp34(_) => Err(LendingError::AssetMismatch),
+            }
+        }
+        
+        /// Pull PSP22 tokens from the caller with `transfer_from` against the caller's allowance
+        fn pull_psp22(&self, token: AccountId, amount: Balance) -> Result<(), LendingError> {
+            if amount == 0 {
+                return Ok(());
+            }
+            let input = ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer_from")))
+                .push_arg(self.env().caller())
+                .push_arg(self.env().account_id())
+                .push_arg(amount)
+                .push_arg(Vec::<u8>::new());
+            Self::invoke_psp22(token, input)
+        }
+        
+        /// Pay an amount of an asset out of the contract
+        fn send_asset(&self, asset: Asset, to: AccountId, amount: Balance) -> Result<(), LendingError> {
+            match asset {
+                Asset::Native => self.env().transfer(to, amount)
+                    .map_err(|_| LendingError::TransferFailed),
+                Asset::Psp22(token) => {
+                    if amount == 0 {
+                        return Ok(());
+                    }
+                    let input = ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                        .push_arg(to)
+                        .push_arg(amount)
+                        .push_arg(Vec::<u8>::new());
+                    Self::invoke_psp22(token, input)
+                }
+                Asset::Psp34(_) => Err(LendingError::AssetMismatch),
+            }
+        }
+        
+        /// Call a PSP22 token message, treating any environment, dispatch or token error as a failed transfer
+        fn invoke_psp22<Args: ink::scale::Encode>(token: AccountId, input: ExecutionInput<Args>) -> Result<(), LendingError> {
+            build_call::<DefaultEnvironment>()
+                .call(token)
+                .exec_input(input)
+                .returns::<Result<(), Psp22Error>>()
+                .try_invoke()
+                .map_err(|_| LendingError::TokenTransferFailed)?
+                .map_err(|_| LendingError::TokenTransferFailed)?
+                .map_err(|_| LendingError::TokenTransferFailed)
+        }
+
+        // ============================================================================
+        // PRICE ORACLE
+        // ============================================================================
+        
+        /// Create or reconfigure the price feed of an asset (owner only)
+        #[ink(message)]
+        pub fn configure_price_feed(
+            &mut self,
+            asset: Asset,
+            max_staleness_ms: u64,
+            max_deviation_bps: u16,
+            twap_window_ms: u64,
+        ) -> Result<(), LendingError> {
+            if self.env().caller() != self.owner {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // The native currency is the unit every price is quoted in
+            if asset == Asset::Native {
+                return Err(LendingError::InvalidPrice);
+            }
+            
+            if max_staleness_ms == 0 || max_deviation_bps == 0 || max_deviation_bps as u128 > BPS {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            let mut feed = self.price_feeds.get(asset).unwrap_or(PriceFeed {
+                asset,
+                reporters: Vec::new(),
+                reports: Vec::new(),
+                price: 0,
+                updated_at: 0,
+                observations: Vec::new(),
+                max_staleness_ms,
+                max_deviation_bps,
+                twap_window_ms,
+            });
+            feed.max_staleness_ms = max_staleness_ms;
+            feed.max_deviation_bps = max_deviation_bps;
+            feed.twap_window_ms = twap_window_ms;
+            self.price_feeds.insert(asset, &feed);
+            
+            Ok(())
+        }
+        
+        /// Authorize an account to report prices for an asset (owner only)
+        #[ink(message)]
+        pub fn add_price_reporter(&mut self, asset: Asset, reporter: AccountId) -> Result<(), LendingError> {
+            if self.env().caller() != self.owner {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            let mut feed = self.price_feeds.get(asset).ok_or(LendingError::PriceFeedNotFound)?;
+            if feed.reporters.contains(&reporter) {
+                return Err(LendingError::ReporterAlreadyAuthorized);
+            }
+            
+            feed.reporters.push(reporter);
+            self.price_feeds.insert(asset, &feed);
+            
+            Ok(())
+        }
+        
+        /// Revoke a reporter and drop its latest report from the aggregate (owner only)
+        #[ink(message)]
+        pub fn remove_price_reporter(&mut self, asset: Asset, reporter: AccountId) -> Result<(), LendingError> {
+            if self.env().caller() != self.owner {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            let mut feed = self.price_feeds.get(asset).ok_or(LendingError::PriceFeedNotFound)?;
+            if !feed.reporters.contains(&reporter) {
+                return Err(LendingError::ReporterNotAuthorized);
+            }
+            
+            feed.reporters.retain(|account| *account != reporter);
+            feed.reports.retain(|report| report.reporter != reporter);
+            self.price_feeds.insert(asset, &feed);
+            
+            Ok(())
+        }
+        
+        /// Report the price of one whole unit of an asset in native base units (reporters only)
+        #[ink(message)]
+        pub fn submit_price(&mut self, asset: Asset, price: Balance) -> Result<Balance, LendingError> {
+            let caller = self.env().caller();
+            let current_time = self.now();
+            
+            if price == 0 {
+                return Err(LendingError::InvalidPrice);
+            }
+            
+            let mut feed = self.price_feeds.get(asset).ok_or(LendingError::PriceFeedNotFound)?;
+            if !feed.reporters.contains(&caller) {
+                return Err(LendingError::ReporterNotAuthorized);
+            }
+            
+            // Reports are checked against the aggregate while it is fresh
+            let aggregate_fresh = feed.price > 0
+                && current_time.saturating_sub(feed.updated_at) <= feed.max_staleness_ms;
+            if aggregate_fresh && oracle::deviation_bps(price, feed.price)? > feed.max_deviation_bps as u128 {
+                return Err(LendingError::PriceDeviationTooLarge);
+            }
+            
+            feed.reports.retain(|report| report.reporter != caller);
+            feed.reports.push(PriceReport {
+                reporter: caller,
+                price,
+                timestamp: current_time,
+            });
+            
+            let fresh_prices: Vec<Balance> = feed.reports.iter()
+                .filter(|report| current_time.saturating_sub(report.timestamp) <= feed.max_staleness_ms)
+                .map(|report| report.price)
+                .collect();
+            let report_count = fresh_prices.len() as u32;
+            let aggregate = oracle::median(fresh_prices).ok_or(LendingError::InvalidPrice)?;
+            
+            feed.price = aggregate;
+            feed.updated_at = current_time;
+            
+            // One observation per timestamp, keeping the latest aggregate
+            if feed.observations.last().is_some_and(|observation| observation.timestamp == current_time) {
+                feed.observations.pop();
+            }
+            feed.observations.push(PriceObservation {
+                price: aggregate,
+                timestamp: current_time,
+            });
+            if feed.observations.len() > MAX_PRICE_OBSERVATIONS {
+                feed.observations.remove(0);
+            }
+            
+            let twap = oracle::twap(&feed.observations, current_time, feed.twap_window_ms)?.unwrap_or(aggregate);
+            self.price_feeds.insert(asset, &feed);
+            
+            self.env().emit_event(OracleUpdated {
+                asset,
+                reporter: caller,
+                price: aggregate,
+                twap,
+                report_count,
+            });
+            
+            Ok(aggregate)
+        }
+        
+        /// Get the current aggregated price of an asset, failing if it is stale
+        #[ink(message)]
+        pub fn get_price(&self, asset: Asset) -> Result<Balance, LendingError> {
+            self.oracle_prices(asset).map(|(spot, _)| spot)
+        }
+        
+        /// Get the time-weighted average price of an asset over its feed's window
+        #[ink(message)]
+        pub fn get_twap(&self, asset: Asset) -> Result<Balance, LendingError> {
+            self.oracle_prices(asset).map(|(_, twap)| twap)
+        }
+        
+        /// Get the configuration, reports and observations of an asset's price feed
+        #[ink(message)]
+        pub fn get_price_feed(&self, asset: Asset) -> Option<PriceFeed> {
+            self.price_feeds.get(asset)
+        }
+        
+        /// Spot and time-weighted prices of an asset, rejecting a feed that has not updated recently
+        fn oracle_prices(&self, asset: Asset) -> Result<(Balance, Balance), LendingError> {
+            let feed = self.price_feeds.get(asset).ok_or(LendingError::PriceFeedNotFound)?;
+            let current_time = self.now();
+            
+            if feed.price == 0 || current_time.saturating_sub(feed.updated_at) > feed.max_staleness_ms {
+                return Err(LendingError::PriceStale);
+            }
+            
+            let twap = oracle::twap(&feed.observations, current_time, feed.twap_window_ms)?.unwrap_or(feed.price);
+            Ok((feed.price, twap))
+        }
+        
+        /// Price of one whole unit of an asset in native base units, with the asset's base units per whole unit
+        fn unit_price(&self, asset: Asset, bound: PriceBound) -> Result<(Balance, Balance), LendingError> {
+            let decimals = match asset {
+                Asset::Native => {
+                    let unit = 10u128.checked_pow(NATIVE_DECIMALS as u32).ok_or(LendingError::ArithmeticOverflow)?;
+                    return Ok((unit, unit));
+                }
+                Asset::Psp22(_) => self.assets.get(asset).ok_or(LendingError::AssetNotRegistered)?.decimals,
+                Asset::Psp34(_) => 0,
+            };
+            
+            let unit = 10u128.checked_pow(decimals as u32).ok_or(LendingError::ArithmeticOverflow)?;
+            let (spot, twap) = self.oracle_prices(asset)?;
+            Ok((oracle::bounded_price(spot, twap, bound), unit))
+        }
+        
+        /// Value an amount of collateral in units of the loan asset, at the lower of spot and TWAP
+        fn collateral_value(&self, collateral_asset: Asset, asset: Asset, amount: Balance) -> Result<Balance, LendingError> {
+            if collateral_asset == asset {
+                return Ok(amount);
+            }
+            
+            let (collateral_price, collateral_unit) = self.unit_price(collateral_asset, PriceBound::Lower)?;
+            let (debt_price, debt_unit) = self.unit_price(asset, PriceBound::Upper)?;
+            let native_value = mul_div(amount, collateral_price, collateral_unit, Rounding::Down)?;
+            mul_div(native_value, debt_unit, debt_price, Rounding::Down)
+        }
+        
+        /// Amount of collateral worth a debt in the loan asset, rounded up in the lenders' favor
+        fn collateral_for_debt(&self, collateral_asset: Asset, asset: Asset, debt: Balance) -> Result<Balance, LendingError> {
+            if collateral_asset == asset {
+                return Ok(debt);
+            }
+            
+            let (collateral_price, collateral_unit) = self.unit_price(collateral_asset, PriceBound::Lower)?;
+            let (debt_price, debt_unit) = self.unit_price(asset, PriceBound::Upper)?;
+            let native_value = mul_div(debt, debt_price, debt_unit, Rounding::Up)?;
+            mul_div(native_value, collateral_unit, collateral_price, Rounding::Up)
+        }
+
+        // ============================================================================
+        // TIME BASIS
+        // ============================================================================
+        
+        /// Set the expected block time used to migrate legacy block-based loans (admin only)
+        #[ink(message)]
+        pub fn set_block_time(&mut self, block_time_ms: u64) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            if block_time_ms == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            self.block_time_ms = block_time_ms;
+            
+            Ok(())
+        }
+        
+        /// Get the expected block time in ms
+        #[ink(message)]
+        pub fn get_block_time(&self) -> u64 {
+            self.block_time_ms
+        }
+        
+        /// Rewrite a loan stored by a block-based version of the contract with timestamps and milliseconds (admin only)
+        #[ink(message)]
+        pub fn migrate_loan_to_timestamps(&mut self, loan_id: u64) -> Result<(), LendingError> {
+            if !self.is_authorized_admin(self.env().caller()) {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            // Legacy loans are told apart by their layout: they no longer decode as a `Loan`
+            let key = (ink::storage::traits::StorageKey::key(&self.loans), loan_id);
+            let legacy = match ink::env::get_contract_storage::<_, Loan>(&key) {
+                Ok(Some(_)) => return Err(LendingError::AlreadyTimestampBased),
+                Ok(None) => return Err(LendingError::LoanNotFound),
+                Err(_) => ink::env::get_contract_storage::<_, LegacyLoan>(&key)
+                    .ok()
+                    .flatten()
+                    .ok_or(LendingError::LoanNotFound)?,
+            };
+            
+            let loan = self.loan_from_legacy(legacy)?;
+            self.loans.insert(loan_id, &loan);
+            
+            // Legacy loans were never counted in the asset totals that closing a loan releases
+            if loan.status.is_open() && loan.lender.is_some() {
+                let amount = loan.amount;
+                self.update_asset_totals(loan.asset, |totals| {
+                    totals.liquidity = totals.liquidity.safe_add(amount)?;
+                    Ok(())
+                })?;
+            }
+            
+            self.env().emit_event(LoanClockMigrated {
+                loan_id,
+                block_time_ms: self.block_time_ms,
+                new_due_date: loan.due_date,
+            });
+            
+            Ok(())
+        }
+        
+        /// Current time in milliseconds, the clock all loan dates and periods are measured in
+        fn now(&self) -> u64 {
+            self.env().block_timestamp()
+        }
+        
+        /// Estimate the timestamp of a block number relative to the current block
+        fn block_to_timestamp(&self, block: u64) -> Result<u64, LendingError> {
+            let current_block = self.env().block_number() as u64;
+            let current_time = self.now();
+            if block <= current_block {
+                Ok(current_time.saturating_sub(current_block.safe_sub(block)?.safe_mul(self.block_time_ms)?))
+            } else {
+                current_time.safe_add(block.safe_sub(current_block)?.safe_mul(self.block_time_ms)?)
+            }
+        }
+        
+        /// Convert a legacy loan: points in time are anchored to the current block, durations scale by the block time
+        fn loan_from_legacy(&self, legacy: LegacyLoan) -> Result<Loan, LendingError> {
+            let block_time_ms = self.block_time_ms;
+            let duration = legacy.duration.safe_mul(block_time_ms)?;
+            let mut loan = self.build_loan(legacy.id, legacy.borrower, legacy.amount, legacy.interest_rate, duration, legacy.collateral)?;
+            
+            loan.lender = legacy.lender;
+            loan.status = legacy.status;
+            loan.created_at = self.block_to_timestamp(legacy.created_at)?;
+            loan.due_date = self.block_to_timestamp(legacy.due_date)?;
+            loan.early_repayment_discount = legacy.early_repayment_discount;
+            loan.extension_count = legacy.extension_count;
+            loan.max_extensions = legacy.max_extensions;
+            loan.extension_fee_rate = legacy.extension_fee_rate;
+            loan.late_fee_rate = legacy.late_fee_rate;
+            loan.max_late_fee_rate = legacy.max_late_fee_rate;
+            loan.total_late_fees = legacy.total_late_fees;
+            loan.overdue_since = legacy.overdue_since.map(|block| self.block_to_timestamp(block)).transpose()?;
+            loan.grace_period = legacy.grace_period.safe_mul(block_time_ms)?;
+            loan.refinance_count = legacy.refinance_count;
+            loan.max_refinances = legacy.max_refinances;
+            loan.refinance_fee_rate = legacy.refinance_fee_rate;
+            loan.original_loan_id = legacy.original_loan_id;
+            loan.refinance_history = legacy.refinance_history;
+            loan.interest_rate_type = legacy.interest_rate_type;
+            loan.base_interest_rate = legacy.base_interest_rate;
+            loan.risk_multiplier = legacy.risk_multiplier;
+            loan.interest_rate_adjustments = legacy.interest_rate_adjustments;
+            loan.last_interest_update = self.block_to_timestamp(legacy.last_interest_update)?;
+            loan.interest_update_frequency = legacy.interest_update_frequency.safe_mul(block_time_ms)?;
+            loan.interest_type = legacy.interest_type;
+            loan.compound_frequency = legacy.compound_frequency;
+            loan.last_compound_date = self.block_to_timestamp(legacy.last_compound_date)?;
+            loan.compound_period_ms = legacy.compound_period_blocks.safe_mul(block_time_ms)?;
+            loan.accrued_interest = legacy.accrued_interest;
+            loan.total_compounded_interest = legacy.total_compounded_interest;
+            loan.payment_structure = legacy.payment_structure;
+            loan.interest_only_periods = legacy.interest_only_periods;
+            loan.current_payment_period = legacy.current_payment_period;
+            loan.interest_only_periods_used = legacy.interest_only_periods_used;
+            loan.next_payment_due = self.block_to_timestamp(legacy.next_payment_due)?;
+            loan.payment_period_ms = legacy.payment_period_blocks.safe_mul(block_time_ms)?;
+            loan.minimum_payment_amount = legacy.minimum_payment_amount;
+            loan.grace_period_ms = legacy.grace_period_blocks.safe_mul(block_time_ms)?;
+            loan.grace_period_used = legacy.grace_period_used.safe_mul(block_time_ms)?;
+            loan.grace_period_extensions = legacy.grace_period_extensions;
+            loan.max_grace_period_extensions = legacy.max_grace_period_extensions;
+            loan.grace_period_reason = legacy.grace_period_reason;
+            loan.grace_period_history = legacy.grace_period_history;
+            loan.liquidity_pool_id = legacy.liquidity_pool_id;
+            loan.pool_share = legacy.pool_share;
+            loan.liquidity_provider = legacy.liquidity_provider;
+            loan.pool_rewards_earned = legacy.pool_rewards_earned;
+            loan.credit_score = legacy.credit_score;
+            loan.collateral_requirements = legacy.collateral_requirements;
+            loan.insurance_policies = legacy.insurance_policies;
+            loan.fraud_flags = legacy.fraud_flags;
+            loan.compliance_status = legacy.compliance_status;
+            
+            for record in loan.refinance_history.iter_mut() {
+                record.timestamp = self.block_to_timestamp(record.timestamp)?;
+            }
+            for adjustment in loan.interest_rate_adjustments.iter_mut() {
+                adjustment.timestamp = self.block_to_timestamp(adjustment.timestamp)?;
+            }
+            for record in loan.grace_period_history.iter_mut() {
+                record.timestamp = self.block_to_timestamp(record.timestamp)?;
+                record.duration = record.duration.safe_mul(block_time_ms)?;
+            }
+            
+            // Replay the legacy payments through the waterfall so the repaid split adds up to what was paid
+            loan.remaining_balance = legacy.remaining_balance.safe_add(legacy.total_paid)?;
+            let mut replayed: Balance = 0;
+            for payment in legacy.partial_payments {
+                let (late_fees_paid, interest_paid, principal_paid) = self.allocate_payment(&mut loan, payment.amount)?;
+                loan.remaining_balance = loan.remaining_balance.saturating_sub(payment.amount);
+                replayed = replayed.safe_add(payment.amount)?;
+                loan.partial_payments.push(PartialPayment {
+                    amount: payment.amount,
+                    timestamp: self.block_to_timestamp(payment.timestamp)?,
+                    payment_type: payment.payment_type,
+                    payer: legacy.borrower,
+                    late_fees_paid,
+                    interest_paid,
+                    principal_paid,
+                });
+            }
+            self.allocate_payment(&mut loan, legacy.total_paid.saturating_sub(replayed))?;
+            loan.total_paid = legacy.total_paid;
+            loan.remaining_balance = legacy.remaining_balance;
+            
+            // Legacy balances already hold the interest for the whole term, so nothing more accrues
+            if loan.lender.is_some() {
+                loan.funded_amount = loan.amount;
+                loan.last_accrual = loan.due_date;
+            }
+            
+            Ok(loan)
+        }
+
+        // ============================================================================
+        // PRIVATE HELPER METHODS
+        // ============================================================================
+        
+        /// Build a new pending loan with default terms
+        fn build_loan(
+            &self,
+            loan_id: u64,
+            borrower: AccountId,
+            amount: Balance,
+            interest_rate: u16,
+            duration: u64,
+            collateral: Balance,
+        ) -> Result<Loan, LendingError> {
+            let current_time = self.now();
+            
+            Ok(Loan {
+                id: loan_id,
+                borrower,
+                lender: None,
+                asset: Asset::Native, // Set by the request for token loans
+                collateral_asset: Asset::Native,
+                amount,
+                interest_rate,
+                duration,
+                collateral,
+                status: LoanStatus::Pending,
+                created_at: current_time,
+                due_date: current_time.safe_add(duration)?,
+                early_repayment_discount: 200, // Default 2% discount for early repayment
+                total_paid: 0,
+                principal_repaid: 0,
+                interest_repaid: 0,
+                late_fees_repaid: 0,
+                remaining_balance: 0, // Will be set when loan is funded
+                partial_payments: Vec::new(),
+                extension_count: 0,
+                max_extensions: 3, // Default maximum of 3 extensions
+                extension_fee_rate: 100, // Default 1% extension fee
+                late_fee_rate: 50, // Default 0.5% daily late fee
+                max_late_fee_rate: 1000, // Default 10% maximum late fee
+                total_late_fees: 0,
+                late_fees_accrued_until: 0,
+                overdue_since: None,
+                grace_period: 600_000, // Default 10 minute grace period
+                refinance_count: 0,
+                max_refinances: 2, // Default maximum of 2 refinances
+                refinance_fee_rate: 200, // Default 2% refinance fee
+                original_loan_id: None,
+                refinance_history: Vec::new(),
+                interest_rate_type: InterestRateType::Fixed, // Default to fixed rate
+                base_interest_rate: interest_rate, // Base rate same as initial rate
+                risk_multiplier: 1000, // Default 1.0x risk multiplier
+                interest_rate_adjustments: Vec::new(),
+                last_interest_update: current_time,
+                interest_update_frequency: MILLIS_PER_DAY, // Default: daily updates
+                interest_type: InterestType::Simple, // Default to simple interest
+                compound_frequency: CompoundFrequency::Daily, // Default to daily compounding
+                last_compound_date: current_time,
+                compound_period_ms: MILLIS_PER_DAY, // Default: daily
+                accrued_interest: 0,
+                total_compounded_interest: 0,
+                borrow_index: BORROW_INDEX_SCALE,
+                last_accrual: current_time,
+                payment_structure: PaymentStructure::PrincipalAndInterest, // Default to P&I
+                interest_only_periods: 0, // Default: no interest-only periods
+                current_payment_period: 0,
+                interest_only_periods_used: 0,
+                next_payment_due: current_time.safe_add(MILLIS_PER_DAY)?, // First payment due in 1 day
+                payment_period_ms: MILLIS_PER_DAY, // Default: daily payments
+                minimum_payment_amount: 0, // No minimum initially
+                grace_period_ms: 600_000, // Default: 10 minute grace period
+                grace_period_used: 0,
+                grace_period_extensions: 0,
+                max_grace_period_extensions: 2, // Default: maximum 2 grace period extensions
+                grace_period_reason: GracePeriodReason::None,
+                grace_period_history: Vec::new(),
+                liquidity_pool_id: None,
+                pool_share: 0,
+                liquidity_provider: None,
+                pool_rewards_earned: 0,
+                credit_score: None,
+                collateral_requirements: Vec::new(),
+                margin_call_at: None,
+                insurance_policies: Vec::new(),
+                fraud_flags: Vec::new(),
+                compliance_status: ComplianceStatus::Pending,
+                funding_deadline: None, // No deadline unless set by the borrower
+                funded_amount: 0,
+                lender_contributions: Vec::new(),
+                amortization_schedule: Vec::new(), // Generated when the loan is funded
+            })
+        }
+
+        /// Validate and store the caller's pending loan request, escrowing the collateral paid with the call
+        fn create_loan_request(&mut self, request: LoanRequest) -> Result<u64, LendingError> {
+            let borrower = self.env().caller();
+            let LoanRequest { asset, collateral_asset, amount, interest_rate, duration, collateral, basket } = request;
+            
+            // Validate parameters
+            if amount == 0 || interest_rate == 0 || duration == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            if interest_rate > 10000 { // Max 100%
+                return Err(LendingError::InvalidInterestRate);
+            }
+            
+            if duration > MILLIS_PER_YEAR { // Max 1 year
+                return Err(LendingError::InvalidDuration);
+            }
+
+            self.require_enabled_asset(asset)?;
+            self.require_enabled_asset(collateral_asset)?;
+
+            // Check if user is blacklisted and track new users
+            let is_new_user = !self.user_profiles.contains(borrower);
+            let user_profile = self.get_or_create_user_profile(borrower);
+            if user_profile.is_blacklisted {
+                return Err(LendingError::UserBlacklisted);
+            }
+            
+            // Increment user count for new users
+            if is_new_user {
+                self.total_users = self.total_users.safe_add(1)?;
+            }
+
+            // Scored borrowers are held to their credit tier's loan size and collateral ratio
+            let credit_tier = self.borrower_credit_tier(borrower);
+            if let Some(tier) = credit_tier.as_ref() {
+                if self.collateral_value(asset, Asset::Native, amount)? > tier.max_loan_value {
+                    return Err(LendingError::CreditTierLimitExceeded);
+                }
+            }
+
+            let mut unsecured = 0;
+            if basket.is_empty() {
+                // Validate collateral ratio, valuing collateral in another asset at oracle prices
+                let min_collateral_ratio = credit_tier.map_or(self.min_collateral_ratio, |tier| tier.min_collateral_ratio);
+                let required_collateral = bps_mul(amount, min_collateral_ratio as u128, Rounding::Up)?;
+                let collateral_value = self.collateral_value(collateral_asset, asset, collateral)?;
+                if collateral_value < required_collateral {
+                    return Err(LendingError::InsufficientCollateral);
+                }
+                unsecured = self.uncovered_principal(amount, collateral_value)?;
+
+                // Collateral must be paid with the request so it can be held in escrow
+                self.receive_asset(collateral_asset, collateral)?;
+            } else {
+                // A basket's haircut-weighted value must cover the whole amount
+                if self.basket_borrowing_power(asset, &basket)? < amount {
+                    return Err(LendingError::InsufficientCollateral);
+                }
+                self.receive_basket(&basket)?;
+            }
+
+            let loan_id = self.total_loans.safe_add(1)?;
+            self.reserve_unsecured_exposure(loan_id, asset, unsecured)?;
+            let mut loan = self.build_loan(loan_id, borrower, amount, interest_rate, duration, collateral)?;
+            loan.asset = asset;
+            loan.collateral_asset = collateral_asset;
+
+            // Price in the borrower's risk level, keeping the requested rate as the effective rate
+            loan.risk_multiplier = self.borrower_risk_multiplier(borrower);
+            loan.base_interest_rate = u16::try_from(mul_div(interest_rate as u128, 1000, loan.risk_multiplier as u128, Rounding::Down)?)
+                .map_err(|_| LendingError::ArithmeticOverflow)?;
+
+            self.loans.insert(loan_id, &loan);
+            self.total_loans = loan_id;
+
+            // Hold collateral in escrow until the loan is repaid
+            self.escrowed_collateral.insert(loan_id, &collateral);
+            self.update_asset_totals(collateral_asset, |totals| {
+                totals.escrowed_collateral = totals.escrowed_collateral.safe_add(collateral)?;
+                Ok(())
+            })?;
+            for position in basket.iter() {
+                self.update_asset_totals(position.asset, |totals| {
+                    totals.escrowed_collateral = totals.escrowed_collateral.safe_add(position.amount)?;
+                    Ok(())
+                })?;
+            }
+            if !basket.is_empty() {
+                self.collateral_baskets.insert(loan_id, &basket);
+            }
+
+            // Update user profile
+            let mut profile = user_profile;
+            profile.active_loans.push(loan_id);
+            self.user_profiles.insert(borrower, &profile);
+
+            self.env().emit_event(LoanCreated {
+                loan_id,
+                borrower,
+                asset,
+                amount,
+                interest_rate,
+                duration,
+            });
+
+            Ok(loan_id)
+        }
+
+        /// Update the offer-backed market depth level for an interest rate, keeping levels sorted by rate
+        fn update_offer_depth(&mut self, interest_rate: u16, liquidity_change: i128, order_count_change: i32) {
+            let current_time = self.now();
+            let index = match self.offer_depth_levels.iter().position(|level| level.price_level >= interest_rate) {
+                Some(i) if self.offer_depth_levels[i].price_level == interest_rate => i,
+                Some(i) => {
+                    self.offer_depth_levels.insert(i, MarketDepthLevel {
+                        price_level: interest_rate,
+                        liquidity_available: 0,
+                        order_count: 0,
+                        last_updated: current_time,
+                    });
+                    i
+                }
+                None => {
+                    self.offer_depth_levels.push(MarketDepthLevel {
+                        price_level: interest_rate,
+                        liquidity_available: 0,
+                        order_count: 0,
+                        last_updated: current_time,
+                    });
+                    self.offer_depth_levels.len().saturating_sub(1)
+                }
+            };
+            
+            let level = &mut self.offer_depth_levels[index];
+            level.liquidity_available = if liquidity_change > 0 {
+                level.liquidity_available.saturating_add(liquidity_change as u128)
+            } else {
+                level.liquidity_available.saturating_sub(liquidity_change.unsigned_abs())
+            };
+            level.order_count = level.order_count.saturating_add_signed(order_count_change);
+            level.last_updated = current_time;
+            
+            // Drop levels with no remaining offers
+            if level.order_count == 0 {
+                self.offer_depth_levels.remove(index);
+            }
+        }
+
+        /// Get or create a user profile
+        fn get_or_create_user_profile(&self, user: AccountId) -> UserProfile {
+            self.user_profiles.get(user).unwrap_or(UserProfile {
+                total_borrowed: 0,
+                total_lent: 0,
+                active_loans: Vec::new(),
+                credit_score: 700, // Default credit score
+                is_blacklisted: false,
+                default_count: 0,
+            })
+        }
+
+
+
+        /// Release a loan's escrowed collateral to the given recipient
+        fn release_collateral(&mut self, loan: &Loan, recipient: AccountId) -> Result<Balance, LendingError> {
+            for position in self.take_collateral_basket(loan.id)? {
+                self.send_asset(position.asset, recipient, position.amount)?;
+                self.env().emit_event(BasketCollateralReleased {
+                    loan_id: loan.id,
+                    asset: position.asset,
+                    recipient,
+                    amount: position.amount,
+                });
+            }
+
+            let amount = self.escrowed_collateral.get(loan.id).unwrap_or(0);
+            if amount == 0 {
+                return Ok(0);
+            }
+
+            self.escrowed_collateral.remove(loan.id);
+            self.update_asset_totals(loan.collateral_asset, |totals| {
+                totals.escrowed_collateral = totals.escrowed_collateral.safe_sub(amount)?;
+                Ok(())
+            })?;
+
+            self.send_asset(loan.collateral_asset, recipient, amount)?;
+
+            // The loan has already been stored by the caller, so record the emptied escrow on it
+            if !loan.collateral_requirements.is_empty() {
+                let mut released = loan.clone();
+                self.sync_collateral_requirements(&mut released, 0);
+                self.loans.insert(loan.id, &released);
+            }
+
+            self.env().emit_event(CollateralReleased {
+                loan_id: loan.id,
+                recipient,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Calculate a loan's health factor in basis points (below 10000 = liquidatable)
+        fn calculate_health_factor(&self, loan: &Loan) -> Result<u128, LendingError> {
+            let collateral = self.escrowed_collateral.get(loan.id).unwrap_or(0);
+            let basket = self.collateral_baskets.get(loan.id).unwrap_or_default();
+            self.health_factor_with(loan, collateral, &basket)
+        }
+
+        /// Health factor the loan would have with the given escrowed collateral and basket
+        fn health_factor_with(&self, loan: &Loan, collateral: Balance, basket: &[CollateralPosition]) -> Result<u128, LendingError> {
+            // Only the share of the debt the loan was admitted with collateral for is held against it
+            let debt = self.current_debt(loan)?;
+            let unsecured = self.get_unsecured_principal(loan.id).min(loan.amount);
+            let debt = if unsecured > 0 {
+                mul_div(debt, loan.amount.safe_sub(unsecured)?, loan.amount, Rounding::Up)?
+            } else {
+                debt
+            };
+            if debt == 0 {
+                return Ok(u128::MAX);
+            }
+            
+            // Collateral in another asset is valued through the oracle
+            let threshold = self.loan_liquidation_threshold(loan);
+            let valued_collateral = self.collateral_value(loan.collateral_asset, loan.asset, collateral)?;
+            let health_factor = mul_div(valued_collateral, threshold as u128, debt, Rounding::Down).unwrap_or(u128::MAX);
+            if basket.is_empty() {
+                return Ok(health_factor);
+            }
+            
+            // Basket positions count at their own collateral factors instead of the loan's threshold
+            let basket_health = bps_ratio(self.basket_borrowing_power(loan.asset, basket)?, debt, Rounding::Down).unwrap_or(u128::MAX);
+            Ok(health_factor.saturating_add(basket_health))
+        }
+
+        /// Liquidation threshold of a loan, where a per-loan requirement overrides the protocol default
+        fn loan_liquidation_threshold(&self, loan: &Loan) -> u16 {
+            loan.collateral_requirements.iter()
+                .map(|req| req.liquidation_threshold)
+                .find(|&threshold| threshold > 0)
+                .unwrap_or(self.liquidation_threshold)
+        }
+
+        /// Maintenance margin of a loan, where a per-loan requirement overrides the protocol default
+        fn loan_maintenance_margin(&self, loan: &Loan) -> u16 {
+            loan.collateral_requirements.iter()
+                .map(|req| req.maintenance_margin)
+                .find(|&margin| margin > 0)
+                .unwrap_or(self.maintenance_margin)
+        }
+
+        /// Check if a loan is undercollateralized or past its grace period
+        fn is_liquidatable_loan(&self, loan: &Loan) -> Result<bool, LendingError> {
+            let current_time = self.now();
+            let grace_period_end = loan.due_date.saturating_add(loan.grace_period);
+            
+            Ok(current_time > grace_period_end || self.calculate_health_factor(loan)? < 10000)
+        }
+
+        /// Remove a closed loan from the borrower's and lender's active loan lists
+        fn remove_from_active_loans(&mut self, loan: &Loan) {
+            let mut borrower_profile = self.get_or_create_user_profile(loan.borrower);
+            borrower_profile.active_loans.retain(|&id| id != loan.id);
+            self.user_profiles.insert(loan.borrower, &borrower_profile);
+
+            self.remove_from_lender_active_loans(loan);
+        }
+
+        /// Remove a loan from the active loans of every participating lender
+        fn remove_from_lender_active_loans(&mut self, loan: &Loan) {
+            let mut lenders: Vec<AccountId> = loan.lender_contributions.iter().map(|c| c.lender).collect();
+            if let Some(lender) = loan.lender {
+                if !lenders.contains(&lender) {
+                    lenders.push(lender);
+                }
+            }
+
+            for lender in lenders {
+                let mut lender_profile = self.get_or_create_user_profile(lender);
+                lender_profile.active_loans.retain(|&id| id != loan.id);
+                self.user_profiles.insert(lender, &lender_profile);
+            }
+        }
+
+        /// Take a full repayment from the payer and close the loan, returning the collateral to the borrower
+        fn apply_full_repayment(&mut self, mut loan: Loan, payer: AccountId) -> Result<(), LendingError> {
+            let loan_id = loan.id;
+            
+            if !loan.status.allows(&LoanAction::Repay) {
+                return Err(LendingError::LoanNotActive);
+            }
+
+            self.accrue_loan_interest(&mut loan)?;
+            let repayment_amount = loan.remaining_balance;
+            
+            self.receive_asset(loan.asset, repayment_amount)?;
+
+            // Record the full payment, split through the waterfall
+            let current_time = self.now();
+            let (late_fees_paid, interest_paid, principal_paid) = self.allocate_payment(&mut loan, repayment_amount)?;
+            let full_payment = PartialPayment {
+                amount: repayment_amount,
+                timestamp: current_time,
+                payment_type: PaymentType::Full,
+                payer,
+                late_fees_paid,
+                interest_paid,
+                principal_paid,
+            };
+
+            // Update loan payment tracking
+            loan.total_paid = loan.total_paid.safe_add(repayment_amount)?;
+            loan.remaining_balance = 0;
+            loan.partial_payments.push(full_payment);
+            self.transition_loan(&mut loan, LoanStatus::Repaid, LoanAction::Repay)?;
+            self.settle_amortization_schedule(&mut loan, current_time)?;
+
+            self.loans.insert(loan_id, &loan);
+
+            // Transfer repayment to lenders pro-rata, net of the protocol fee on interest
+            let protocol_fee = self.collect_protocol_fee(loan.asset, interest_paid, FeeSource::Interest)?;
+            self.distribute_to_lenders(&loan, repayment_amount.safe_sub(protocol_fee)?)?;
+
+            // Return escrowed collateral to borrower
+            self.release_collateral(&loan, loan.borrower)?;
+
+            // Update borrower profile
+            let mut borrower_profile = self.get_or_create_user_profile(loan.borrower);
+            borrower_profile.total_borrowed = borrower_profile.total_borrowed.safe_add(loan.amount)?;
+            borrower_profile.active_loans.retain(|&id| id != loan_id);
+            self.user_profiles.insert(loan.borrower, &borrower_profile);
+
+            // Update lender profiles
+            self.remove_from_lender_active_loans(&loan);
+
+            self.release_loan_liquidity(&loan)?;
+
+            self.env().emit_event(LoanRepaid {
+                loan_id,
+                borrower: loan.borrower,
+                payer,
+                amount: repayment_amount,
+                late_fees_paid,
+                interest_paid,
+                principal_paid,
+            });
+
+            Ok(())
+        }
+
+        /// Take a discounted early repayment from the payer and close the loan, returning the collateral to the borrower
+        fn apply_early_repayment(&mut self, mut loan: Loan, payer: AccountId) -> Result<(), LendingError> {
+            let loan_id = loan.id;
+            
+            if !loan.status.allows(&LoanAction::EarlyRepay) {
+                return Err(LendingError::LoanNotActive);
+            }
+
+            let current_time = self.now();
+            if current_time >= loan.due_date {
+                return Err(LendingError::LoanAlreadyDue); // Use regular repayment
+            }
+
+            // Calculate early repayment discount
+            let time_early = loan.due_date.safe_sub(current_time)?;
+            let discount_percentage = self.calculate_early_repayment_discount(time_early, loan.duration)?;
+            
+            // Discount applies to the interest accrued so far
+            self.accrue_loan_interest(&mut loan)?;
+            let original_repayment = loan.remaining_balance;
+            let accrued_interest = original_repayment.saturating_sub(loan.amount.safe_sub(loan.principal_repaid)?);
+            let discount_amount = bps_mul(accrued_interest, discount_percentage as u128, Rounding::Down)?;
+            let discounted_repayment = original_repayment.safe_sub(discount_amount)?;
+            
+            self.receive_asset(loan.asset, discounted_repayment)?;
+
+            // Record the early payment; the discount forgives interest before the waterfall applies
+            let current_time = self.now();
+            loan.remaining_balance = loan.remaining_balance.safe_sub(discount_amount)?;
+            let (late_fees_paid, interest_paid, principal_paid) = self.allocate_payment(&mut loan, discounted_repayment)?;
+            let early_payment = PartialPayment {
+                amount: discounted_repayment,
+                timestamp: current_time,
+                payment_type: PaymentType::Early,
+                payer,
+                late_fees_paid,
+                interest_paid,
+                principal_paid,
+            };
+
+            // Update loan payment tracking
+            loan.total_paid = loan.total_paid.safe_add(discounted_repayment)?;
+            loan.remaining_balance = 0;
+            loan.partial_payments.push(early_payment);
+            self.transition_loan(&mut loan, LoanStatus::EarlyRepaid, LoanAction::EarlyRepay)?;
+            self.settle_amortization_schedule(&mut loan, current_time)?;
+
+            self.loans.insert(loan_id, &loan);
+
+            // Transfer discounted repayment to lenders pro-rata, net of the protocol fee on interest
+            let protocol_fee = self.collect_protocol_fee(loan.asset, interest_paid, FeeSource::Interest)?;
+            self.distribute_to_lenders(&loan, discounted_repayment.safe_sub(protocol_fee)?)?;
+
+            // Return escrowed collateral to borrower
+            self.release_collateral(&loan, loan.borrower)?;
+
+            // Update borrower profile
+            let mut borrower_profile = self.get_or_create_user_profile(loan.borrower);
+            borrower_profile.total_borrowed = borrower_profile.total_borrowed.safe_add(loan.amount)?;
+            borrower_profile.active_loans.retain(|&id| id != loan_id);
+            self.user_profiles.insert(loan.borrower, &borrower_profile);
+
+            // Update lender profiles
+            self.remove_from_lender_active_loans(&loan);
+
+            self.release_loan_liquidity(&loan)?;
+
+            self.env().emit_event(LoanEarlyRepaid {
+                loan_id,
+                borrower: loan.borrower,
+                payer,
+                original_amount: original_repayment,
+                discounted_amount: discounted_repayment,
+                discount_applied: discount_amount,
+                time_early,
+            });
+
+            Ok(())
+        }
+
+        /// Take an interest-only period payment from the payer and pass it on to the lenders
+        fn apply_interest_only_payment(&mut self, mut loan: Loan, payer: AccountId) -> Result<(), LendingError> {
+            let loan_id = loan.id;
+            
+            if !loan.status.allows(&LoanAction::Repay) {
+                return Err(LendingError::LoanNotActive);
+            }
+            
+            // Check if loan supports interest-only payments
+            if loan.payment_structure != PaymentStructure::InterestOnly {
+                return Err(LendingError::NotInterestOnly);
+            }
+            
+            // Check if it's time for payment
+            let current_time = self.now();
+            if current_time < loan.next_payment_due {
+                return Err(LendingError::PaymentNotDue);
+            }
+            
+            // Check if interest-only periods are available
+            if loan.interest_only_periods_used >= loan.interest_only_periods {
+                return Err(LendingError::NoInterestOnlyPeriodsLeft);
+            }
+            
+            // Calculate interest payment for this period; it cannot close the loan
+            let interest_payment = self.calculate_interest_payment(loan_id)?;
+            self.accrue_loan_interest(&mut loan)?;
+            if interest_payment >= loan.remaining_balance {
+                return Err(LendingError::PaymentExceedsBalance);
+            }
+            self.receive_asset(loan.asset, interest_payment)?;
+            
+            // Record the payment, split through the waterfall
+            let (late_fees_paid, interest_paid, principal_paid) = self.allocate_payment(&mut loan, interest_payment)?;
+            loan.total_paid = loan.total_paid.safe_add(interest_payment)?;
+            loan.remaining_balance = loan.remaining_balance.safe_sub(interest_payment)?;
+            loan.partial_payments.push(PartialPayment {
+                amount: interest_payment,
+                timestamp: current_time,
+                payment_type: PaymentType::Partial,
+                payer,
+                late_fees_paid,
+                interest_paid,
+                principal_paid,
+            });
+            if loan.status != LoanStatus::Overdue {
+                self.transition_loan(&mut loan, LoanStatus::PartiallyPaid, LoanAction::Repay)?;
+            }
+            
+            // Update loan state
+            loan.current_payment_period = loan.current_payment_period.safe_add(1)?;
+            loan.interest_only_periods_used = loan.interest_only_periods_used.safe_add(1)?;
+            loan.next_payment_due = current_time.safe_add(loan.payment_period_ms)?;
+            
+            // If this was the last interest-only period, switch to P&I
+            if loan.interest_only_periods_used >= loan.interest_only_periods {
+                loan.payment_structure = PaymentStructure::PrincipalAndInterest;
+                loan.minimum_payment_amount = self.calculate_minimum_payment(loan_id)?;
+            }
+            
+            self.loans.insert(loan_id, &loan);
+            
+            // Transfer payment to lenders pro-rata, net of the protocol fee on its interest share
+            let protocol_fee = self.collect_protocol_fee(loan.asset, interest_paid, FeeSource::Interest)?;
+            self.distribute_to_lenders(&loan, interest_payment.safe_sub(protocol_fee)?)?;
+            
+            self.env().emit_event(InterestOnlyPaymentMade {
+                loan_id,
+                borrower: loan.borrower,
+                payer,
+                payment_period: loan.current_payment_period,
+                interest_paid: interest_payment,
+                principal_remaining: loan.amount,
+                next_payment_due: loan.next_payment_due,
+            });
+            
+            Ok(())
+        }
+
+        /// Take a payment from the caller and apply it to a loan, closing it when the balance reaches zero
+        fn apply_partial_payment(&mut self, mut loan: Loan, payer: AccountId, payment_amount: Balance) -> Result<(), LendingError> {
+            let loan_id = loan.id;
+            
+            if !loan.status.allows(&LoanAction::Repay) {
+                return Err(LendingError::LoanNotActive);
+            }
+
+            if payment_amount == 0 {
+                return Err(LendingError::InvalidAmount);
+            }
+
+            self.accrue_loan_interest(&mut loan)?;
+            if payment_amount > loan.remaining_balance {
+                return Err(LendingError::PaymentExceedsBalance);
+            }
+            self.receive_asset(loan.asset, payment_amount)?;
+
+            // Overdue loans are charged the late fees accrued since they were last charged
+            if loan.status == LoanStatus::Overdue {
+                self.accrue_late_fees(&mut loan)?;
+            }
+
+            // Record the partial payment, split through the waterfall
+            let current_time = self.now();
+            let (late_fees_paid, interest_paid, principal_paid) = self.allocate_payment(&mut loan, payment_amount)?;
+            let partial_payment = PartialPayment {
+                amount: payment_amount,
+                timestamp: current_time,
+                payment_type: PaymentType::Partial,
+                payer,
+                late_fees_paid,
+                interest_paid,
+                principal_paid,
+            };
+
+            // Update loan payment tracking
+            loan.total_paid = loan.total_paid.safe_add(payment_amount)?;
+            loan.remaining_balance = loan.remaining_balance.safe_sub(payment_amount)?;
+            loan.partial_payments.push(partial_payment);
+
+            // Flag installments missed before this payment, then apply it to the schedule in order
+            self.mark_missed_installments(&mut loan, current_time)?;
+            self.apply_payment_to_schedule(&mut loan, payment_amount, current_time)?;
+            
+            // Update loan status; an overdue loan stays overdue until it is repaid
+            if loan.remaining_balance == 0 {
+                self.transition_loan(&mut loan, LoanStatus::Repaid, LoanAction::Repay)?;
+            } else if loan.status != LoanStatus::Overdue {
+                self.transition_loan(&mut loan, LoanStatus::PartiallyPaid, LoanAction::Repay)?;
+            }
+
+            self.loans.insert(loan_id, &loan);
+
+            // Transfer payment to lenders pro-rata, net of the protocol fee on its interest share
+            let protocol_fee = self.collect_protocol_fee(loan.asset, interest_paid, FeeSource::Interest)?;
+            self.distribute_to_lenders(&loan, payment_amount.safe_sub(protocol_fee)?)?;
+
+            // Update borrower profile if loan is fully repaid
+            if loan.remaining_balance == 0 {
+                // Final payment returns escrowed collateral to borrower
+                self.release_collateral(&loan, loan.borrower)?;
+
+                let mut borrower_profile = self.get_or_create_user_profile(loan.borrower);
+                borrower_profile.total_borrowed = borrower_profile.total_borrowed.safe_add(loan.amount)?;
+                borrower_profile.active_loans.retain(|&id| id != loan_id);
+                self.user_profiles.insert(loan.borrower, &borrower_profile);
+
+                // Update lender profiles
+                self.remove_from_lender_active_loans(&loan);
+
+                self.release_loan_liquidity(&loan)?;
+            }
+
+            self.env().emit_event(LoanPartiallyPaid {
+                loan_id,
+                borrower: loan.borrower,
+                payer,
+                payment_amount,
+                late_fees_paid,
+                interest_paid,
+                principal_paid,
+                remaining_balance: loan.remaining_balance,
+                total_paid: loan.total_paid,
+            });
+
+            Ok(())
+        }
+
+
+        /// Use an active guarantee to pay down a defaulting loan, returning (amount called, collateral reimbursed to the guarantor)
+        fn call_guarantee(&mut self, loan: &mut Loan, collateral: Balance) -> Result<(Balance, Balance), LendingError> {
+            let mut guarantee = match self.loan_guarantees.get(loan.id) {
+                Some(guarantee) if guarantee.status == GuaranteeStatus::Active => guarantee,
+                _ => return Ok((0, 0)),
+            };
+            
+            let amount_called = guarantee.stake.min(loan.remaining_balance);
+            let (late_fees_paid, interest_paid, principal_paid) = self.allocate_payment(loan, amount_called)?;
+            loan.remaining_balance = loan.remaining_balance.safe_sub(amount_called)?;
+            loan.total_paid = loan.total_paid.safe_add(amount_called)?;
+            loan.partial_payments.push(PartialPayment {
+                amount: amount_called,
+                timestamp: self.now(),
+                payment_type: PaymentType::Partial,
+                payer: guarantee.guarantor,
+                late_fees_paid,
+                interest_paid,
+                principal_paid,
+            });
+            let protocol_fee = self.collect_protocol_fee(loan.asset, interest_paid, FeeSource::Interest)?;
+            self.distribute_to_lenders(loan, amount_called.safe_sub(protocol_fee)?)?;
+            
+            // Collateral beyond the remaining debt reimburses the guarantor first
+            let reimbursed = collateral.saturating_sub(loan.remaining_balance).min(amount_called);
+            let stake_returned = guarantee.stake.safe_sub(amount_called)?;
+            
+            guarantee.amount_called = amount_called;
+            guarantee.recovery_claim = amount_called.safe_sub(reimbursed)?;
+            guarantee.status = GuaranteeStatus::Called;
+            self.loan_guarantees.insert(loan.id, &guarantee);
+            
+            let guarantor_payout = stake_returned.safe_add(reimbursed)?;
+            if guarantor_payout > 0 {
+                self.send_asset(Asset::Native, guarantee.guarantor, guarantor_payout)?;
+            }
+            
+            self.env().emit_event(GuaranteeCalled {
+                loan_id: loan.id,
+                guarantor: guarantee.guarantor,
+                amount_called,
+                recovery_claim: guarantee.recovery_claim,
+            });
+            
+            Ok((amount_called, reimbursed))
+        }
+
+        /// Pay down a called guarantor's recovery claim out of surplus collateral, returning what is left for the borrower
+        fn reimburse_guarantor(&mut self, loan: &Loan, asset: Asset, surplus: Balance) -> Result<Balance, LendingError> {
+            let mut guarantee = match self.loan_guarantees.get(loan.id) {
+                Some(guarantee) if guarantee.status == GuaranteeStatus::Called && guarantee.recovery_claim > 0 && surplus > 0 => guarantee,
+                _ => return Ok(surplus),
+            };
+            
+            // Recovery claims are denominated in the native token like the stake
+            let claim_repaid = guarantee.recovery_claim.min(self.collateral_value(asset, Asset::Native, surplus)?);
+            let reimbursed = self.collateral_for_debt(asset, Asset::Native, claim_repaid)?.min(surplus);
+            guarantee.recovery_claim = guarantee.recovery_claim.safe_sub(claim_repaid)?;
+            self.loan_guarantees.insert(loan.id, &guarantee);
+            
+            if reimbursed > 0 {
+                self.send_asset(asset, guarantee.guarantor, reimbursed)?;
+            }
+            
+            self.env().emit_event(GuarantorRepaid {
+                loan_id: loan.id,
+                guarantor: guarantee.guarantor,
+                amount: claim_repaid,
+                recovery_claim: guarantee.recovery_claim,
+            });
+            
+            surplus.safe_sub(reimbursed)
         }
 
-        /// Get total number of loans
-        #[ink(message)]
-        pub fn get_total_loans(&self) -> u64 {
-            self.total_loans
+        /// Check whether a loan's lender positions can change hands
+        fn is_position_transferable(&self, loan: &Loan) -> bool {
+            loan.status.allows(&LoanAction::TransferPosition)
         }
 
-        /// Get total liquidity in the contract
-        #[ink(message)]
-        pub fn get_total_liquidity(&self) -> Balance {
-            self.total_liquidity
+        /// Get the principal a lender has contributed to a loan
+        fn lender_principal(&self, loan: &Loan, lender: AccountId) -> Balance {
+            if loan.lender_contributions.is_empty() {
+                return if loan.lender == Some(lender) { loan.amount } else { 0 };
+            }
+            
+            loan.lender_contributions.iter()
+                .filter(|c| c.lender == lender)
+                .map(|c| c.amount)
+                .sum()
         }
 
-        /// Get early repayment discount for a loan
-        #[ink(message)]
-        pub fn get_early_repayment_discount(&self, loan_id: u64) -> Result<u16, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            let current_block = self.env().block_number() as u64;
+        /// Move a lender's position and repayment rights to another account, updating both profiles
+        fn move_lender_position(&mut self, loan: &mut Loan, from: AccountId, to: AccountId) -> Result<(), LendingError> {
+            if !self.is_position_transferable(loan) {
+                return Err(LendingError::LoanNotActive);
+            }
             
-            if current_block >= loan.due_date {
-                return Ok(0); // No discount if loan is already due
+            if from == to || to == loan.borrower {
+                return Err(LendingError::Unauthorized);
+            }
+            
+            let principal = self.lender_principal(loan, from);
+            if principal == 0 {
+                return Err(LendingError::Unauthorized);
             }
             
-            let blocks_early = loan.due_date - current_block;
-            Ok(self.calculate_early_repayment_discount(blocks_early, loan.duration))
+            if self.get_or_create_user_profile(to).is_blacklisted {
+                return Err(LendingError::UserBlacklisted);
+            }
+            
+            // Merge into the new lender's existing contribution if they already participate
+            let already_lender = self.lender_principal(loan, to) > 0;
+            if loan.lender_contributions.is_empty() {
+                loan.funded_amount = loan.amount; // Single lender funded the whole loan
+            } else {
+                loan.lender_contributions.retain(|c| c.lender != from);
+            }
+            loan.funded_amount = loan.funded_amount.safe_sub(principal)?;
+            self.record_contribution(loan, to, principal)?;
+            
+            if loan.lender == Some(from) {
+                loan.lender = Some(to);
+            }
+            
+            self.loans.insert(loan.id, loan);
+            
+            let mut from_profile = self.get_or_create_user_profile(from);
+            from_profile.total_lent = from_profile.total_lent.saturating_sub(principal);
+            from_profile.active_loans.retain(|&id| id != loan.id);
+            self.user_profiles.insert(from, &from_profile);
+            
+            let mut to_profile = self.get_or_create_user_profile(to);
+            to_profile.total_lent = to_profile.total_lent.safe_add(principal)?;
+            if !already_lender {
+                to_profile.active_loans.push(loan.id);
+            }
+            self.user_profiles.insert(to, &to_profile);
+            
+            self.env().emit_event(LenderPositionTransferred {
+                loan_id: loan.id,
+                from,
+                to,
+                principal,
+            });
+            
+            Ok(())
         }
 
-        /// Get loan payment information
-        #[ink(message)]
-        pub fn get_loan_payment_info(&self, loan_id: u64) -> Result<(Balance, Balance, Vec<PartialPayment>), LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            Ok((loan.total_paid, loan.remaining_balance, loan.partial_payments.clone()))
-        }
+        /// Record a lender contribution, merging repeat contributions from the same lender
+        fn record_contribution(&self, loan: &mut Loan, lender: AccountId, amount: Balance) -> Result<(), LendingError> {
+            loan.funded_amount = loan.funded_amount.safe_add(amount)?;
 
-        /// Get partial payment count for a loan
-        #[ink(message)]
-        pub fn get_partial_payment_count(&self, loan_id: u64) -> Result<u32, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            Ok(loan.partial_payments.len() as u32)
-        }
+            match loan.lender_contributions.iter_mut().find(|c| c.lender == lender) {
+                Some(existing) => existing.amount = existing.amount.safe_add(amount)?,
+                None => loan.lender_contributions.push(LenderContribution { lender, amount, share: 0 }),
+            }
 
-        /// Get loan extension information
-        #[ink(message)]
-        pub fn get_loan_extension_info(&self, loan_id: u64) -> Result<(u32, u32, u16), LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            Ok((loan.extension_count, loan.max_extensions, loan.extension_fee_rate))
+            for contribution in loan.lender_contributions.iter_mut() {
+                contribution.share = u16::try_from(bps_ratio(contribution.amount, loan.amount, Rounding::Down)?).map_err(|_| LendingError::ArithmeticOverflow)?;
+            }
+
+            Ok(())
         }
 
-        /// Check if a loan can be extended
-        #[ink(message)]
-        pub fn can_extend_loan(&self, loan_id: u64) -> Result<bool, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            let current_block = self.env().block_number() as u64;
+        /// Activate a fully funded loan, the first contributor becoming the lead lender
+        fn activate_funded_loan(&mut self, loan: &mut Loan) -> Result<(), LendingError> {
+            let lead_lender = loan.lender_contributions[0].lender;
+            loan.lender = Some(lead_lender);
+            self.transition_loan(loan, LoanStatus::Active, LoanAction::Fund)?;
             
-            let can_extend = loan.extension_count < loan.max_extensions && 
-                           current_block < loan.due_date &&
-                           (loan.status == LoanStatus::Active || loan.status == LoanStatus::PartiallyPaid);
+            // Debt starts at the principal and grows as interest accrues
+            loan.remaining_balance = loan.amount;
+            loan.borrow_index = BORROW_INDEX_SCALE;
+            loan.last_accrual = self.now();
+            let projected_interest = bps_mul(loan.amount, loan.interest_rate as u128, Rounding::Up)?;
+            self.generate_amortization_schedule(loan, loan.created_at, loan.amount, projected_interest)?;
             
-            Ok(can_extend)
-        }
+            self.loans.insert(loan.id, loan);
 
-        /// Calculate extension fee for a loan
-        #[ink(message)]
-        pub fn calculate_extension_fee(&self, loan_id: u64) -> Result<Balance, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            let extension_fee = (loan.remaining_balance * loan.extension_fee_rate as u128) / 10000;
-            Ok(extension_fee)
+            // Update lender profiles
+            for contribution in loan.lender_contributions.iter() {
+                let mut lender_profile = self.get_or_create_user_profile(contribution.lender);
+                lender_profile.total_lent = lender_profile.total_lent.safe_add(contribution.amount)?;
+                lender_profile.active_loans.push(loan.id);
+                self.user_profiles.insert(contribution.lender, &lender_profile);
+            }
+
+            let amount = loan.amount;
+            self.update_asset_totals(loan.asset, |totals| {
+                totals.liquidity = totals.liquidity.safe_add(amount)?;
+                Ok(())
+            })?;
+
+            self.env().emit_event(LoanFunded {
+                loan_id: loan.id,
+                lender: lead_lender,
+                amount: loan.amount,
+            });
+
+            Ok(())
         }
 
-        /// Get late fee information for a loan
-        #[ink(message)]
-        pub fn get_late_fee_info(&self, loan_id: u64) -> Result<(Balance, u16, u16, Option<u64>), LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            Ok((loan.total_late_fees, loan.late_fee_rate, loan.max_late_fee_rate, loan.overdue_since))
+        /// Distribute a payment to the loan's lenders pro-rata to their contributions
+        fn distribute_to_lenders(&mut self, loan: &Loan, amount: Balance) -> Result<(), LendingError> {
+            self.distribute_asset_to_lenders(loan, loan.asset, amount)
         }
 
-        /// Calculate current late fees for a loan
-        #[ink(message)]
-        pub fn calculate_current_late_fees(&self, loan_id: u64) -> Result<Balance, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            let current_block = self.env().block_number() as u64;
-            let grace_period_end = loan.due_date + loan.grace_period;
-            
-            if current_block <= grace_period_end {
-                return Ok(0); // No late fees yet
+        /// Distribute an amount of an asset, such as seized collateral, to the loan's lenders pro-rata
+        fn distribute_asset_to_lenders(&mut self, loan: &Loan, asset: Asset, amount: Balance) -> Result<(), LendingError> {
+            if amount == 0 {
+                return Ok(());
             }
-            
-            let overdue_blocks = current_block - grace_period_end;
-            let days_overdue = overdue_blocks / 14400;
-            let late_fee_rate = (loan.late_fee_rate * days_overdue as u16).min(loan.max_late_fee_rate);
-            let late_fees = (loan.remaining_balance * late_fee_rate as u128) / 10000;
-            
-            Ok(late_fees)
-        }
 
-        /// Check if a loan is overdue
-        #[ink(message)]
-        pub fn is_loan_overdue(&self, loan_id: u64) -> Result<bool, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            let current_block = self.env().block_number() as u64;
-            let grace_period_end = loan.due_date + loan.grace_period;
-            Ok(current_block > grace_period_end)
+            // Pool loans are collateralized in the pool's asset, so everything recovered goes back to the pool
+            if let Some(pool_id) = loan.liquidity_pool_id {
+                return self.credit_pool(pool_id, amount, 0);
+            }
+
+            if loan.lender_contributions.is_empty() {
+                if let Some(lender) = loan.lender {
+                    self.send_asset(asset, lender, amount)?;
+                }
+                return Ok(());
+            }
+
+            // The last lender receives the rounding remainder
+            let mut distributed = 0;
+            let last_index = loan.lender_contributions.len().saturating_sub(1);
+            for (index, contribution) in loan.lender_contributions.iter().enumerate() {
+                let share = if index == last_index {
+                    amount.safe_sub(distributed)?
+                } else {
+                    mul_div(amount, contribution.amount, loan.funded_amount, Rounding::Down)?
+                };
+                distributed = distributed.safe_add(share)?;
+
+                if share > 0 {
+                    self.send_asset(asset, contribution.lender, share)?;
+                }
+            }
+
+            Ok(())
         }
 
-        /// Get loan refinance information
-        #[ink(message)]
-        pub fn get_loan_refinance_info(&self, loan_id: u64) -> Result<(u32, u32, u16), LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            Ok((loan.refinance_count, loan.max_refinances, loan.refinance_fee_rate))
+        /// Refund all contributions of a loan that never became fully funded
+        fn refund_contributions(&mut self, loan: &mut Loan) -> Result<(), LendingError> {
+            for contribution in loan.lender_contributions.iter() {
+                self.send_asset(loan.asset, contribution.lender, contribution.amount)?;
+
+                self.env().emit_event(ContributionRefunded {
+                    loan_id: loan.id,
+                    lender: contribution.lender,
+                    amount: contribution.amount,
+                });
+            }
+
+            loan.lender_contributions.clear();
+            loan.funded_amount = 0;
+
+            Ok(())
         }
 
-        /// Check if a loan can be refinanced
-        #[ink(message)]
-        pub fn can_refinance_loan(&self, loan_id: u64) -> Result<bool, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            let current_block = self.env().block_number() as u64;
-            
-            let can_refinance = loan.refinance_count < loan.max_refinances && 
-                              current_block < loan.due_date &&
-                              (loan.status == LoanStatus::Active || loan.status == LoanStatus::PartiallyPaid);
+        /// Move a loan to a new status through the transition table and record why
+        fn transition_loan(&self, loan: &mut Loan, to: LoanStatus, reason: LoanAction) -> Result<(), LendingError> {
+            if loan.status == to {
+                return Ok(());
+            }
+            if !loan.status.can_transition_to(&to) {
+                return Err(LendingError::InvalidStatusTransition);
+            }
             
-            Ok(can_refinance)
+            let from = core::mem::replace(&mut loan.status, to.clone());
+            self.env().emit_event(LoanStatusChanged {
+                loan_id: loan.id,
+                from,
+                to,
+                reason,
+            });
+            Ok(())
         }
 
-        /// Calculate refinance fee for a loan
-        #[ink(message)]
-        pub fn calculate_refinance_fee(&self, loan_id: u64) -> Result<Balance, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            let refinance_fee = (loan.remaining_balance * loan.refinance_fee_rate as u128) / 10000;
-            Ok(refinance_fee)
+        /// Check whether a pending loan request is past its funding deadline
+        fn is_funding_deadline_passed(&self, loan: &Loan) -> bool {
+            match loan.funding_deadline {
+                Some(deadline) => self.now() > deadline,
+                None => false,
+            }
         }
 
-        /// Get refinance history for a loan
-        #[ink(message)]
-        pub fn get_refinance_history(&self, loan_id: u64) -> Result<Vec<RefinanceRecord>, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            Ok(loan.refinance_history.clone())
+        /// Late fee rate in basis points: the daily rate for each full day overdue, capped at the maximum rate
+        fn late_fee_bps(overdue_ms: u64, late_fee_rate: u16, max_late_fee_rate: u16) -> Result<u64, LendingError> {
+            let days_overdue = overdue_ms.safe_div(MILLIS_PER_DAY)?;
+            Ok((late_fee_rate as u64).saturating_mul(days_overdue).min(max_late_fee_rate as u64))
         }
 
-        // ============================================================================
-        // PRIVATE HELPER METHODS
-        // ============================================================================
-        
-        /// Get or create a user profile
-        fn get_or_create_user_profile(&self, user: AccountId) -> UserProfile {
-            self.user_profiles.get(user).unwrap_or(UserProfile {
-                total_borrowed: 0,
-                total_lent: 0,
-                active_loans: Vec::new(),
-                credit_score: 700, // Default credit score
-                is_blacklisted: false,
-            })
+        /// Late fee on a balance for the time overdue
+        fn late_fee(balance: Balance, overdue_ms: u64, late_fee_rate: u16, max_late_fee_rate: u16) -> Result<Balance, LendingError> {
+            bps_mul(balance, Self::late_fee_bps(overdue_ms, late_fee_rate, max_late_fee_rate)? as u128, Rounding::Up)
         }
 
+        /// Add the late fees for the days overdue that have not been charged yet, returning the amount added
+        fn accrue_late_fees(&self, loan: &mut Loan) -> Result<Balance, LendingError> {
+            let current_time = self.now();
+            let grace_period_end = loan.due_date.safe_add(loan.grace_period)?;
+            let charged_until = loan.late_fees_accrued_until.max(grace_period_end);
+            if current_time <= charged_until {
+                return Ok(0);
+            }
+            
+            // Only the rate for days not yet charged is billed, so the maximum rate holds across charges
+            let charged_bps = Self::late_fee_bps(charged_until.safe_sub(grace_period_end)?, loan.late_fee_rate, loan.max_late_fee_rate)?;
+            let total_bps = Self::late_fee_bps(current_time.safe_sub(grace_period_end)?, loan.late_fee_rate, loan.max_late_fee_rate)?;
+            let late_fees = bps_mul(loan.remaining_balance, total_bps.safe_sub(charged_bps)? as u128, Rounding::Up)?;
+            
+            loan.late_fees_accrued_until = current_time;
+            loan.total_late_fees = loan.total_late_fees.safe_add(late_fees)?;
+            loan.remaining_balance = loan.remaining_balance.safe_add(late_fees)?;
+            Ok(late_fees)
+        }
 
-
-        /// Calculate repayment amount for a loan
-        fn calculate_repayment_amount(&self, loan_id: u64) -> Result<Balance, LendingError> {
-            let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            let interest_amount = (loan.amount * loan.interest_rate as u128) / 10000;
-            Ok(loan.amount + interest_amount)
+        /// Split a payment through the waterfall (late fees, then interest, then principal) and update running totals
+        fn allocate_payment(&self, loan: &mut Loan, amount: Balance) -> Result<(Balance, Balance, Balance), LendingError> {
+            let late_fees_due = loan.total_late_fees.saturating_sub(loan.late_fees_repaid);
+            let principal_due = loan.amount.saturating_sub(loan.principal_repaid);
+            let interest_due = loan.remaining_balance.saturating_sub(late_fees_due.safe_add(principal_due)?);
+            
+            let late_fees_paid = amount.min(late_fees_due);
+            let interest_paid = amount.safe_sub(late_fees_paid)?.min(interest_due);
+            let principal_paid = amount.safe_sub(late_fees_paid)?.safe_sub(interest_paid)?;
+            
+            loan.late_fees_repaid = loan.late_fees_repaid.safe_add(late_fees_paid)?;
+            loan.interest_repaid = loan.interest_repaid.safe_add(interest_paid)?;
+            loan.principal_repaid = loan.principal_repaid.safe_add(principal_paid)?;
+            
+            Ok((late_fees_paid, interest_paid, principal_paid))
         }
 
         /// Calculate early repayment discount
-        fn calculate_early_repayment_discount(&self, blocks_early: u64, total_duration: u64) -> u16 {
+        fn calculate_early_repayment_discount(&self, time_early: u64, total_duration: u64) -> Result<u16, LendingError> {
             // Calculate discount based on how early the repayment is
             // More early = higher discount (up to 5%)
-            let early_percentage = (blocks_early * 10000) / total_duration;
+            let early_percentage = bps_ratio(time_early as u128, total_duration as u128, Rounding::Down)?;
             
-            match early_percentage {
+            let discount = match early_percentage {
                 // Repaying in first 25% of loan duration: 5% discount
                 p if p >= 7500 => 500, // 5%
                 // Repaying in first 50% of loan duration: 3% discount  
@@ -2689,7 +7161,8 @@ pub mod lending_contract {
                 p if p >= 2500 => 200, // 2%
                 // Repaying in last 25% of loan duration: 1% discount
                 _ => 100, // 1%
-            }
+            };
+            Ok(discount)
         }
 
         // ============================================================================
@@ -2700,7 +7173,7 @@ pub mod lending_contract {
         #[ink(message)]
         pub fn rebalance_pool(&mut self, pool_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Only pool creator or authorized users can rebalance
             if pool.liquidity_providers.is_empty() || pool.liquidity_providers[0].account != caller {
@@ -2709,13 +7182,13 @@ pub mod lending_contract {
             
             // Check if pool is active
             if pool.status != PoolStatus::Active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::PoolNotActive);
             }
             
             // Check rebalance frequency
-            let current_block = self.env().block_number() as u64;
-            if current_block < pool.last_rebalance + pool.rebalance_frequency {
-                return Err(LendingError::InvalidAmount); // Reuse error for too frequent rebalancing
+            let current_time = self.now();
+            if current_time < pool.last_rebalance.safe_add(pool.rebalance_frequency)? {
+                return Err(LendingError::RebalanceTooFrequent);
             }
             
             // Perform rebalancing
@@ -2724,7 +7197,7 @@ pub mod lending_contract {
             
             // Update pool state
             pool.current_liquidity_ratio = new_ratio;
-            pool.last_rebalance = current_block;
+            pool.last_rebalance = current_time;
             pool.performance_score = self.calculate_performance_score(&pool)?;
             
             self.liquidity_pools.insert(pool_id, &pool);
@@ -2745,7 +7218,7 @@ pub mod lending_contract {
         #[ink(message)]
         pub fn set_auto_rebalancing(&mut self, pool_id: u64, enabled: bool) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Only pool creator can change auto-rebalancing settings
             if pool.liquidity_providers.is_empty() || pool.liquidity_providers[0].account != caller {
@@ -2768,7 +7241,7 @@ pub mod lending_contract {
             threshold: u16,
         ) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Only pool creator can change rebalancing parameters
             if pool.liquidity_providers.is_empty() || pool.liquidity_providers[0].account != caller {
@@ -2776,7 +7249,7 @@ pub mod lending_contract {
             }
             
             // Validate parameters
-            if frequency < 14400 || target_ratio > 10000 || threshold > 1000 {
+            if frequency < MILLIS_PER_DAY || target_ratio > 10000 || threshold > 1000 {
                 return Err(LendingError::InvalidAmount);
             }
             
@@ -2792,14 +7265,14 @@ pub mod lending_contract {
         /// Check if pool needs rebalancing
         #[ink(message)]
         pub fn needs_rebalancing(&self, pool_id: u64) -> Result<bool, LendingError> {
-            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             if !pool.auto_rebalance_enabled {
                 return Ok(false);
             }
             
-            let current_block = self.env().block_number() as u64;
-            if current_block < pool.last_rebalance + pool.rebalance_frequency {
+            let current_time = self.now();
+            if current_time < pool.last_rebalance.safe_add(pool.rebalance_frequency)? {
                 return Ok(false);
             }
             
@@ -2815,7 +7288,7 @@ pub mod lending_contract {
         /// Get pool rebalancing information
         #[ink(message)]
         pub fn get_pool_rebalancing_info(&self, pool_id: u64) -> Result<(u16, u64, u64, u16, u16, bool), LendingError> {
-            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             Ok((
                 pool.performance_score,
                 pool.last_rebalance,
@@ -2831,10 +7304,10 @@ pub mod lending_contract {
             let current_ratio = pool.current_liquidity_ratio;
             let target_ratio = pool.target_liquidity_ratio;
             
-            // Calculate new ratio based on performance
-            let performance_factor = pool.performance_score as f64 / 10000.0;
-            let ratio_adjustment = ((target_ratio as i32 - current_ratio as i32) as f64 * performance_factor) as i32;
-            let new_ratio = (current_ratio as i32 + ratio_adjustment).max(1000).min(10000) as u16;
+            // Move towards the target ratio in proportion to performance
+            let ratio_gap = mul_div(target_ratio.abs_diff(current_ratio) as u128, pool.performance_score as u128, BPS, Rounding::Down)? as i32;
+            let ratio_adjustment = if target_ratio >= current_ratio { ratio_gap } else { -ratio_gap };
+            let new_ratio = (current_ratio as i32).saturating_add(ratio_adjustment).clamp(1000, 10000) as u16;
             
             // Determine rebalance reason
             let reason = if new_ratio > current_ratio {
@@ -2846,9 +7319,8 @@ pub mod lending_contract {
             };
             
             // Calculate liquidity adjustment
-            let adjustment = if new_ratio != current_ratio {
-                let adjustment_factor = (new_ratio as f64 - current_ratio as f64) / 10000.0;
-                (pool.total_liquidity as f64 * adjustment_factor) as u128
+            let adjustment = if new_ratio > current_ratio {
+                bps_mul(pool.total_liquidity, new_ratio.safe_sub(current_ratio)? as u128, Rounding::Down)?
             } else {
                 0
             };
@@ -2863,23 +7335,23 @@ pub mod lending_contract {
             
             // Factor 1: Liquidity utilization (0-2000 points)
             let utilization = if pool.total_liquidity > 0 {
-                (pool.active_loans as f64 / pool.total_liquidity as f64) * 2000.0
+                mul_div(pool.active_loans as u128, 2000, pool.total_liquidity, Rounding::Down)?
             } else {
-                0.0
+                0
             };
-            score += utilization as u32;
+            score = score.safe_add(utilization.min(2000) as u32)?;
             
             // Factor 2: Reward distribution efficiency (0-2000 points)
             let reward_efficiency = if pool.total_liquidity > 0 {
-                (pool.total_rewards_distributed as f64 / pool.total_liquidity as f64) * 2000.0
+                mul_div(pool.total_rewards_distributed, 2000, pool.total_liquidity, Rounding::Down)?
             } else {
-                0.0
+                0
             };
-            score += reward_efficiency as u32;
+            score = score.safe_add(reward_efficiency.min(2000) as u32)?;
             
             // Factor 3: Provider diversity (0-1000 points)
-            let provider_diversity = (pool.liquidity_providers.len() as u32).min(10) * 100;
-            score += provider_diversity;
+            let provider_diversity = (pool.liquidity_providers.len() as u32).min(10).safe_mul(100)?;
+            score = score.safe_add(provider_diversity)?;
             
             // Ensure score is within bounds
             score = score.min(10000);
@@ -2899,7 +7371,7 @@ pub mod lending_contract {
             reward_tokens: Vec<RewardToken>,
         ) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Only pool creator can enable yield farming
             if pool.liquidity_providers.is_empty() || pool.liquidity_providers[0].account != caller {
@@ -2908,7 +7380,7 @@ pub mod lending_contract {
             
             // Check if pool is active
             if pool.status != PoolStatus::Active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::PoolNotActive);
             }
             
             // Validate reward tokens
@@ -2926,7 +7398,7 @@ pub mod lending_contract {
                 pool_id,
                 enabled_by: caller,
                 reward_tokens_count: reward_tokens.len() as u32,
-                staking_requirements: format!("Min: {}, Lock: {} blocks", 
+                staking_requirements: format!("Min: {}, Lock: {} ms", 
                     pool.staking_requirements.min_stake_amount, 
                     pool.staking_requirements.lock_period),
             });
@@ -2938,16 +7410,16 @@ pub mod lending_contract {
         #[ink(message)]
         pub fn stake_tokens(&mut self, pool_id: u64, amount: Balance) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Check if yield farming is enabled
             if !pool.yield_farming_enabled {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::YieldFarmingDisabled);
             }
             
             // Check if pool is active
             if pool.status != PoolStatus::Active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::PoolNotActive);
             }
             
             // Validate staking amount
@@ -2960,12 +7432,12 @@ pub mod lending_contract {
             let (tier_level, multiplier) = self.calculate_staking_tier(&pool, amount)?;
             
             // Create or update staking position
-            let current_block = self.env().block_number() as u64;
-            let _lock_end_time = current_block + pool.staking_requirements.lock_period;
+            let current_time = self.now();
+            let _lock_end_time = current_time.safe_add(pool.staking_requirements.lock_period)?;
             
             // For now, we'll just update the pool's total staked tokens
             // In a real implementation, you'd store individual staking positions
-            pool.total_staked_tokens += amount;
+            pool.total_staked_tokens = pool.total_staked_tokens.safe_add(amount)?;
             
             self.liquidity_pools.insert(pool_id, &pool);
             
@@ -2985,34 +7457,34 @@ pub mod lending_contract {
         #[ink(message)]
         pub fn claim_yield_rewards(&mut self, pool_id: u64) -> Result<Balance, LendingError> {
             let caller = self.env().caller();
-            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Check if yield farming is enabled
             if !pool.yield_farming_enabled {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::YieldFarmingDisabled);
             }
             
             // Check if pool is active
             if pool.status != PoolStatus::Active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::PoolNotActive);
             }
             
             // For demonstration, calculate rewards based on staked amount and time
             // In a real implementation, you'd track individual staking positions
-            let _current_block = self.env().block_number() as u64;
+            let _current_time = self.now();
             let base_reward_rate = 100; // 1% base reward rate
             let staked_amount = 10000; // Assume staker has 10,000 staked
-            let time_factor = 1; // Assume 1 block since last claim
+            let time_factor = 1; // Assume 1 ms since last claim
             
             // Calculate base rewards
-            let base_rewards = (staked_amount as f64 * base_reward_rate as f64 / 10000.0 * time_factor as f64) as u128;
+            let base_rewards = bps_mul(staked_amount.safe_mul(time_factor)?, base_reward_rate, Rounding::Down)?;
             
             // Apply tier multiplier (assume Gold tier: 1.5x)
             let tier_multiplier = 1500; // 1.5x
-            let total_rewards = (base_rewards as f64 * tier_multiplier as f64 / 1000.0) as u128;
+            let total_rewards = mul_div(base_rewards, tier_multiplier as u128, 1000, Rounding::Down)?;
             
             if total_rewards == 0 {
-                return Err(LendingError::InvalidAmount); // No rewards to claim
+                return Err(LendingError::NoRewardsToClaim);
             }
             
             self.env().emit_event(YieldRewardsClaimed {
@@ -3030,7 +7502,7 @@ pub mod lending_contract {
         /// Get yield farming information
         #[ink(message)]
         pub fn get_yield_farming_info(&self, pool_id: u64) -> Result<(bool, u32, Balance, u32), LendingError> {
-            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             Ok((
                 pool.yield_farming_enabled,
                 pool.reward_tokens.len() as u32,
@@ -3042,7 +7514,7 @@ pub mod lending_contract {
         /// Get staking tier information
         #[ink(message)]
         pub fn get_staking_tiers(&self, pool_id: u64) -> Result<Vec<(String, Balance, u16, u16)>, LendingError> {
-            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             let tiers: Vec<(String, Balance, u16, u16)> = pool.tier_multipliers.iter()
                 .map(|tier| (
@@ -3071,7 +7543,7 @@ pub mod lending_contract {
             
             match best_tier {
                 Some(tier_name) => Ok((tier_name, best_multiplier)),
-                None => Err(LendingError::InvalidAmount),
+                None => Err(LendingError::NoTierQualifies),
             }
         }
 
@@ -3089,17 +7561,17 @@ pub mod lending_contract {
             order_count_change: i32,
         ) -> Result<(), LendingError> {
             let _caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Check if pool is active
             if pool.status != PoolStatus::Active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::PoolNotActive);
             }
             
             // Find the market depth level
             let level_index = pool.market_depth_levels.iter().position(|level| level.price_level == price_level);
             if level_index.is_none() {
-                return Err(LendingError::InvalidAmount); // Invalid price level
+                return Err(LendingError::InvalidPriceLevel);
             }
             
             let level_index = level_index.unwrap();
@@ -3107,22 +7579,22 @@ pub mod lending_contract {
             
             // Update liquidity and order count
             if liquidity_change < 0 && (-liquidity_change) > level.liquidity_available as i128 {
-                return Err(LendingError::InvalidAmount); // Cannot remove more than available
+                return Err(LendingError::InsufficientLiquidity);
             }
             
             level.liquidity_available = if liquidity_change > 0 {
-                level.liquidity_available + liquidity_change as u128
+                level.liquidity_available.safe_add(liquidity_change as u128)?
             } else {
                 level.liquidity_available.saturating_sub((-liquidity_change) as u128)
             };
             
             level.order_count = if order_count_change > 0 {
-                level.order_count + order_count_change as u32
+                level.order_count.safe_add(order_count_change as u32)?
             } else {
                 level.order_count.saturating_sub(order_count_change.abs() as u32)
             };
             
-            level.last_updated = self.env().block_number() as u64;
+            level.last_updated = self.now();
             
             // Update the pool
             pool.market_depth_levels[level_index] = level.clone();
@@ -3143,7 +7615,7 @@ pub mod lending_contract {
         #[ink(message)]
         pub fn apply_optimal_distribution(&mut self, pool_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Only pool creator can apply optimal distribution
             if pool.liquidity_providers.is_empty() || pool.liquidity_providers[0].account != caller {
@@ -3152,7 +7624,7 @@ pub mod lending_contract {
             
             // Check if pool is active
             if pool.status != PoolStatus::Active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::PoolNotActive);
             }
             
             // Calculate optimal distribution
@@ -3178,18 +7650,18 @@ pub mod lending_contract {
         #[ink(message)]
         pub fn check_concentration_limits(&mut self, pool_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Only pool creator can check concentration limits
             if pool.liquidity_providers.is_empty() || pool.liquidity_providers[0].account != caller {
                 return Err(LendingError::Unauthorized);
             }
             
-            // Check single pool concentration
+            // Check single pool concentration against all pool liquidity in the same asset
             let total_liquidity = pool.total_liquidity;
             let max_concentration = pool.concentration_limits.max_single_pool_concentration;
             let current_concentration = if total_liquidity > 0 {
-                (total_liquidity * 10000) / self.total_liquidity
+                bps_ratio(total_liquidity, self.asset_totals.get(pool.asset).unwrap_or_default().pool_liquidity, Rounding::Down)?
             } else {
                 0
             };
@@ -3208,7 +7680,7 @@ pub mod lending_contract {
             // Check provider concentration
             for provider in &pool.liquidity_providers {
                 let provider_concentration = if total_liquidity > 0 {
-                    (provider.liquidity_provided * 10000) / total_liquidity
+                    bps_ratio(provider.liquidity_provided, total_liquidity, Rounding::Down)?
                 } else {
                     0
                 };
@@ -3228,15 +7700,29 @@ pub mod lending_contract {
             Ok(())
         }
         
-        /// Get market depth information
+        /// Get market depth information, merging in the liquidity of active lender offers for native pools
         #[ink(message)]
         pub fn get_market_depth_info(&self, pool_id: u64) -> Result<(Vec<(u16, Balance, u32)>, bool, String), LendingError> {
-            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
-            
-            let depth_info: Vec<(u16, Balance, u32)> = pool.market_depth_levels.iter()
+            let pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
+
+            let mut depth_info: Vec<(u16, Balance, u32)> = pool.market_depth_levels.iter()
                 .map(|level| (level.price_level, level.liquidity_available, level.order_count))
                 .collect();
-            
+
+            // Lender offers are funded in the native token, so only native pools see their liquidity
+            if pool.asset == Asset::Native {
+                for level in &self.offer_depth_levels {
+                    match depth_info.iter_mut().find(|(price_level, _, _)| *price_level == level.price_level) {
+                        Some((_, liquidity, orders)) => {
+                            *liquidity = liquidity.saturating_add(level.liquidity_available);
+                            *orders = orders.saturating_add(level.order_count);
+                        }
+                        None => depth_info.push((level.price_level, level.liquidity_available, level.order_count)),
+                    }
+                }
+                depth_info.sort_by_key(|(price_level, _, _)| *price_level);
+            }
+
             let distribution_summary = self.get_market_depth_summary(&pool)?;
             
             Ok((depth_info, pool.depth_based_pricing, distribution_summary))
@@ -3246,7 +7732,7 @@ pub mod lending_contract {
         #[ink(message)]
         pub fn set_depth_based_pricing(&mut self, pool_id: u64, enabled: bool) -> Result<(), LendingError> {
             let caller = self.env().caller();
-            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::LoanNotFound)?;
+            let mut pool = self.liquidity_pools.get(pool_id).ok_or(LendingError::PoolNotFound)?;
             
             // Only pool creator can change depth-based pricing
             if pool.liquidity_providers.is_empty() || pool.liquidity_providers[0].account != caller {
@@ -3274,20 +7760,20 @@ pub mod lending_contract {
                 let mut new_level = level.clone();
                 
                 // Calculate target liquidity for this level
-                let target_liquidity = total_liquidity / pool.market_depth_levels.len() as u128;
+                let target_liquidity = total_liquidity.safe_div(pool.market_depth_levels.len() as u128)?;
                 let current_liquidity = level.liquidity_available;
                 
                 if target_liquidity > current_liquidity {
-                    let to_add = (target_liquidity - current_liquidity).min(max_per_level - current_liquidity);
-                    new_level.liquidity_available = current_liquidity + to_add;
-                    total_moved += to_add;
+                    let to_add = (target_liquidity - current_liquidity).min(max_per_level.saturating_sub(current_liquidity));
+                    new_level.liquidity_available = current_liquidity.safe_add(to_add)?;
+                    total_moved = total_moved.safe_add(to_add)?;
                 } else if current_liquidity > target_liquidity {
-                    let to_remove = (current_liquidity - target_liquidity).min(current_liquidity - min_per_level);
-                    new_level.liquidity_available = current_liquidity - to_remove;
-                    total_moved += to_remove;
+                    let to_remove = (current_liquidity - target_liquidity).min(current_liquidity.saturating_sub(min_per_level));
+                    new_level.liquidity_available = current_liquidity.safe_sub(to_remove)?;
+                    total_moved = total_moved.safe_add(to_remove)?;
                 }
                 
-                new_level.last_updated = self.env().block_number() as u64;
+                new_level.last_updated = self.now();
                 new_levels.push(new_level);
             }
             
@@ -3307,7 +7793,7 @@ pub mod lending_contract {
                 .sum();
             
             let level_count = pool.market_depth_levels.len();
-            let avg_depth = if level_count > 0 { total_depth / level_count as u128 } else { 0 };
+            let avg_depth = if level_count > 0 { total_depth.safe_div(level_count as u128)? } else { 0 };
             
             Ok(format!("Total: {}, Levels: {}, Avg: {}", total_depth, level_count, avg_depth))
         }
@@ -3326,7 +7812,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let _user_profile = self.user_profiles.get(user_id).ok_or(LendingError::LoanNotFound)?;
+            let _user_profile = self.user_profiles.get(user_id).ok_or(LendingError::UserNotFound)?;
             let current_score = self.credit_scores.get(user_id);
             
             // Calculate credit score based on various factors
@@ -3334,23 +7820,23 @@ pub mod lending_contract {
             
             // Factor 1: Payment History (35% weight)
             let payment_score = self.calculate_payment_history_score(user_id)?;
-            total_score += (payment_score * 35) / 100;
+            total_score = total_score.safe_add(payment_score.safe_mul(35)?.safe_div(100)?)?;
             
             // Factor 2: Credit Utilization (30% weight)
             let utilization_score = self.calculate_credit_utilization_score(user_id)?;
-            total_score += (utilization_score * 30) / 100;
+            total_score = total_score.safe_add(utilization_score.safe_mul(30)?.safe_div(100)?)?;
             
             // Factor 3: Credit History Length (15% weight)
             let history_score = self.calculate_credit_history_score(user_id)?;
-            total_score += (history_score * 15) / 100;
+            total_score = total_score.safe_add(history_score.safe_mul(15)?.safe_div(100)?)?;
             
             // Factor 4: New Credit (10% weight)
             let new_credit_score = self.calculate_new_credit_score(user_id)?;
-            total_score += (new_credit_score * 10) / 100;
+            total_score = total_score.safe_add(new_credit_score.safe_mul(10)?.safe_div(100)?)?;
             
             // Factor 5: Credit Mix (10% weight)
             let mix_score = self.calculate_credit_mix_score(user_id)?;
-            total_score += (mix_score * 10) / 100;
+            total_score = total_score.safe_add(mix_score.safe_mul(10)?.safe_div(100)?)?;
             
             // Ensure score is within valid range (300-850)
             total_score = total_score.min(850).max(300);
@@ -3393,13 +7879,13 @@ pub mod lending_contract {
                         description: "Credit mix score".to_string(),
                     },
                 ],
-                last_updated: self.env().block_number() as u64,
+                last_updated: self.now(),
                 score_history: vec![
                     CreditScoreRecord {
                         score: total_score,
-                        change: (total_score as i16) - (old_score as i16),
+                        change: (total_score as i16).saturating_sub(old_score as i16),
                         reason: "Credit score calculated".to_string(),
-                        timestamp: self.env().block_number() as u64,
+                        timestamp: self.now(),
                     }
                 ],
                 risk_level: risk_level.clone(),
@@ -3441,10 +7927,10 @@ pub mod lending_contract {
             let collateral_req = CollateralRequirement {
                 collateral_type,
                 required_amount,
-                current_amount: 0, // Initially no collateral provided
+                current_amount: self.escrowed_collateral.get(loan_id).unwrap_or(0),
                 liquidation_threshold,
                 maintenance_margin,
-                last_updated: self.env().block_number() as u64,
+                last_updated: self.now(),
             };
             
             loan.collateral_requirements.push(collateral_req);
@@ -3471,7 +7957,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let policy_id = self.total_insurance_policies + 1;
+            let policy_id = self.total_insurance_policies.safe_add(1)?;
             let policy = InsurancePolicy {
                 policy_id,
                 insured_amount,
@@ -3479,7 +7965,7 @@ pub mod lending_contract {
                 coverage_period,
                 deductible,
                 status: InsuranceStatus::Active,
-                created_at: self.env().block_number() as u64,
+                created_at: self.now(),
             };
             
             self.insurance_policies.insert(policy_id, &policy);
@@ -3513,7 +7999,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let rule_id = self.total_fraud_rules + 1;
+            let rule_id = self.total_fraud_rules.safe_add(1)?;
             let rule = FraudDetectionRule {
                 rule_id,
                 rule_type,
@@ -3599,12 +8085,12 @@ pub mod lending_contract {
             let mut updated_records = current_records.clone();
             
             let record = ComplianceRecord {
-                record_id: updated_records.len() as u64 + 1,
+                record_id: (updated_records.len() as u64).safe_add(1)?,
                 user_id,
                 compliance_type: compliance_type.clone(),
                 status: status.clone(),
-                verification_date: self.env().block_number() as u64,
-                expiry_date: self.env().block_number() as u64 + 5184000, // 1 year
+                verification_date: self.now(),
+                expiry_date: self.now().safe_add(MILLIS_PER_YEAR)?, // 1 year
                 documents,
             };
             
@@ -3617,7 +8103,7 @@ pub mod lending_contract {
                 compliance_type: format!("{:?}", compliance_type),
                 old_status: "Unknown".to_string(),
                 new_status: format!("{:?}", status),
-                verification_date: self.env().block_number() as u64,
+                verification_date: self.now(),
             });
             
             Ok(())
@@ -3626,7 +8112,7 @@ pub mod lending_contract {
         /// Get credit score information for a user
         #[ink(message)]
         pub fn get_credit_score_info(&self, user_id: AccountId) -> Result<(u16, String, Vec<(String, u16, String)>), LendingError> {
-            let credit_score = self.credit_scores.get(user_id).ok_or(LendingError::LoanNotFound)?;
+            let credit_score = self.credit_scores.get(user_id).ok_or(LendingError::CreditScoreNotFound)?;
             
             let factors: Vec<(String, u16, String)> = credit_score.factors.iter()
                 .map(|f| (
@@ -3662,20 +8148,23 @@ pub mod lending_contract {
         }
         
         // Helper functions for credit score calculation
-        fn calculate_payment_history_score(&self, _user_id: AccountId) -> Result<u16, LendingError> {
-            // Simplified payment history calculation
-            // In a real implementation, this would analyze actual payment data
-            Ok(700) // Default score
+        fn calculate_payment_history_score(&self, user_id: AccountId) -> Result<u16, LendingError> {
+            // Simplified payment history calculation: each default costs 200 points
+            let default_count = self.user_profiles.get(user_id).map(|p| p.default_count).unwrap_or(0);
+            let default_penalty = default_count.saturating_mul(200).min(700) as u16;
+            700u16.safe_sub(default_penalty)
         }
         
         fn calculate_credit_utilization_score(&self, user_id: AccountId) -> Result<u16, LendingError> {
             // Simplified credit utilization calculation
-            let user_profile = self.user_profiles.get(user_id).ok_or(LendingError::LoanNotFound)?;
+            let user_profile = self.user_profiles.get(user_id).ok_or(LendingError::UserNotFound)?;
             
             if user_profile.total_borrowed == 0 {
                 Ok(800) // No debt = excellent score
+            } else if user_profile.total_lent == 0 {
+                Ok(400) // Borrowing with nothing lent counts as full utilization
             } else {
-                let utilization = (user_profile.total_borrowed * 10000) / user_profile.total_lent;
+                let utilization = bps_ratio(user_profile.total_borrowed, user_profile.total_lent, Rounding::Down)?;
                 if utilization < 3000 { // < 30%
                     Ok(750)
                 } else if utilization < 5000 { // < 50%
@@ -3690,7 +8179,7 @@ pub mod lending_contract {
         
         fn calculate_credit_history_score(&self, user_id: AccountId) -> Result<u16, LendingError> {
             // Simplified credit history calculation
-            let user_profile = self.user_profiles.get(user_id).ok_or(LendingError::LoanNotFound)?;
+            let user_profile = self.user_profiles.get(user_id).ok_or(LendingError::UserNotFound)?;
             
             if user_profile.active_loans.len() > 5 {
                 Ok(800) // Long history
@@ -3768,23 +8257,23 @@ pub mod lending_contract {
         #[ink(message)]
         pub fn update_loan_metrics(&mut self, loan_id: u64) -> Result<(), LendingError> {
             let loan = self.loans.get(loan_id).ok_or(LendingError::LoanNotFound)?;
-            let current_block = self.env().block_number() as u64;
+            let current_time = self.now();
             
             // Calculate performance metrics
             let total_interest_paid = loan.total_paid.saturating_sub(loan.amount);
-            let total_fees_paid = loan.total_late_fees + 
-                (loan.extension_count as u128 * loan.extension_fee_rate as u128 * loan.amount / 10000) +
-                (loan.refinance_count as u128 * loan.refinance_fee_rate as u128 * loan.amount / 10000);
+            let total_fees_paid = loan.total_late_fees
+                .safe_add(bps_mul(loan.amount, (loan.extension_count as u128).safe_mul(loan.extension_fee_rate as u128)?, Rounding::Up)?)?
+                .safe_add(bps_mul(loan.amount, (loan.refinance_count as u128).safe_mul(loan.refinance_fee_rate as u128)?, Rounding::Up)?)?;
             
             let days_to_repayment = if loan.status == LoanStatus::Repaid || loan.status == LoanStatus::EarlyRepaid {
-                current_block.saturating_sub(loan.created_at)
+                current_time.saturating_sub(loan.created_at)
             } else {
                 0
             };
             
             let payment_efficiency = if loan.total_paid > 0 {
-                let expected_total = loan.amount + (loan.amount * loan.interest_rate as u128 / 10000);
-                ((loan.total_paid * 10000) / expected_total) as u16
+                let expected_total = loan.amount.safe_add(bps_mul(loan.amount, loan.interest_rate as u128, Rounding::Up)?)?;
+                u16::try_from(bps_ratio(loan.total_paid, expected_total, Rounding::Down)?).map_err(|_| LendingError::ArithmeticOverflow)?
             } else {
                 0
             };
@@ -3798,18 +8287,21 @@ pub mod lending_contract {
                     RiskLevel::Poor => 5500,
                     RiskLevel::VeryPoor => 4000,
                 };
-                (risk_factor * loan.interest_rate as u32 / 10000) as u16
+                u16::try_from(bps_mul(loan.interest_rate as u128, risk_factor, Rounding::Up)?).map_err(|_| LendingError::ArithmeticOverflow)?
             } else {
                 loan.interest_rate
             };
             
             let collateral_utilization = if loan.collateral > 0 {
-                ((loan.amount * 10000) / loan.collateral) as u16
+                bps_ratio(loan.amount, loan.collateral, Rounding::Down)?.min(10000) as u16
             } else {
                 0
             };
             
-            let performance_score = (payment_efficiency + risk_adjusted_return + (10000 - collateral_utilization)) / 3;
+            let performance_score = ((payment_efficiency as u32)
+                .safe_add(risk_adjusted_return as u32)?
+                .safe_add(10000u32.safe_sub(collateral_utilization as u32)?)?
+                / 3) as u16;
             
             let metrics = LoanPerformanceMetrics {
                 loan_id,
@@ -3825,11 +8317,11 @@ pub mod lending_contract {
                 extension_count: loan.extension_count,
                 refinance_count: loan.refinance_count,
                 performance_score,
-                last_updated: current_block,
+                last_updated: current_time,
             };
             
             self.loan_performance_metrics.insert(loan_id, &metrics);
-            self.total_loan_metrics += 1;
+            self.total_loan_metrics = self.total_loan_metrics.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(LoanMetricsUpdated {
@@ -3847,15 +8339,15 @@ pub mod lending_contract {
         #[ink(message)]
         pub fn update_portfolio_analytics(&mut self, user_id: AccountId) -> Result<(), LendingError> {
             let user_profile = self.user_profiles.get(user_id).ok_or(LendingError::UserNotFound)?;
-            let current_block = self.env().block_number() as u64;
+            let current_time = self.now();
             
             // Calculate portfolio metrics
-            let total_portfolio_value = user_profile.total_borrowed + user_profile.total_lent;
+            let total_portfolio_value = user_profile.total_borrowed.safe_add(user_profile.total_lent)?;
             let active_loans_count = user_profile.active_loans.len() as u32;
             
             // Simplified calculations - in real implementation would analyze actual loan data
             let completed_loans_count = if user_profile.total_borrowed > 0 { 1 } else { 0 };
-            let defaulted_loans_count = 0; // Would need to track defaults
+            let defaulted_loans_count = user_profile.default_count;
             let average_loan_size = if active_loans_count > 0 {
                 user_profile.total_borrowed / active_loans_count as u128
             } else {
@@ -3880,7 +8372,7 @@ pub mod lending_contract {
                 expected_return,
                 volatility_score,
                 liquidity_score,
-                last_updated: current_block,
+                last_updated: current_time,
             };
             
             self.user_portfolio_analytics.insert(user_id, &analytics);
@@ -3899,10 +8391,11 @@ pub mod lending_contract {
         /// Update market statistics
         #[ink(message)]
         pub fn update_market_statistics(&mut self) -> Result<(), LendingError> {
-            let current_block = self.env().block_number() as u64;
+            let current_time = self.now();
             
-            // Calculate market metrics
-            let total_market_cap = self.total_liquidity;
+            // Calculate market metrics in the native currency
+            let native_liquidity = self.asset_totals.get(Asset::Native).unwrap_or_default().liquidity;
+            let total_market_cap = native_liquidity;
             let total_active_loans = self.total_loans;
             
             // Calculate average interest rate from active loans
@@ -3912,8 +8405,8 @@ pub mod lending_contract {
             for i in 1..=self.total_loans {
                 if let Some(loan) = self.loans.get(i) {
                     if loan.status == LoanStatus::Active {
-                        total_rate += loan.interest_rate as u32;
-                        active_loan_count += 1;
+                        total_rate = total_rate.safe_add(loan.interest_rate as u32)?;
+                        active_loan_count = active_loan_count.safe_add(1)?;
                     }
                 }
             }
@@ -3926,10 +8419,14 @@ pub mod lending_contract {
             
             // Simplified market metrics
             let market_volatility = 5000; // 50% - would need historical data
-            let liquidity_depth = if self.total_liquidity > 0 { 7000 } else { 3000 };
-            let default_rate = 500; // 5% - would need actual default tracking
-            let utilization_rate = if self.total_liquidity > 0 {
-                ((self.total_loans as u128 * 10000) / self.total_liquidity) as u16
+            let liquidity_depth = if native_liquidity > 0 { 7000 } else { 3000 };
+            let default_rate = if self.total_loans > 0 {
+                bps_ratio(self.total_defaults as u128, self.total_loans as u128, Rounding::Down)?.min(10000) as u16
+            } else {
+                0
+            };
+            let utilization_rate = if native_liquidity > 0 {
+                bps_ratio(self.total_loans as u128, native_liquidity, Rounding::Down)?.min(u16::MAX as u128) as u16
             } else {
                 0
             };
@@ -3945,18 +8442,18 @@ pub mod lending_contract {
                 default_rate,
                 utilization_rate,
                 market_trend,
-                last_updated: current_block,
+                last_updated: current_time,
             };
             
             self.market_statistics = new_stats;
             
             // Add to historical data
             let historical_point = HistoricalDataPoint {
-                timestamp: current_block,
+                timestamp: current_time,
                 total_loans: self.total_loans,
-                total_volume: self.total_liquidity,
+                total_volume: native_liquidity,
                 average_rate: average_interest_rate,
-                default_count: 0, // Would need to track defaults
+                default_count: self.total_defaults as u32,
                 active_users: self.total_users as u32,
             };
             
@@ -3986,8 +8483,8 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let benchmark_id = self.total_benchmarks + 1;
-            let current_block = self.env().block_number() as u64;
+            let benchmark_id = self.total_benchmarks.safe_add(1)?;
+            let current_time = self.now();
             
             let benchmark = PerformanceBenchmark {
                 benchmark_id,
@@ -3996,11 +8493,11 @@ pub mod lending_contract {
                 target_score,
                 current_score: 0,
                 weight,
-                last_updated: current_block,
+                last_updated: current_time,
             };
             
             self.performance_benchmarks.insert(benchmark_id, &benchmark);
-            self.total_benchmarks += 1;
+            self.total_benchmarks = self.total_benchmarks.safe_add(1)?;
             
             Ok(benchmark_id)
         }
@@ -4012,7 +8509,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let current_block = self.env().block_number() as u64;
+            let current_time = self.now();
             
             for i in 1..=self.total_benchmarks {
                 if let Some(mut benchmark) = self.performance_benchmarks.get(i) {
@@ -4027,7 +8524,7 @@ pub mod lending_contract {
                     };
                     
                     benchmark.current_score = current_score;
-                    benchmark.last_updated = current_block;
+                    benchmark.last_updated = current_time;
                     
                     self.performance_benchmarks.insert(i, &benchmark);
                     
@@ -4051,8 +8548,8 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let report_id = self.total_analytics_reports + 1;
-            let current_block = self.env().block_number() as u64;
+            let report_id = self.total_analytics_reports.safe_add(1)?;
+            let current_time = self.now();
             
             // Generate report metrics
             let mut metrics = Vec::new();
@@ -4076,14 +8573,14 @@ pub mod lending_contract {
             
             metrics.push(AnalyticsMetric {
                 name: "Average Interest Rate".to_string(),
-                value: format!("{:.2}%", self.market_statistics.average_interest_rate as f64 / 100.0),
+                value: format!("{}.{:02}%", self.market_statistics.average_interest_rate / 100, self.market_statistics.average_interest_rate % 100),
                 unit: "Percentage".to_string(),
                 change_from_previous: 0,
                 trend: MetricTrend::Stable,
             });
             
             // Generate summary and recommendations
-            let summary = format!("Analytics report for {:?} period ending at block {}", report_type, current_block);
+            let summary = format!("Analytics report for {:?} period ending at {} ms", report_type, current_time);
             let recommendations = vec![
                 "Monitor market volatility trends".to_string(),
                 "Review risk management parameters".to_string(),
@@ -4093,7 +8590,7 @@ pub mod lending_contract {
             let report = AnalyticsReport {
                 report_id,
                 report_type: report_type.clone(),
-                generated_at: current_block,
+                generated_at: current_time,
                 data_period,
                 summary,
                 metrics,
@@ -4101,13 +8598,13 @@ pub mod lending_contract {
             };
             
             self.analytics_reports.insert(report_id, &report);
-            self.total_analytics_reports += 1;
+            self.total_analytics_reports = self.total_analytics_reports.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(AnalyticsReportGenerated {
                 report_id,
                 report_type: format!("{:?}", report_type),
-                generated_at: current_block,
+                generated_at: current_time,
                 metrics_count: report.metrics.len() as u32,
             });
             
@@ -4125,8 +8622,8 @@ pub mod lending_contract {
             
             for i in 1..=self.total_loan_metrics {
                 if let Some(metrics) = self.loan_performance_metrics.get(i) {
-                    total_score += metrics.performance_score as u32;
-                    count += 1;
+                    total_score = total_score.saturating_add(metrics.performance_score as u32);
+                    count = count.saturating_add(1);
                 }
             }
             
@@ -4143,7 +8640,7 @@ pub mod lending_contract {
             let volatility = self.market_statistics.market_volatility;
             
             // Lower default rate and volatility = higher score
-            let score = 10000 - (default_rate + volatility) / 2;
+            let score = 10000u16.saturating_sub(default_rate.saturating_add(volatility) / 2);
             score.max(1000).min(10000) // Ensure score is between 10% and 100%
         }
 
@@ -4161,7 +8658,7 @@ pub mod lending_contract {
                 6000
             };
             
-            (utilization_score + depth) / 2
+            ((utilization_score as u32).saturating_add(depth as u32) / 2) as u16
         }
 
         fn calculate_user_experience_score(&self) -> u16 {
@@ -4173,7 +8670,7 @@ pub mod lending_contract {
                 return 5000;
             }
             
-            let activity_rate = ((active_loans as u32 * 10000) / total_users as u32) as u16;
+            let activity_rate = ((active_loans as u128).saturating_mul(10000) / total_users as u128).min(10000) as u16;
             activity_rate.min(10000)
         }
 
@@ -4202,7 +8699,7 @@ pub mod lending_contract {
         /// Get loan performance metrics
         #[ink(message)]
         pub fn get_loan_metrics(&self, loan_id: u64) -> Result<LoanPerformanceMetrics, LendingError> {
-            self.loan_performance_metrics.get(loan_id).ok_or(LendingError::LoanNotFound)
+            self.loan_performance_metrics.get(loan_id).ok_or(LendingError::LoanMetricsNotFound)
         }
 
         /// Get user portfolio analytics
@@ -4232,7 +8729,7 @@ pub mod lending_contract {
         /// Get analytics report
         #[ink(message)]
         pub fn get_analytics_report(&self, report_id: u64) -> Result<AnalyticsReport, LendingError> {
-            self.analytics_reports.get(report_id).ok_or(LendingError::LoanNotFound)
+            self.analytics_reports.get(report_id).ok_or(LendingError::ReportNotFound)
         }
 
         /// Get historical data points
@@ -4297,10 +8794,10 @@ pub mod lending_contract {
             
             // Flash loan fee (typically 0.09% = 9 basis points)
             let fee_rate = 9;
-            let fee_amount = (amount * fee_rate as u128) / 10000;
-            let total_repay_amount = amount + fee_amount;
+            let fee_amount = bps_mul(amount, fee_rate as u128, Rounding::Up)?;
+            let total_repay_amount = amount.safe_add(fee_amount)?;
             
-            let flash_loan_id = self.total_flash_loans + 1;
+            let flash_loan_id = self.total_flash_loans.safe_add(1)?;
             
             let flash_loan = FlashLoan {
                 id: flash_loan_id,
@@ -4319,7 +8816,7 @@ pub mod lending_contract {
             };
             
             self.flash_loans.insert(flash_loan_id, &flash_loan);
-            self.total_flash_loans += 1;
+            self.total_flash_loans = self.total_flash_loans.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(FlashLoanExecuted {
@@ -4334,23 +8831,29 @@ pub mod lending_contract {
             Ok(flash_loan_id)
         }
 
-        /// Repay a flash loan
-        #[ink(message)]
+        /// Repay a flash loan, sending the amount plus fee with the call
+        #[ink(message, payable)]
         pub fn repay_flash_loan(&mut self, flash_loan_id: u64) -> Result<(), LendingError> {
             let caller = self.env().caller();
             let current_block = self.env().block_number() as u64;
             
             let mut flash_loan = self.flash_loans.get(flash_loan_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::FlashLoanNotFound)?;
             
             if flash_loan.borrower != caller {
                 return Err(LendingError::Unauthorized);
             }
             
             if flash_loan.status != FlashLoanStatus::Executed {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::FlashLoanNotExecuted);
             }
             
+            if self.env().transferred_value() != flash_loan.total_repay_amount {
+                return Err(LendingError::InvalidAmount);
+            }
+            
+            self.collect_protocol_fee(Asset::Native, flash_loan.fee_amount, FeeSource::FlashLoan)?;
+            
             // Update flash loan status
             flash_loan.status = FlashLoanStatus::Repaid;
             flash_loan.repaid_at = Some(current_block);
@@ -4381,7 +8884,6 @@ pub mod lending_contract {
             symbol: String,
             decimals: u8,
             total_supply: u128,
-            valuation: Balance,
             rarity_score: u16,
             market_demand: u16,
         ) -> Result<u64, LendingError> {
@@ -4389,11 +8891,18 @@ pub mod lending_contract {
             let current_block = self.env().block_number() as u64;
             
             // Validate parameters
-            if valuation == 0 || rarity_score > 10000 || market_demand > 10000 {
+            if rarity_score > 10000 || market_demand > 10000 {
                 return Err(LendingError::InvalidAmount);
             }
             
-            let nft_id = self.total_nft_collateral + 1;
+            // NFTs are valued at the collection's conservative oracle price, so the collection needs a feed
+            let collection = Asset::Psp34(contract_address);
+            if !self.price_feeds.contains(collection) {
+                return Err(LendingError::PriceFeedNotFound);
+            }
+            let valuation = self.unit_price(collection, PriceBound::Lower)?.0;
+            
+            let nft_id = self.total_nft_collateral.safe_add(1)?;
             
             let metadata = NFTMetadata {
                 token_id,
@@ -4419,7 +8928,7 @@ pub mod lending_contract {
             };
             
             self.nft_collateral.insert(nft_id, &nft_collateral);
-            self.total_nft_collateral += 1;
+            self.total_nft_collateral = self.total_nft_collateral.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(NFTCollateralAdded {
@@ -4450,7 +8959,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let bridge_id = self.total_cross_chain_bridges + 1;
+            let bridge_id = self.total_cross_chain_bridges.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             
             let bridge = CrossChainBridge {
@@ -4469,7 +8978,7 @@ pub mod lending_contract {
             };
             
             self.cross_chain_bridges.insert(bridge_id, &bridge);
-            self.total_cross_chain_bridges += 1;
+            self.total_cross_chain_bridges = self.total_cross_chain_bridges.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(CrossChainBridgeCreated {
@@ -4496,17 +9005,17 @@ pub mod lending_contract {
             let current_block = self.env().block_number() as u64;
             
             let bridge = self.cross_chain_bridges.get(bridge_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::BridgeNotFound)?;
             
             if bridge.status != BridgeStatus::Active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::BridgeNotActive);
             }
             
             if amount < bridge.min_transfer || amount > bridge.max_transfer {
                 return Err(LendingError::InvalidAmount);
             }
             
-            let transfer_id = self.total_cross_chain_transfers + 1;
+            let transfer_id = self.total_cross_chain_transfers.safe_add(1)?;
             
             let transfer = CrossChainTransfer {
                 transfer_id,
@@ -4522,7 +9031,7 @@ pub mod lending_contract {
             };
             
             self.cross_chain_transfers.insert(transfer_id, &transfer);
-            self.total_cross_chain_transfers += 1;
+            self.total_cross_chain_transfers = self.total_cross_chain_transfers.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(CrossChainTransferInitiated {
@@ -4553,7 +9062,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let pool_id = self.total_staking_pools + 1;
+            let pool_id = self.total_staking_pools.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             
             let pool = StakingPool {
@@ -4572,7 +9081,7 @@ pub mod lending_contract {
             };
             
             self.staking_pools.insert(pool_id, &pool);
-            self.total_staking_pools += 1;
+            self.total_staking_pools = self.total_staking_pools.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(StakingPoolCreated {
@@ -4598,10 +9107,10 @@ pub mod lending_contract {
             let current_block = self.env().block_number() as u64;
             
             let pool = self.staking_pools.get(pool_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::StakingPoolNotFound)?;
             
             if !pool.is_active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::StakingPoolInactive);
             }
             
             if amount < pool.min_stake || amount > pool.max_stake {
@@ -4609,15 +9118,15 @@ pub mod lending_contract {
             }
             
             if lock_period_index as usize >= pool.lock_periods.len() {
-                return Err(LendingError::InvalidAmount);
+                return Err(LendingError::InvalidLockPeriod);
             }
             
             let lock_period = pool.lock_periods[lock_period_index as usize];
             let multiplier = pool.multipliers[lock_period_index as usize];
             let _early_unstake_penalty = pool.early_unstake_penalties[lock_period_index as usize];
             
-            let position_id = self.total_staking_positions + 1;
-            let unlock_time = current_block + lock_period;
+            let position_id = self.total_staking_positions.safe_add(1)?;
+            let unlock_time = current_block.safe_add(lock_period)?;
             
             let position = StakingPosition {
                 staker: caller,
@@ -4632,7 +9141,7 @@ pub mod lending_contract {
             };
             
             self.staking_positions.insert(position_id, &position);
-            self.total_staking_positions += 1;
+            self.total_staking_positions = self.total_staking_positions.safe_add(1)?;
             
             // Update user positions
             let mut user_positions = self.user_staking_positions.get(caller).unwrap_or(Vec::new());
@@ -4672,7 +9181,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let campaign_id = self.total_liquidity_mining_campaigns + 1;
+            let campaign_id = self.total_liquidity_mining_campaigns.safe_add(1)?;
             let _current_block = self.env().block_number() as u64;
             
             let campaign = LiquidityMining {
@@ -4698,7 +9207,7 @@ pub mod lending_contract {
             // Note: current_block is used implicitly in the campaign creation
             
             self.liquidity_mining_campaigns.insert(campaign_id, &campaign);
-            self.total_liquidity_mining_campaigns += 1;
+            self.total_liquidity_mining_campaigns = self.total_liquidity_mining_campaigns.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(LiquidityMiningCampaignCreated {
@@ -4724,21 +9233,21 @@ pub mod lending_contract {
             let current_block = self.env().block_number() as u64;
             
             let campaign = self.liquidity_mining_campaigns.get(campaign_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::CampaignNotFound)?;
             
             if !campaign.is_active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::CampaignInactive);
             }
             
             if current_block < campaign.start_block || current_block > campaign.end_block {
-                return Err(LendingError::InvalidAmount);
+                return Err(LendingError::CampaignNotRunning);
             }
             
             if amount < campaign.min_stake || amount > campaign.max_stake {
                 return Err(LendingError::InvalidAmount);
             }
             
-            let position_id = self.total_liquidity_mining_positions + 1;
+            let position_id = self.total_liquidity_mining_positions.safe_add(1)?;
             let multiplier = if campaign.bonus_multipliers.is_empty() {
                 1000 // 1x default
             } else {
@@ -4758,7 +9267,7 @@ pub mod lending_contract {
             };
             
             self.liquidity_mining_positions.insert(position_id, &position);
-            self.total_liquidity_mining_positions += 1;
+            self.total_liquidity_mining_positions = self.total_liquidity_mining_positions.safe_add(1)?;
             
             // Update user positions
             let mut user_positions = self.user_liquidity_mining_positions.get(caller).unwrap_or(Vec::new());
@@ -4784,49 +9293,49 @@ pub mod lending_contract {
         /// Get flash loan information
         #[ink(message)]
         pub fn get_flash_loan(&self, flash_loan_id: u64) -> Result<FlashLoan, LendingError> {
-            self.flash_loans.get(flash_loan_id).ok_or(LendingError::LoanNotFound)
+            self.flash_loans.get(flash_loan_id).ok_or(LendingError::FlashLoanNotFound)
         }
 
         /// Get NFT collateral information
         #[ink(message)]
         pub fn get_nft_collateral(&self, nft_id: u64) -> Result<NFTCollateral, LendingError> {
-            self.nft_collateral.get(nft_id).ok_or(LendingError::LoanNotFound)
+            self.nft_collateral.get(nft_id).ok_or(LendingError::NftCollateralNotFound)
         }
 
         /// Get cross-chain bridge information
         #[ink(message)]
         pub fn get_cross_chain_bridge(&self, bridge_id: u64) -> Result<CrossChainBridge, LendingError> {
-            self.cross_chain_bridges.get(bridge_id).ok_or(LendingError::LoanNotFound)
+            self.cross_chain_bridges.get(bridge_id).ok_or(LendingError::BridgeNotFound)
         }
 
         /// Get cross-chain transfer information
         #[ink(message)]
         pub fn get_cross_chain_transfer(&self, transfer_id: u64) -> Result<CrossChainTransfer, LendingError> {
-            self.cross_chain_transfers.get(transfer_id).ok_or(LendingError::LoanNotFound)
+            self.cross_chain_transfers.get(transfer_id).ok_or(LendingError::CrossChainTransferNotFound)
         }
 
         /// Get staking pool information
         #[ink(message)]
         pub fn get_staking_pool(&self, pool_id: u64) -> Result<StakingPool, LendingError> {
-            self.staking_pools.get(pool_id).ok_or(LendingError::LoanNotFound)
+            self.staking_pools.get(pool_id).ok_or(LendingError::StakingPoolNotFound)
         }
 
         /// Get staking position information
         #[ink(message)]
         pub fn get_staking_position(&self, position_id: u64) -> Result<StakingPosition, LendingError> {
-            self.staking_positions.get(position_id).ok_or(LendingError::LoanNotFound)
+            self.staking_positions.get(position_id).ok_or(LendingError::StakingPositionNotFound)
         }
 
         /// Get liquidity mining campaign information
         #[ink(message)]
         pub fn get_liquidity_mining_campaign(&self, campaign_id: u64) -> Result<LiquidityMining, LendingError> {
-            self.liquidity_mining_campaigns.get(campaign_id).ok_or(LendingError::LoanNotFound)
+            self.liquidity_mining_campaigns.get(campaign_id).ok_or(LendingError::CampaignNotFound)
         }
 
         /// Get liquidity mining position information
         #[ink(message)]
         pub fn get_liquidity_mining_position(&self, position_id: u64) -> Result<LiquidityMiningPosition, LendingError> {
-            self.liquidity_mining_positions.get(position_id).ok_or(LendingError::LoanNotFound)
+            self.liquidity_mining_positions.get(position_id).ok_or(LendingError::MiningPositionNotFound)
         }
 
         /// Get user staking positions
@@ -4875,7 +9384,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let token_id = self.total_governance_tokens + 1;
+            let token_id = self.total_governance_tokens.safe_add(1)?;
             let _current_block = self.env().block_number() as u64;
             
             let token = GovernanceToken {
@@ -4893,7 +9402,7 @@ pub mod lending_contract {
             };
             
             self.governance_tokens.insert(token_id, &token);
-            self.total_governance_tokens += 1;
+            self.total_governance_tokens = self.total_governance_tokens.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(GovernanceTokenCreated {
@@ -4921,28 +9430,28 @@ pub mod lending_contract {
             }
             
             let mut token = self.governance_tokens.get(token_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::GovernanceTokenNotFound)?;
             
             if !token.is_active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::GovernanceTokenInactive);
             }
             
-            if token.circulating_supply + amount > token.total_supply {
-                return Err(LendingError::InvalidAmount);
+            if token.circulating_supply.safe_add(amount)? > token.total_supply {
+                return Err(LendingError::TokenSupplyExceeded);
             }
             
             // Update token supply
-            token.circulating_supply += amount;
+            token.circulating_supply = token.circulating_supply.safe_add(amount)?;
             self.governance_tokens.insert(token_id, &token);
             
             // Update user balance
             let current_balance = self.user_governance_tokens.get(recipient).unwrap_or(0);
-            self.user_governance_tokens.insert(recipient, &(current_balance + amount));
+            self.user_governance_tokens.insert(recipient, &current_balance.safe_add(amount)?);
             
             // Update voting power
-            let voting_power = (amount * token.voting_power_multiplier as u128) / 1000;
+            let voting_power = mul_div(amount, token.voting_power_multiplier as u128, 1000, Rounding::Down)?;
             let current_voting_power = self.user_voting_power.get(recipient).unwrap_or(0);
-            self.user_voting_power.insert(recipient, &(current_voting_power + voting_power));
+            self.user_voting_power.insert(recipient, &current_voting_power.safe_add(voting_power)?);
             
             Ok(())
         }
@@ -4968,15 +9477,15 @@ pub mod lending_contract {
             
             // Check if user has enough governance tokens to create proposal
             let user_balance = self.user_governance_tokens.get(caller).unwrap_or(0);
-            let token = self.governance_tokens.get(1).ok_or(LendingError::LoanNotFound)?; // Assume first token
+            let token = self.governance_tokens.get(1).ok_or(LendingError::GovernanceTokenNotFound)?; // Assume first token
             
             if user_balance < token.min_stake_for_proposal {
-                return Err(LendingError::InvalidAmount);
+                return Err(LendingError::InsufficientProposalStake);
             }
             
-            let proposal_id = self.total_proposals + 1;
+            let proposal_id = self.total_proposals.safe_add(1)?;
             let voting_start = current_block;
-            let voting_end = current_block + voting_period;
+            let voting_end = current_block.safe_add(voting_period)?;
             
             let proposal = GovernanceProposal {
                 proposal_id,
@@ -5002,7 +9511,7 @@ pub mod lending_contract {
             };
             
             self.governance_proposals.insert(proposal_id, &proposal);
-            self.total_proposals += 1;
+            self.total_proposals = self.total_proposals.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(GovernanceProposalCreated {
@@ -5031,14 +9540,14 @@ pub mod lending_contract {
             let current_block = self.env().block_number() as u64;
             
             let proposal = self.governance_proposals.get(proposal_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::ProposalNotFound)?;
             
             if proposal.status != ProposalStatus::Active {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::ProposalNotActive);
             }
             
             if current_block < proposal.voting_start || current_block > proposal.voting_end {
-                return Err(LendingError::InvalidAmount);
+                return Err(LendingError::VotingClosed);
             }
             
             // Check if user has already voted
@@ -5053,17 +9562,17 @@ pub mod lending_contract {
                     reason: None,
                 });
                 if vote.proposal_id == proposal_id {
-                    return Err(LendingError::InvalidAmount); // Already voted
+                    return Err(LendingError::AlreadyVoted);
                 }
             }
             
             // Get user's voting power
             let voting_power = self.user_voting_power.get(caller).unwrap_or(0);
             if voting_power == 0 {
-                return Err(LendingError::InvalidAmount);
+                return Err(LendingError::NoVotingPower);
             }
             
-            let vote_id = self.total_votes + 1;
+            let vote_id = self.total_votes.safe_add(1)?;
             
             let vote = Vote {
                 voter: caller,
@@ -5075,7 +9584,7 @@ pub mod lending_contract {
             };
             
             self.votes.insert(vote_id, &vote);
-            self.total_votes += 1;
+            self.total_votes = self.total_votes.safe_add(1)?;
             
             // Update user votes
             let mut user_votes = self.user_votes.get(caller).unwrap_or(Vec::new());
@@ -5085,15 +9594,17 @@ pub mod lending_contract {
             // Update proposal vote counts
             let mut proposal = self.governance_proposals.get(proposal_id).unwrap();
             match vote_choice {
-                VoteChoice::For => proposal.total_votes_for += voting_power,
-                VoteChoice::Against => proposal.total_votes_against += voting_power,
-                VoteChoice::Abstain => proposal.total_votes_abstain += voting_power,
+                VoteChoice::For => proposal.total_votes_for = proposal.total_votes_for.safe_add(voting_power)?,
+                VoteChoice::Against => proposal.total_votes_against = proposal.total_votes_against.safe_add(voting_power)?,
+                VoteChoice::Abstain => proposal.total_votes_abstain = proposal.total_votes_abstain.safe_add(voting_power)?,
             }
             
             // Check if proposal should be approved/rejected
-            let total_votes = proposal.total_votes_for + proposal.total_votes_against + proposal.total_votes_abstain;
+            let total_votes = proposal.total_votes_for
+                .safe_add(proposal.total_votes_against)?
+                .safe_add(proposal.total_votes_abstain)?;
             if total_votes >= proposal.quorum {
-                let approval_percentage = (proposal.total_votes_for * 10000) / total_votes;
+                let approval_percentage = bps_ratio(proposal.total_votes_for, total_votes, Rounding::Down)?;
                 if approval_percentage >= proposal.threshold.into() {
                     proposal.status = ProposalStatus::Approved;
                 } else {
@@ -5122,14 +9633,14 @@ pub mod lending_contract {
             let current_block = self.env().block_number() as u64;
             
             let mut proposal = self.governance_proposals.get(proposal_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::ProposalNotFound)?;
             
             if proposal.status != ProposalStatus::Approved {
-                return Err(LendingError::LoanNotActive);
+                return Err(LendingError::ProposalNotApproved);
             }
             
-            if current_block < proposal.voting_end + proposal.execution_delay {
-                return Err(LendingError::InvalidAmount); // Execution delay not met
+            if current_block < proposal.voting_end.safe_add(proposal.execution_delay)? {
+                return Err(LendingError::ExecutionDelayNotMet);
             }
             
             // Execute the proposal based on type
@@ -5192,7 +9703,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let treasury_id = self.total_treasuries + 1;
+            let treasury_id = self.total_treasuries.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             
             let treasury = Treasury {
@@ -5210,7 +9721,7 @@ pub mod lending_contract {
             };
             
             self.treasuries.insert(treasury_id, &treasury);
-            self.total_treasuries += 1;
+            self.total_treasuries = self.total_treasuries.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(TreasuryCreated {
@@ -5242,7 +9753,7 @@ pub mod lending_contract {
                 return Err(LendingError::InvalidAmount);
             }
             
-            let wallet_id = self.total_multi_sig_wallets + 1;
+            let wallet_id = self.total_multi_sig_wallets.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             
             let wallet = MultiSignatureWallet {
@@ -5260,7 +9771,7 @@ pub mod lending_contract {
             };
             
             self.multi_sig_wallets.insert(wallet_id, &wallet);
-            self.total_multi_sig_wallets += 1;
+            self.total_multi_sig_wallets = self.total_multi_sig_wallets.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(MultiSignatureWalletCreated {
@@ -5295,7 +9806,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
             
-            let dao_id = self.total_daos + 1;
+            let dao_id = self.total_daos.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             
             let dao = DAOConfiguration {
@@ -5317,7 +9828,7 @@ pub mod lending_contract {
             };
             
             self.dao_configurations.insert(dao_id, &dao);
-            self.total_daos += 1;
+            self.total_daos = self.total_daos.safe_add(1)?;
             
             // Emit event
             self.env().emit_event(DAOCreated {
@@ -5339,13 +9850,13 @@ pub mod lending_contract {
         /// Get governance token information
         #[ink(message)]
         pub fn get_governance_token(&self, token_id: u64) -> Result<GovernanceToken, LendingError> {
-            self.governance_tokens.get(token_id).ok_or(LendingError::LoanNotFound)
+            self.governance_tokens.get(token_id).ok_or(LendingError::GovernanceTokenNotFound)
         }
 
         /// Get governance proposal information
         #[ink(message)]
         pub fn get_governance_proposal(&self, proposal_id: u64) -> Result<GovernanceProposal, LendingError> {
-            self.governance_proposals.get(proposal_id).ok_or(LendingError::LoanNotFound)
+            self.governance_proposals.get(proposal_id).ok_or(LendingError::ProposalNotFound)
         }
 
         /// Get user's governance token balance
@@ -5363,19 +9874,19 @@ pub mod lending_contract {
         /// Get treasury information
         #[ink(message)]
         pub fn get_treasury(&self, treasury_id: u64) -> Result<Treasury, LendingError> {
-            self.treasuries.get(treasury_id).ok_or(LendingError::LoanNotFound)
+            self.treasuries.get(treasury_id).ok_or(LendingError::TreasuryNotFound)
         }
 
         /// Get multi-signature wallet information
         #[ink(message)]
         pub fn get_multi_signature_wallet(&self, wallet_id: u64) -> Result<MultiSignatureWallet, LendingError> {
-            self.multi_sig_wallets.get(wallet_id).ok_or(LendingError::LoanNotFound)
+            self.multi_sig_wallets.get(wallet_id).ok_or(LendingError::MultiSigWalletNotFound)
         }
 
         /// Get DAO configuration
         #[ink(message)]
         pub fn get_dao_configuration(&self, dao_id: u64) -> Result<DAOConfiguration, LendingError> {
-            self.dao_configurations.get(dao_id).ok_or(LendingError::LoanNotFound)
+            self.dao_configurations.get(dao_id).ok_or(LendingError::DaoNotFound)
         }
 
         /// Get governance statistics
@@ -5420,7 +9931,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
 
-            let batch_id = self.total_batch_operations + 1;
+            let batch_id = self.total_batch_operations.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             let caller = self.env().caller();
 
@@ -5428,9 +9939,9 @@ pub mod lending_contract {
             let mut total_gas_estimate = 0;
 
             for (index, operation_data) in operations.iter().enumerate() {
-                let item_id = (index as u64) + 1;
-                let gas_estimate = operation_data.len() as u64 * 100; // Rough estimate
-                total_gas_estimate += gas_estimate;
+                let item_id = (index as u64).safe_add(1)?;
+                let gas_estimate = (operation_data.len() as u64).safe_mul(100)?; // Rough estimate
+                total_gas_estimate = total_gas_estimate.safe_add(gas_estimate)?;
 
                 let batch_item = BatchItem {
                     item_id,
@@ -5457,7 +9968,7 @@ pub mod lending_contract {
             };
 
             self.batch_operations.insert(batch_id, &batch_operation);
-            self.total_batch_operations += 1;
+            self.total_batch_operations = self.total_batch_operations.safe_add(1)?;
             self.batch_operation_queue.push(batch_id);
 
             // Emit event
@@ -5480,7 +9991,7 @@ pub mod lending_contract {
             }
 
             let mut batch = self.batch_operations.get(batch_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::BatchNotFound)?;
 
             if batch.status != BatchStatus::Pending {
                 return Err(LendingError::InvalidStatus);
@@ -5500,13 +10011,13 @@ pub mod lending_contract {
                     Ok(_) => {
                         item.status = BatchItemStatus::Executed;
                         item.executed_at = Some(current_block);
-                        success_count += 1;
-                        total_gas_used += item.gas_estimate;
+                        success_count = success_count.safe_add(1)?;
+                        total_gas_used = total_gas_used.safe_add(item.gas_estimate)?;
                     }
                     Err(e) => {
                         item.status = BatchItemStatus::Failed;
                         item.error_message = Some(format!("{:?}", e));
-                        error_count += 1;
+                        error_count = error_count.safe_add(1)?;
                     }
                 }
             }
@@ -5551,7 +10062,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
 
-            let optimization_id = self.total_storage_optimizations + 1;
+            let optimization_id = self.total_storage_optimizations.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             let caller = self.env().caller();
 
@@ -5569,7 +10080,7 @@ pub mod lending_contract {
             };
 
             self.storage_optimizations.insert(optimization_id, &optimization);
-            self.total_storage_optimizations += 1;
+            self.total_storage_optimizations = self.total_storage_optimizations.safe_add(1)?;
             self.optimization_queue.push(optimization_id);
 
             // Emit event
@@ -5592,7 +10103,7 @@ pub mod lending_contract {
             }
 
             let mut optimization = self.storage_optimizations.get(optimization_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::OptimizationNotFound)?;
 
             if optimization.status != OptimizationStatus::Proposed {
                 return Err(LendingError::InvalidStatus);
@@ -5608,27 +10119,27 @@ pub mod lending_contract {
             match optimization.optimization_type {
                 StorageOptimizationType::DataCompression => {
                     // Simulate data compression
-                    optimization.new_storage_size = optimization.old_storage_size * 80 / 100; // 20% reduction
+                    optimization.new_storage_size = optimization.old_storage_size.safe_mul(80)? / 100; // 20% reduction
                 }
                 StorageOptimizationType::StructureOptimization => {
                     // Simulate structure optimization
-                    optimization.new_storage_size = optimization.old_storage_size * 85 / 100; // 15% reduction
+                    optimization.new_storage_size = optimization.old_storage_size.safe_mul(85)? / 100; // 15% reduction
                 }
                 StorageOptimizationType::UnusedDataRemoval => {
                     // Simulate unused data removal
-                    optimization.new_storage_size = optimization.old_storage_size * 90 / 100; // 10% reduction
+                    optimization.new_storage_size = optimization.old_storage_size.safe_mul(90)? / 100; // 10% reduction
                 }
                 StorageOptimizationType::IndexOptimization => {
                     // Simulate index optimization
-                    optimization.new_storage_size = optimization.old_storage_size * 95 / 100; // 5% reduction
+                    optimization.new_storage_size = optimization.old_storage_size.safe_mul(95)? / 100; // 5% reduction
                 }
                 StorageOptimizationType::CacheImplementation => {
                     // Simulate cache implementation
-                    optimization.new_storage_size = optimization.old_storage_size * 88 / 100; // 12% reduction
+                    optimization.new_storage_size = optimization.old_storage_size.safe_mul(88)? / 100; // 12% reduction
                 }
             }
 
-            optimization.gas_savings = optimization.old_storage_size - optimization.new_storage_size;
+            optimization.gas_savings = optimization.old_storage_size.safe_sub(optimization.new_storage_size)?;
             optimization.cost_savings = (optimization.gas_savings as u128) * 1; // 1 wei per gas unit
             optimization.status = OptimizationStatus::Applied;
             optimization.applied_at = Some(current_block);
@@ -5659,7 +10170,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
 
-            let contract_id = self.total_upgradeable_contracts + 1;
+            let contract_id = self.total_upgradeable_contracts.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             let caller = self.env().caller();
 
@@ -5676,7 +10187,7 @@ pub mod lending_contract {
             };
 
             self.upgradeable_contracts.insert(contract_id, &contract);
-            self.total_upgradeable_contracts += 1;
+            self.total_upgradeable_contracts = self.total_upgradeable_contracts.safe_add(1)?;
 
             Ok(contract_id)
         }
@@ -5695,10 +10206,10 @@ pub mod lending_contract {
             }
 
             let mut contract = self.upgradeable_contracts.get(contract_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::UpgradeableContractNotFound)?;
 
             if !contract.is_upgradeable {
-                return Err(LendingError::InvalidStatus);
+                return Err(LendingError::ContractNotUpgradeable);
             }
 
             let _current_block = self.env().block_number() as u64;
@@ -5706,7 +10217,7 @@ pub mod lending_contract {
 
             // Create upgrade record
             let upgrade = ContractUpgrade {
-                upgrade_id: contract.upgrade_history.len() as u64 + 1,
+                upgrade_id: (contract.upgrade_history.len() as u64).safe_add(1)?,
                 from_version: contract.current_version.clone(),
                 to_version: new_version.clone(),
                 implementation_address: new_implementation,
@@ -5747,7 +10258,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
 
-            let optimization_id = self.total_gas_optimizations + 1;
+            let optimization_id = self.total_gas_optimizations.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             let caller = self.env().caller();
 
@@ -5771,7 +10282,7 @@ pub mod lending_contract {
             };
 
             self.gas_optimizations.insert(optimization_id, &optimization);
-            self.total_gas_optimizations += 1;
+            self.total_gas_optimizations = self.total_gas_optimizations.safe_add(1)?;
 
             // Update gas usage tracker
             self.gas_usage_tracker.insert(&function_name, &new_gas_usage);
@@ -5799,7 +10310,7 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
 
-            let process_id = self.total_parallel_processes + 1;
+            let process_id = self.total_parallel_processes.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             let caller = self.env().caller();
 
@@ -5807,9 +10318,9 @@ pub mod lending_contract {
             let mut total_gas_estimate = 0;
 
             for (index, operation_data) in operations.iter().enumerate() {
-                let operation_id = (index as u64) + 1;
-                let gas_estimate = operation_data.len() as u64 * 100; // Rough estimate
-                total_gas_estimate += gas_estimate;
+                let operation_id = (index as u64).safe_add(1)?;
+                let gas_estimate = (operation_data.len() as u64).safe_mul(100)?; // Rough estimate
+                total_gas_estimate = total_gas_estimate.safe_add(gas_estimate)?;
 
                 let parallel_operation = ParallelOperation {
                     operation_id,
@@ -5840,7 +10351,7 @@ pub mod lending_contract {
             };
 
             self.parallel_processes.insert(process_id, &parallel_process);
-            self.total_parallel_processes += 1;
+            self.total_parallel_processes = self.total_parallel_processes.safe_add(1)?;
 
             // Emit event
             self.env().emit_event(ParallelProcessStarted {
@@ -5862,7 +10373,7 @@ pub mod lending_contract {
             }
 
             let mut process = self.parallel_processes.get(process_id)
-                .ok_or(LendingError::LoanNotFound)?;
+                .ok_or(LendingError::ParallelProcessNotFound)?;
 
             if process.status != ParallelProcessStatus::Running {
                 return Err(LendingError::InvalidStatus);
@@ -5882,14 +10393,14 @@ pub mod lending_contract {
                         operation.status = ParallelOperationStatus::Completed;
                         operation.output_data = Some(output_data);
                         operation.completed_at = Some(current_block);
-                        operation.gas_used = operation.input_data.len() as u64 * 100;
-                        completed_count += 1;
-                        total_gas_used += operation.gas_used;
+                        operation.gas_used = (operation.input_data.len() as u64).safe_mul(100)?;
+                        completed_count = completed_count.safe_add(1)?;
+                        total_gas_used = total_gas_used.safe_add(operation.gas_used)?;
                     }
                     Err(_) => {
                         operation.status = ParallelOperationStatus::Failed;
                         operation.error_message = Some("Operation failed".to_string());
-                        failed_count += 1;
+                        failed_count = failed_count.safe_add(1)?;
                     }
                 }
             }
@@ -5898,7 +10409,7 @@ pub mod lending_contract {
             process.completed_operations = completed_count;
             process.failed_operations = failed_count;
             process.gas_used = total_gas_used;
-            process.execution_time = current_block - process.created_at;
+            process.execution_time = current_block.safe_sub(process.created_at)?;
             process.completed_at = Some(current_block);
 
             self.parallel_processes.insert(process_id, &process);
@@ -5922,15 +10433,17 @@ pub mod lending_contract {
                 return Err(LendingError::Unauthorized);
             }
 
-            let metrics_id = self.total_performance_metrics + 1;
+            let metrics_id = self.total_performance_metrics.safe_add(1)?;
             let current_block = self.env().block_number() as u64;
             let contract_address = self.env().account_id();
 
             // Calculate performance metrics
             let total_gas_used = self.calculate_total_gas_usage();
-            let total_transactions = self.total_loans + self.total_batch_operations + self.total_parallel_processes;
+            let total_transactions = self.total_loans
+                .safe_add(self.total_batch_operations)?
+                .safe_add(self.total_parallel_processes)?;
             let successful_transactions = self.total_loans; // Simplified
-            let failed_transactions = total_transactions - successful_transactions;
+            let failed_transactions = total_transactions.safe_sub(successful_transactions)?;
             let storage_size = self.calculate_storage_size();
             
             let optimization_score = self.calculate_optimization_score();
@@ -5955,7 +10468,7 @@ pub mod lending_contract {
             };
 
             self.performance_metrics.insert(metrics_id, &metrics);
-            self.total_performance_metrics += 1;
+            self.total_performance_metrics = self.total_performance_metrics.safe_add(1)?;
 
             // Emit event
             self.env().emit_event(PerformanceMetricsUpdated {
@@ -5980,49 +10493,49 @@ pub mod lending_contract {
                 BatchOperationType::LoanCreation => {
                     // Simulate loan creation
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                 }
                 BatchOperationType::LoanRepayment => {
                     // Simulate loan repayment
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                 }
                 BatchOperationType::UserRegistration => {
                     // Simulate user registration
                     if operation_data.len() < 32 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                 }
                 BatchOperationType::CollateralManagement => {
                     // Simulate collateral management
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                 }
                 BatchOperationType::LiquidityProvision => {
                     // Simulate liquidity provision
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                 }
                 BatchOperationType::GovernanceVoting => {
                     // Simulate governance voting
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                 }
                 BatchOperationType::TreasuryOperations => {
                     // Simulate treasury operations
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                 }
                 BatchOperationType::MultiSigTransactions => {
                     // Simulate multi-sig transactions
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                 }
             }
@@ -6036,35 +10549,35 @@ pub mod lending_contract {
                 ParallelProcessType::BatchLoanProcessing => {
                     // Simulate batch loan processing
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                     Ok(vec![1, 2, 3, 4]) // Simulated output
                 }
                 ParallelProcessType::ConcurrentUserOperations => {
                     // Simulate concurrent user operations
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                     Ok(vec![5, 6, 7, 8]) // Simulated output
                 }
                 ParallelProcessType::ParallelAnalytics => {
                     // Simulate parallel analytics
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                     Ok(vec![9, 10, 11, 12]) // Simulated output
                 }
                 ParallelProcessType::MultiPoolOperations => {
                     // Simulate multi-pool operations
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                     Ok(vec![13, 14, 15, 16]) // Simulated output
                 }
                 ParallelProcessType::GovernanceBatchProcessing => {
                     // Simulate governance batch processing
                     if operation_data.len() < 4 {
-                        return Err(LendingError::InvalidAmount);
+                        return Err(LendingError::InvalidOperationData);
                     }
                     Ok(vec![17, 18, 19, 20]) // Simulated output
                 }
@@ -6075,38 +10588,40 @@ pub mod lending_contract {
         fn calculate_total_gas_usage(&self) -> u64 {
             // Simplified calculation since Mapping doesn't have iter()
             // In a real implementation, you would track this separately
-            self.total_gas_optimizations * 1000 // Rough estimate
+            self.total_gas_optimizations.saturating_mul(1000) // Rough estimate
         }
 
         /// Calculate storage size
         fn calculate_storage_size(&self) -> u64 {
             // Simplified storage size calculation
             let base_size = 1000; // Base contract size
-            let loan_size = self.total_loans * 100; // Approximate size per loan
-            let user_size = self.total_users * 50; // Approximate size per user
-            let pool_size = self.total_pools * 80; // Approximate size per pool
-            let governance_size = self.total_proposals * 60; // Approximate size per proposal
-            let batch_size = self.total_batch_operations * 40; // Approximate size per batch
-            let optimization_size = self.total_storage_optimizations * 30; // Approximate size per optimization
+            let loan_size = self.total_loans.saturating_mul(100); // Approximate size per loan
+            let user_size = self.total_users.saturating_mul(50); // Approximate size per user
+            let pool_size = self.total_pools.saturating_mul(80); // Approximate size per pool
+            let governance_size = self.total_proposals.saturating_mul(60); // Approximate size per proposal
+            let batch_size = self.total_batch_operations.saturating_mul(40); // Approximate size per batch
+            let optimization_size = self.total_storage_optimizations.saturating_mul(30); // Approximate size per optimization
             
-            base_size + loan_size + user_size + pool_size + governance_size + batch_size + optimization_size
+            [loan_size, user_size, pool_size, governance_size, batch_size, optimization_size]
+                .iter()
+                .fold(base_size, |total, size| total.saturating_add(*size))
         }
 
         /// Calculate optimization score
         fn calculate_optimization_score(&self) -> u16 {
-            let mut score = 500; // Base score of 50%
+            let mut score: u64 = 500; // Base score of 50%
             
             // Add points for optimizations
-            score += self.total_storage_optimizations as u16 * 50; // 5% per optimization
-            score += self.total_gas_optimizations as u16 * 30; // 3% per gas optimization
-            score += self.total_batch_operations as u16 * 20; // 2% per batch operation
+            score = score.saturating_add(self.total_storage_optimizations.saturating_mul(50)); // 5% per optimization
+            score = score.saturating_add(self.total_gas_optimizations.saturating_mul(30)); // 3% per gas optimization
+            score = score.saturating_add(self.total_batch_operations.saturating_mul(20)); // 2% per batch operation
             
             // Cap at 1000 (100%)
             if score > 1000 {
                 score = 1000;
             }
             
-            score
+            score as u16
         }
 
         /// Calculate performance rating
@@ -6127,37 +10642,37 @@ pub mod lending_contract {
         /// Get batch operation information
         #[ink(message)]
         pub fn get_batch_operation(&self, batch_id: u64) -> Result<BatchOperation, LendingError> {
-            self.batch_operations.get(batch_id).ok_or(LendingError::LoanNotFound)
+            self.batch_operations.get(batch_id).ok_or(LendingError::BatchNotFound)
         }
 
         /// Get storage optimization information
         #[ink(message)]
         pub fn get_storage_optimization(&self, optimization_id: u64) -> Result<StorageOptimization, LendingError> {
-            self.storage_optimizations.get(optimization_id).ok_or(LendingError::LoanNotFound)
+            self.storage_optimizations.get(optimization_id).ok_or(LendingError::OptimizationNotFound)
         }
 
         /// Get upgradeable contract information
         #[ink(message)]
         pub fn get_upgradeable_contract(&self, contract_id: u64) -> Result<UpgradeableContract, LendingError> {
-            self.upgradeable_contracts.get(contract_id).ok_or(LendingError::LoanNotFound)
+            self.upgradeable_contracts.get(contract_id).ok_or(LendingError::UpgradeableContractNotFound)
         }
 
         /// Get gas optimization information
         #[ink(message)]
         pub fn get_gas_optimization(&self, optimization_id: u64) -> Result<GasOptimization, LendingError> {
-            self.gas_optimizations.get(optimization_id).ok_or(LendingError::LoanNotFound)
+            self.gas_optimizations.get(optimization_id).ok_or(LendingError::OptimizationNotFound)
         }
 
         /// Get parallel process information
         #[ink(message)]
         pub fn get_parallel_process(&self, process_id: u64) -> Result<ParallelProcessing, LendingError> {
-            self.parallel_processes.get(process_id).ok_or(LendingError::LoanNotFound)
+            self.parallel_processes.get(process_id).ok_or(LendingError::ParallelProcessNotFound)
         }
 
         /// Get performance metrics
         #[ink(message)]
         pub fn get_performance_metrics(&self, metrics_id: u64) -> Result<PerformanceMetrics, LendingError> {
-            self.performance_metrics.get(metrics_id).ok_or(LendingError::LoanNotFound)
+            self.performance_metrics.get(metrics_id).ok_or(LendingError::PerformanceMetricsNotFound)
         }
 
         /// Get performance statistics
@@ -3,6 +3,8 @@
 pub mod lending_contract;
 pub mod types;
 pub mod errors;
+pub mod math;
+pub mod oracle;
 
 pub use lending_contract::lending_contract::LendingContract;
 pub use types::*;
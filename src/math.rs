@@ -0,0 +1,225 @@
+use crate::errors::LendingError;
+
+// ============================================================================
+// FIXED-POINT MATH
+// ============================================================================
+//
+// Deterministic integer arithmetic for interest, fee, reward and share
+// calculations. Floating point is avoided because it is not guaranteed to
+// behave identically across Wasm engines. Every operation that can lose
+// precision takes an explicit rounding direction, and callers round in the
+// protocol's favor: up for amounts owed to the protocol (interest, fees,
+// required collateral) and down for amounts paid out (rewards, discounts,
+// bonuses, shares).
+
+pub const WAD: u128 = 1_000_000_000_000_000_000; // 18 decimals
+pub const RAY: u128 = 1_000_000_000_000_000_000_000_000_000; // 27 decimals
+pub const BPS: u128 = 10_000; // Basis points in one unit
+
+/// Direction to round an inexact result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Down, // Towards zero, for amounts paid out by the protocol
+    Up,   // Away from zero, for amounts owed to the protocol
+}
+
+/// Compute `a * b / denominator` with a 256-bit intermediate product
+pub fn mul_div(a: u128, b: u128, denominator: u128, rounding: Rounding) -> Result<u128, LendingError> {
+    if denominator == 0 {
+        return Err(LendingError::DivisionByZero);
+    }
+
+    let (high, low) = full_mul(a, b);
+    if high >= denominator {
+        return Err(LendingError::ArithmeticOverflow);
+    }
+
+    let (quotient, remainder) = div_wide(high, low, denominator);
+    if rounding == Rounding::Up && remainder != 0 {
+        quotient.checked_add(1).ok_or(LendingError::ArithmeticOverflow)
+    } else {
+        Ok(quotient)
+    }
+}
+
+/// Apply a basis-point rate to an amount
+pub fn bps_mul(amount: u128, bps: u128, rounding: Rounding) -> Result<u128, LendingError> {
+    mul_div(amount, bps, BPS, rounding)
+}
+
+/// Express `numerator / denominator` in basis points
+pub fn bps_ratio(numerator: u128, denominator: u128, rounding: Rounding) -> Result<u128, LendingError> {
+    mul_div(numerator, BPS, denominator, rounding)
+}
+
+/// Full 256-bit product of two u128 values as (high, low) halves
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    let mask = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & mask);
+    let (b_high, b_low) = (b >> 64, b & mask);
+
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let high_high = a_high * b_high;
+
+    let middle = (low_low >> 64) + (high_low & mask) + (low_high & mask);
+    let low = (low_low & mask) | (middle << 64);
+    let high = high_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// Divide a 256-bit value by a u128, given `high < denominator` so the quotient fits
+fn div_wide(high: u128, low: u128, denominator: u128) -> (u128, u128) {
+    if high == 0 {
+        return (low / denominator, low % denominator);
+    }
+
+    // Binary long division over the low half
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    (quotient, remainder)
+}
+
+// ============================================================================
+// CHECKED INTEGER ARITHMETIC
+// ============================================================================
+
+/// Checked arithmetic that reports overflow and division by zero as errors
+pub trait SafeMath: Sized {
+    fn safe_add(self, rhs: Self) -> Result<Self, LendingError>;
+    fn safe_sub(self, rhs: Self) -> Result<Self, LendingError>;
+    fn safe_mul(self, rhs: Self) -> Result<Self, LendingError>;
+    fn safe_div(self, rhs: Self) -> Result<Self, LendingError>;
+    fn safe_rem(self, rhs: Self) -> Result<Self, LendingError>;
+}
+
+macro_rules! impl_safe_math {
+    ($($int:ty),*) => {
+        $(
+            impl SafeMath for $int {
+                fn safe_add(self, rhs: Self) -> Result<Self, LendingError> {
+                    self.checked_add(rhs).ok_or(LendingError::ArithmeticOverflow)
+                }
+
+                fn safe_sub(self, rhs: Self) -> Result<Self, LendingError> {
+                    self.checked_sub(rhs).ok_or(LendingError::ArithmeticOverflow)
+                }
+
+                fn safe_mul(self, rhs: Self) -> Result<Self, LendingError> {
+                    self.checked_mul(rhs).ok_or(LendingError::ArithmeticOverflow)
+                }
+
+                fn safe_div(self, rhs: Self) -> Result<Self, LendingError> {
+                    if rhs == 0 {
+                        return Err(LendingError::DivisionByZero);
+                    }
+                    Ok(self / rhs)
+                }
+
+                fn safe_rem(self, rhs: Self) -> Result<Self, LendingError> {
+                    if rhs == 0 {
+                        return Err(LendingError::DivisionByZero);
+                    }
+                    Ok(self % rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_safe_math!(u8, u16, u32, u64, u128);
+
+// ============================================================================
+// WAD AND RAY
+// ============================================================================
+
+macro_rules! fixed_point {
+    ($name:ident, $scale:expr) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+        pub struct $name(pub u128);
+
+        impl $name {
+            pub const ZERO: $name = $name(0);
+            pub const ONE: $name = $name($scale);
+
+            /// Fixed-point value of a basis-point rate
+            pub fn from_bps(bps: u128) -> Result<Self, LendingError> {
+                bps.checked_mul($scale / BPS).map($name).ok_or(LendingError::ArithmeticOverflow)
+            }
+
+            /// Fixed-point value of `numerator / denominator`
+            pub fn from_ratio(numerator: u128, denominator: u128, rounding: Rounding) -> Result<Self, LendingError> {
+                mul_div(numerator, $scale, denominator, rounding).map($name)
+            }
+
+            pub fn checked_add(self, other: Self) -> Result<Self, LendingError> {
+                self.0.checked_add(other.0).map($name).ok_or(LendingError::ArithmeticOverflow)
+            }
+
+            pub fn checked_sub(self, other: Self) -> Result<Self, LendingError> {
+                self.0.checked_sub(other.0).map($name).ok_or(LendingError::ArithmeticOverflow)
+            }
+
+            pub fn checked_mul(self, other: Self, rounding: Rounding) -> Result<Self, LendingError> {
+                mul_div(self.0, other.0, $scale, rounding).map($name)
+            }
+
+            pub fn checked_div(self, other: Self, rounding: Rounding) -> Result<Self, LendingError> {
+                mul_div(self.0, $scale, other.0, rounding).map($name)
+            }
+
+            /// Multiply by a plain integer, such as a number of elapsed milliseconds
+            pub fn checked_mul_int(self, value: u128) -> Result<Self, LendingError> {
+                self.0.checked_mul(value).map($name).ok_or(LendingError::ArithmeticOverflow)
+            }
+
+            /// Raise to an integer power by repeated squaring, rounding every step the same way
+            pub fn checked_pow(self, mut exponent: u64, rounding: Rounding) -> Result<Self, LendingError> {
+                let mut base = self;
+                let mut result = Self::ONE;
+                while exponent > 0 {
+                    if exponent & 1 == 1 {
+                        result = result.checked_mul(base, rounding)?;
+                    }
+                    exponent >>= 1;
+                    if exponent > 0 {
+                        base = base.checked_mul(base, rounding)?;
+                    }
+                }
+                Ok(result)
+            }
+
+            /// Apply this factor to a token amount
+            pub fn mul_amount(self, amount: u128, rounding: Rounding) -> Result<u128, LendingError> {
+                mul_div(amount, self.0, $scale, rounding)
+            }
+        }
+    };
+}
+
+fixed_point!(Wad, WAD);
+fixed_point!(Ray, RAY);
+
+impl Ray {
+    /// Reduce to 18 decimals
+    pub fn to_wad(self, rounding: Rounding) -> Result<Wad, LendingError> {
+        mul_div(self.0, WAD, RAY, rounding).map(Wad)
+    }
+}
+
+impl Wad {
+    /// Extend to 27 decimals
+    pub fn to_ray(self) -> Result<Ray, LendingError> {
+        self.0.checked_mul(RAY / WAD).map(Ray).ok_or(LendingError::ArithmeticOverflow)
+    }
+}
@@ -0,0 +1,89 @@
+use ink_prelude::vec::Vec;
+
+use crate::errors::LendingError;
+use crate::math::{SafeMath, mul_div, bps_ratio, Rounding};
+use crate::types::{Balance, PriceObservation};
+
+// ============================================================================
+// PRICE ORACLE
+// ============================================================================
+//
+// Aggregation for the contract's price feeds. Authorized reporters push a
+// price per asset, and the feed's price is the median of the reports that are
+// still fresh, so one faulty reporter cannot move it. Each new median is kept
+// as an observation, and the time-weighted average over those observations
+// smooths out short spikes. Valuations take the side of the two prices that
+// favors the protocol.
+
+/// Which of the spot and time-weighted prices to value an amount at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceBound {
+    Lower, // For collateral, so a short spike cannot inflate it
+    Upper, // For debt, so a short dip cannot shrink it
+}
+
+/// Median of a set of prices, averaging the middle pair of an even set
+pub fn median(mut prices: Vec<Balance>) -> Option<Balance> {
+    if prices.is_empty() {
+        return None;
+    }
+
+    prices.sort_unstable();
+    let middle = prices.len() / 2;
+    if prices.len() % 2 == 1 {
+        return Some(prices[middle]);
+    }
+
+    // Halve before adding so the sum cannot overflow
+    let (low, high) = (prices[middle - 1], prices[middle]);
+    Some(low / 2 + high / 2 + (low % 2 + high % 2) / 2)
+}
+
+/// Distance of `price` from `reference` in basis points of the reference
+pub fn deviation_bps(price: Balance, reference: Balance) -> Result<u128, LendingError> {
+    bps_ratio(price.abs_diff(reference), reference, Rounding::Up)
+}
+
+/// Time-weighted average price over the window ending at `now`
+///
+/// Each observation holds until the next one, and the last holds until `now`.
+/// Time before the first observation is not counted. Returns `None` without
+/// observations and the latest price when the covered time is zero.
+pub fn twap(observations: &[PriceObservation], now: u64, window_ms: u64) -> Result<Option<Balance>, LendingError> {
+    let latest = match observations.last() {
+        Some(observation) => observation.price,
+        None => return Ok(None),
+    };
+
+    let window_start = now.saturating_sub(window_ms);
+    let mut segments = Vec::with_capacity(observations.len());
+    let mut covered = 0u64;
+    for (index, observation) in observations.iter().enumerate() {
+        let end = observations.get(index + 1).map_or(now, |next| next.timestamp).min(now);
+        let start = observation.timestamp.max(window_start);
+        if end > start {
+            let duration = end.safe_sub(start)?;
+            covered = covered.safe_add(duration)?;
+            segments.push((observation.price, duration));
+        }
+    }
+
+    if covered == 0 {
+        return Ok(Some(latest));
+    }
+
+    // Sum each segment's share of the window so large prices cannot overflow the product
+    let mut average = 0u128;
+    for (price, duration) in segments {
+        average = average.safe_add(mul_div(price, duration as u128, covered as u128, Rounding::Down)?)?;
+    }
+    Ok(Some(average))
+}
+
+/// Pick the spot or time-weighted price for a valuation
+pub fn bounded_price(spot: Balance, twap: Balance, bound: PriceBound) -> Balance {
+    match bound {
+        PriceBound::Lower => spot.min(twap),
+        PriceBound::Upper => spot.max(twap),
+    }
+}
@@ -9,15 +9,20 @@ pub struct Loan {
     pub id: u64,
     pub borrower: AccountId,
     pub lender: Option<AccountId>,
+    pub asset: Asset, // Asset the principal and payments are denominated in
+    pub collateral_asset: Asset, // Asset the escrowed collateral is denominated in
     pub amount: Balance,
     pub interest_rate: u16, // Basis points (e.g., 500 = 5%)
-    pub duration: u64, // Duration in blocks
+    pub duration: u64, // Duration in milliseconds
     pub collateral: Balance,
     pub status: LoanStatus,
     pub created_at: u64,
     pub due_date: u64,
     pub early_repayment_discount: u16, // Early repayment discount in basis points (default: 200 = 2%)
     pub total_paid: Balance, // Total amount paid so far (principal + interest)
+    pub principal_repaid: Balance, // Total principal repaid so far
+    pub interest_repaid: Balance, // Total interest repaid so far
+    pub late_fees_repaid: Balance, // Total late fees repaid so far
     pub remaining_balance: Balance, // Remaining balance to be paid
     pub partial_payments: Vec<PartialPayment>, // History of partial payments
     pub extension_count: u32, // Number of times loan has been extended
@@ -26,8 +31,9 @@ pub struct Loan {
     pub late_fee_rate: u16, // Daily late fee rate in basis points (default: 50 = 0.5%)
     pub max_late_fee_rate: u16, // Maximum late fee rate in basis points (default: 1000 = 10%)
     pub total_late_fees: Balance, // Total late fees accumulated
-    pub overdue_since: Option<u64>, // Block number when loan became overdue
-    pub grace_period: u64, // Grace period in blocks before late fees start (default: 100 = ~10 minutes)
+    pub late_fees_accrued_until: u64, // Timestamp (ms) late fees have been charged up to
+    pub overdue_since: Option<u64>, // Timestamp (ms) when loan became overdue
+    pub grace_period: u64, // Grace period in ms before late fees start (default: 10 minutes)
     pub refinance_count: u32, // Number of times loan has been refinanced
     pub max_refinances: u32, // Maximum allowed refinances (default: 2)
     pub refinance_fee_rate: u16, // Refinance fee in basis points (default: 200 = 2%)
@@ -37,22 +43,24 @@ pub struct Loan {
     pub base_interest_rate: u16, // Base interest rate for variable loans
     pub risk_multiplier: u16, // Risk-based multiplier (1000 = 1.0x, 1200 = 1.2x)
     pub interest_rate_adjustments: Vec<InterestRateAdjustment>, // History of rate changes
-    pub last_interest_update: u64, // Block number of last interest rate update
-    pub interest_update_frequency: u64, // How often interest rates can be updated (blocks)
+    pub last_interest_update: u64, // Timestamp (ms) of last interest rate update
+    pub interest_update_frequency: u64, // How often interest rates can be updated (ms)
     pub interest_type: InterestType, // Simple or compound interest
     pub compound_frequency: CompoundFrequency, // How often interest compounds
-    pub last_compound_date: u64, // Block number of last compound calculation
-    pub compound_period_blocks: u64, // Blocks per compound period
+    pub last_compound_date: u64, // Timestamp (ms) of last compound calculation
+    pub compound_period_ms: u64, // Milliseconds per compound period
     pub accrued_interest: Balance, // Interest accrued since last compound
     pub total_compounded_interest: Balance, // Total interest from compounding
+    pub borrow_index: u128, // Cumulative interest growth per unit of principal, scaled by BORROW_INDEX_SCALE
+    pub last_accrual: u64, // Timestamp (ms) interest was last accrued into the balance
     pub payment_structure: PaymentStructure, // Type of payment structure
     pub interest_only_periods: u32, // Total interest-only periods allowed
     pub current_payment_period: u32, // Current payment period number
     pub interest_only_periods_used: u32, // Interest-only periods already used
-    pub next_payment_due: u64, // Block number when next payment is due
-    pub payment_period_blocks: u64, // Blocks per payment period
+    pub next_payment_due: u64, // Timestamp (ms) when next payment is due
+    pub payment_period_ms: u64, // Milliseconds per payment period
     pub minimum_payment_amount: Balance, // Minimum payment required per period
-    pub grace_period_blocks: u64, // Grace period in blocks (configurable)
+    pub grace_period_ms: u64, // Grace period in ms (configurable)
     pub grace_period_used: u64, // How much of grace period has been used
     pub grace_period_extensions: u32, // Number of grace period extensions used
     pub max_grace_period_extensions: u32, // Maximum grace period extensions allowed
@@ -64,9 +72,26 @@ pub struct Loan {
     pub pool_rewards_earned: Balance, // Rewards earned from pool participation
     pub credit_score: Option<CreditScore>, // Borrower's credit score
     pub collateral_requirements: Vec<CollateralRequirement>, // Collateral requirements
+    pub margin_call_at: Option<u64>, // Timestamp (ms) of the outstanding margin call, cleared once health recovers
     pub insurance_policies: Vec<InsurancePolicy>, // Insurance coverage
     pub fraud_flags: Vec<FraudDetectionRule>, // Fraud detection flags
     pub compliance_status: ComplianceStatus, // Compliance verification status
+    pub funding_deadline: Option<u64>, // Timestamp (ms) after which an unfunded request can be expired
+    pub funded_amount: Balance, // Amount contributed by lenders so far
+    pub lender_contributions: Vec<LenderContribution>, // Lenders participating in the loan
+    pub amortization_schedule: Vec<Installment>, // Installments for principal and interest loans
+}
+
+/// Terms and collateral of a new loan request, validated and stored by the contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoanRequest {
+    pub asset: Asset, // Asset the principal is denominated in
+    pub collateral_asset: Asset, // Asset of single-asset collateral
+    pub amount: Balance,
+    pub interest_rate: u16, // Basis points
+    pub duration: u64, // Duration in ms
+    pub collateral: Balance, // Single-asset collateral paid with the request
+    pub basket: Vec<CollateralPosition>, // Collateral basket used instead of single-asset collateral
 }
 
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
@@ -81,6 +106,110 @@ pub enum LoanStatus {
     Refinanced, // New status for refinanced loans
     Defaulted,
     Liquidated,
+    Cancelled, // Pending request withdrawn by the borrower
+    Expired, // Pending request not funded before its funding deadline
+}
+
+impl LoanStatus {
+    /// Whether the loan is funded and still owes a balance
+    pub fn is_open(&self) -> bool {
+        matches!(self, LoanStatus::Active | LoanStatus::PartiallyPaid | LoanStatus::Overdue)
+    }
+
+    /// Whether the loan has reached a final status
+    pub fn is_closed(&self) -> bool {
+        !self.is_open() && *self != LoanStatus::Pending
+    }
+
+    /// Transition table: the statuses a loan may move to from this one
+    pub fn can_transition_to(&self, next: &LoanStatus) -> bool {
+        match self {
+            LoanStatus::Pending => matches!(
+                next,
+                LoanStatus::Active | LoanStatus::Cancelled | LoanStatus::Expired
+            ),
+            LoanStatus::Active | LoanStatus::PartiallyPaid => matches!(
+                next,
+                LoanStatus::PartiallyPaid | LoanStatus::Overdue | LoanStatus::Repaid | LoanStatus::EarlyRepaid
+                    | LoanStatus::Refinanced | LoanStatus::Defaulted | LoanStatus::Liquidated
+            ),
+            LoanStatus::Overdue => matches!(
+                next,
+                LoanStatus::Repaid | LoanStatus::Refinanced | LoanStatus::Defaulted | LoanStatus::Liquidated
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether an action is permitted while the loan has this status
+    pub fn allows(&self, action: &LoanAction) -> bool {
+        match action {
+            LoanAction::Fund | LoanAction::Cancel | LoanAction::Expire | LoanAction::SetFundingDeadline => {
+                *self == LoanStatus::Pending
+            }
+            LoanAction::AddGuarantee => *self == LoanStatus::Pending || self.is_open(),
+            // Collateral can be topped up until the loan closes, but only withdrawn while it is current
+            LoanAction::AddCollateral => self.is_open(),
+            LoanAction::WithdrawCollateral => matches!(self, LoanStatus::Active | LoanStatus::PartiallyPaid),
+            // Term changes and late fee assessment stop once the loan is overdue
+            LoanAction::EarlyRepay | LoanAction::Extend | LoanAction::Refinance | LoanAction::ApplyLateFees | LoanAction::ModifyTerms => {
+                matches!(self, LoanStatus::Active | LoanStatus::PartiallyPaid)
+            }
+            LoanAction::Repay | LoanAction::AccrueInterest | LoanAction::ThirdPartyRefinance | LoanAction::Liquidate
+                | LoanAction::DeclareDefault | LoanAction::TransferPosition => self.is_open(),
+            // A defaulted loan keeps its guarantee until the guarantor has been called
+            LoanAction::ReleaseGuarantee => self.is_closed() && *self != LoanStatus::Defaulted,
+        }
+    }
+}
+
+/// Operations on a loan that depend on its status
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub enum LoanAction {
+    Fund, // Fund or contribute to a pending request
+    Cancel, // Withdraw a pending request
+    Expire, // Expire a request not funded before its deadline
+    SetFundingDeadline,
+    Repay, // Full, partial or on-behalf repayment
+    EarlyRepay, // Repay before the due date at a discount
+    AccrueInterest,
+    Extend,
+    ApplyLateFees,
+    Refinance, // Renegotiate terms with the existing lenders
+    ThirdPartyRefinance, // Propose or accept a payoff by a new lender
+    ModifyTerms, // Rate type, payment structure and grace period changes
+    Liquidate,
+    DeclareDefault,
+    AddGuarantee,
+    ReleaseGuarantee,
+    TransferPosition, // Transfer, list or sell a lender position
+    AddCollateral,
+    WithdrawCollateral, // Withdraw collateral above the maintenance margin
+}
+
+impl LoanAction {
+    pub const ALL: [LoanAction; 19] = [
+        LoanAction::Fund,
+        LoanAction::Cancel,
+        LoanAction::Expire,
+        LoanAction::SetFundingDeadline,
+        LoanAction::Repay,
+        LoanAction::EarlyRepay,
+        LoanAction::AccrueInterest,
+        LoanAction::Extend,
+        LoanAction::ApplyLateFees,
+        LoanAction::Refinance,
+        LoanAction::ThirdPartyRefinance,
+        LoanAction::ModifyTerms,
+        LoanAction::Liquidate,
+        LoanAction::DeclareDefault,
+        LoanAction::AddGuarantee,
+        LoanAction::ReleaseGuarantee,
+        LoanAction::TransferPosition,
+        LoanAction::AddCollateral,
+        LoanAction::WithdrawCollateral,
+    ];
 }
 
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
@@ -91,20 +220,181 @@ pub struct UserProfile {
     pub active_loans: Vec<u64>,
     pub credit_score: u16,
     pub is_blacklisted: bool,
+    pub default_count: u32, // Number of loans defaulted as borrower
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct LenderContribution {
+    pub lender: AccountId,
+    pub amount: Balance, // Principal contributed by this lender
+    pub share: u16, // Share of the loan in basis points
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct Guarantee {
+    pub loan_id: u64,
+    pub guarantor: AccountId,
+    pub stake: Balance, // Funds locked against the loan
+    pub amount_called: Balance, // Stake used to cover the borrower's default
+    pub recovery_claim: Balance, // Amount the borrower still owes the guarantor
+    pub status: GuaranteeStatus,
+    pub created_at: u64, // Timestamp (ms) when the guarantee was posted
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub enum GuaranteeStatus {
+    Active,
+    Called, // Stake was used on default
+    Released, // Stake returned after the loan closed
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct RefinanceProposal {
+    pub id: u64,
+    pub loan_id: u64, // Loan to be refinanced
+    pub new_lender: AccountId, // Lender offering to pay off the current lenders
+    pub new_interest_rate: u16, // Offered interest rate in basis points
+    pub new_duration: u64, // Offered duration in ms
+    pub max_payoff: Balance, // Escrowed funds capping the payoff; the unused part is refunded on acceptance
+    pub created_at: u64, // Timestamp (ms) when the proposal was made
+    pub status: RefinanceProposalStatus,
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub enum RefinanceProposalStatus {
+    Open,
+    Accepted,
+    Withdrawn,
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct LoanListing {
+    pub loan_id: u64,
+    pub seller: AccountId, // Lender selling their position
+    pub price: Balance, // Asking price for the position's future repayments
+    pub listed_at: u64, // Timestamp (ms) when the position was listed
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct LoanOffer {
+    pub id: u64,
+    pub lender: AccountId,
+    pub max_amount: Balance, // Total liquidity committed to the offer
+    pub available_amount: Balance, // Liquidity still available to borrowers
+    pub min_duration: u64, // Minimum loan duration in ms
+    pub max_duration: u64, // Maximum loan duration in ms
+    pub min_interest_rate: u16, // Rate charged on accepted loans in basis points
+    pub min_credit_score: u16, // Minimum borrower credit score (0 = no requirement)
+    pub accepted_collateral_types: Vec<CollateralType>, // Collateral types the lender accepts
+    pub status: OfferStatus,
+    pub created_at: u64, // Timestamp (ms) when offer was created
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub enum OfferStatus {
+    Active,
+    Filled, // All liquidity has been borrowed
+    Cancelled,
+}
+
+/// Revolving credit: the borrower draws and repays against an approved limit until the line expires
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct CreditLine {
+    pub id: u64,
+    pub borrower: AccountId,
+    pub lender: Option<AccountId>, // Lender escrowing the limit, None for pool-funded lines
+    pub liquidity_pool_id: Option<u64>, // Pool the line draws from, None for lender-funded lines
+    pub asset: Asset, // Asset the line is denominated in
+    pub limit: Balance, // Maximum principal drawn at any time
+    pub drawn_principal: Balance, // Principal currently drawn
+    pub balance: Balance, // Drawn principal plus unpaid interest and fees
+    pub interest_rate: u16, // Annual rate on the drawn balance in basis points
+    pub interest_type: InterestType,
+    pub compound_frequency: CompoundFrequency,
+    pub compound_period_ms: u64, // Compounding period in ms
+    pub borrow_index: u128, // Cumulative interest growth since opening, 18 decimals
+    pub commitment_fee_rate: u16, // Annual fee on the undrawn limit in basis points
+    pub last_accrual: u64, // Timestamp (ms) interest and fees were last booked
+    pub total_drawn: Balance, // Principal drawn over the line's life
+    pub total_interest: Balance, // Interest booked over the line's life
+    pub total_commitment_fees: Balance, // Commitment fees booked over the line's life
+    pub late_fee_rate: u16, // Daily late fee in basis points
+    pub max_late_fee_rate: u16, // Maximum late fee in basis points
+    pub total_late_fees: Balance,
+    pub late_fees_repaid: Balance,
+    pub grace_period_ms: u64, // Time after expiry before late fees apply
+    pub grace_period_extensions: u32,
+    pub max_grace_period_extensions: u32,
+    pub grace_period_history: Vec<GracePeriodRecord>,
+    pub opened_at: u64, // Timestamp (ms) when the line was opened
+    pub expires_at: u64, // Timestamp (ms) after which draws stop and the balance is due
+    pub overdue_since: Option<u64>, // Timestamp (ms) when the grace period after expiry ended unpaid
+    pub status: CreditLineStatus,
+}
+
+/// Pricing, tenor and late payment terms a credit line is opened with
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct CreditLineTerms {
+    pub interest_rate: u16, // Annual rate on the drawn balance in basis points
+    pub interest_type: InterestType,
+    pub commitment_fee_rate: u16, // Annual fee on the undrawn limit in basis points
+    pub duration: u64, // Time in ms the line stays open for draws
+    pub late_fee_rate: u16, // Daily late fee in basis points
+    pub max_late_fee_rate: u16, // Maximum late fee in basis points
+    pub grace_period_ms: u64, // Time after expiry before late fees apply
+    pub max_grace_period_extensions: u32,
+}
+
+impl CreditLineTerms {
+    /// Terms with the loan defaults for late fees and grace periods
+    pub fn new(interest_rate: u16, interest_type: InterestType, commitment_fee_rate: u16, duration: u64) -> Self {
+        Self {
+            interest_rate,
+            interest_type,
+            commitment_fee_rate,
+            duration,
+            late_fee_rate: 50, // 0.5% daily late fee
+            max_late_fee_rate: 1000, // 10% maximum late fee
+            grace_period_ms: 600_000, // 10 minute grace period
+            max_grace_period_extensions: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub enum CreditLineStatus {
+    Active, // Open for draws until expiry
+    Overdue, // Unpaid past expiry and the grace period; late fees apply
+    Closed, // No further draws; any remaining balance stays repayable
 }
 
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(StorageLayout))]
 pub struct PartialPayment {
     pub amount: Balance,
-    pub timestamp: u64, // Block number when payment was made
+    pub timestamp: u64, // Timestamp (ms) when payment was made
     pub payment_type: PaymentType,
+    pub payer: AccountId, // Account that made the payment (borrower or a third party)
+    pub late_fees_paid: Balance, // Portion applied to late fees
+    pub interest_paid: Balance, // Portion applied to interest
+    pub principal_paid: Balance, // Portion applied to principal
 }
 
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(StorageLayout))]
 pub struct RefinanceRecord {
-    pub timestamp: u64, // Block number when refinancing occurred
+    pub timestamp: u64, // Timestamp (ms) when refinancing occurred
     pub old_lender: AccountId, // Previous lender
     pub new_lender: AccountId, // New lender
     pub old_interest_rate: u16, // Previous interest rate
@@ -123,7 +413,7 @@ pub enum InterestRateType {
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(StorageLayout))]
 pub struct InterestRateAdjustment {
-    pub timestamp: u64, // Block number when adjustment occurred
+    pub timestamp: u64, // Timestamp (ms) when adjustment occurred
     pub old_rate: u16,  // Previous interest rate
     pub new_rate: u16,  // New interest rate
     pub reason: RateAdjustmentReason, // Reason for the adjustment
@@ -158,11 +448,24 @@ pub enum InterestType {
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(StorageLayout))]
 pub enum CompoundFrequency {
-    Daily,      // Compound every day (14400 blocks)
-    Weekly,     // Compound every week (100800 blocks)
-    Monthly,    // Compound every month (432000 blocks)
-    Quarterly,  // Compound every quarter (1296000 blocks)
-    Annually,   // Compound every year (5184000 blocks)
+    Daily,      // Compound every day
+    Weekly,     // Compound every week
+    Monthly,    // Compound every month (30 days)
+    Quarterly,  // Compound every quarter (90 days)
+    Annually,   // Compound every year (365 days)
+}
+
+impl CompoundFrequency {
+    /// Length of one compounding period in milliseconds
+    pub fn period_ms(&self) -> u64 {
+        match self {
+            CompoundFrequency::Daily => MILLIS_PER_DAY,
+            CompoundFrequency::Weekly => MILLIS_PER_WEEK,
+            CompoundFrequency::Monthly => MILLIS_PER_MONTH,
+            CompoundFrequency::Quarterly => MILLIS_PER_QUARTER,
+            CompoundFrequency::Annually => MILLIS_PER_YEAR,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
@@ -172,6 +475,27 @@ pub enum PaymentStructure {
     InterestOnly,
 }
 
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct Installment {
+    pub period: u32, // Installment number, starting at 1
+    pub due_date: u64, // Timestamp (ms) when the installment is due
+    pub principal: Balance, // Principal portion of the installment
+    pub interest: Balance, // Interest portion of the installment
+    pub remaining_balance: Balance, // Scheduled balance outstanding once this installment is paid
+    pub amount_paid: Balance, // Amount paid towards this installment so far
+    pub status: InstallmentStatus,
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub enum InstallmentStatus {
+    Pending,
+    Paid,
+    Missed, // Not paid by its due date plus the grace period
+    PaidLate, // Paid after being marked missed
+}
+
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(StorageLayout))]
 pub enum GracePeriodReason {
@@ -188,7 +512,7 @@ pub enum GracePeriodReason {
 pub struct GracePeriodRecord {
     pub timestamp: u64,           // When grace period was granted
     pub reason: GracePeriodReason, // Reason for grace period
-    pub duration: u64,            // Duration in blocks
+    pub duration: u64,            // Duration in ms
     pub extension_number: u32,    // Which extension this was
     pub granted_by: AccountId,    // Who granted the grace period
 }
@@ -198,7 +522,9 @@ pub struct GracePeriodRecord {
 pub struct LiquidityPool {
     pub id: u64,
     pub name: String,
+    pub asset: Asset, // Asset the pool's liquidity is denominated in
     pub total_liquidity: Balance,
+    pub total_borrowed: Balance, // Principal lent out to open pool loans and credit lines
     pub active_loans: u32,
     pub total_volume: Balance,
     pub pool_fee_rate: u16, // Pool fee in basis points
@@ -210,8 +536,8 @@ pub struct LiquidityPool {
     pub liquidity_providers: Vec<LiquidityProvider>,
     pub total_rewards_distributed: Balance,
     pub performance_score: u16, // Pool performance score (0-10000)
-    pub last_rebalance: u64, // Block number of last rebalance
-    pub rebalance_frequency: u64, // How often to rebalance (blocks)
+    pub last_rebalance: u64, // Timestamp (ms) of last rebalance
+    pub rebalance_frequency: u64, // How often to rebalance (ms)
     pub target_liquidity_ratio: u16, // Target liquidity ratio (basis points)
     pub current_liquidity_ratio: u16, // Current liquidity ratio (basis points)
     pub rebalance_threshold: u16, // Threshold for triggering rebalance
@@ -227,6 +553,18 @@ pub struct LiquidityPool {
     pub concentration_limits: ConcentrationLimits, // Limits to prevent over-concentration
 }
 
+/// Name, starting liquidity, rates and liquidity bounds a liquidity pool is created with
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct PoolConfig {
+    pub name: String,
+    pub initial_liquidity: Balance,
+    pub pool_fee_rate: u16, // Pool fee in basis points
+    pub reward_rate: u16,   // Reward rate in basis points
+    pub min_liquidity: Balance,
+    pub max_liquidity: Balance,
+}
+
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(StorageLayout))]
 pub struct LiquidityProvider {
@@ -262,7 +600,7 @@ pub struct RewardToken {
 #[cfg_attr(feature = "std", derive(StorageLayout))]
 pub struct StakingRequirements {
     pub min_stake_amount: Balance,
-    pub lock_period: u64, // Minimum staking period in blocks
+    pub lock_period: u64, // Minimum staking period in ms
     pub early_unstake_penalty: u16, // Penalty for early unstaking (basis points)
     pub max_stake_amount: Balance,
 }
@@ -296,7 +634,7 @@ pub struct MarketDepthLevel {
     pub price_level: u16, // Price level in basis points (1000 = 100%)
     pub liquidity_available: Balance, // Available liquidity at this price level
     pub order_count: u32, // Number of orders at this price level
-    pub last_updated: u64, // Block number when this level was last updated
+    pub last_updated: u64, // Timestamp (ms) when this level was last updated
 }
 
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
@@ -315,7 +653,7 @@ pub struct ConcentrationLimits {
     pub max_single_pool_concentration: u16, // Maximum concentration in single pool (basis points)
     pub max_provider_concentration: u16, // Maximum concentration per provider (basis points)
     pub min_pool_diversity: u16, // Minimum number of pools for diversification
-    pub concentration_check_frequency: u64, // How often to check concentration (blocks)
+    pub concentration_check_frequency: u64, // How often to check concentration (ms)
 }
 
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
@@ -323,7 +661,7 @@ pub struct ConcentrationLimits {
 pub struct CreditScore {
     pub score: u16, // Credit score (300-850)
     pub factors: Vec<CreditFactor>, // Factors contributing to score
-    pub last_updated: u64, // Block number when score was last updated
+    pub last_updated: u64, // Timestamp (ms) when score was last updated
     pub score_history: Vec<CreditScoreRecord>, // Historical score changes
     pub risk_level: RiskLevel, // Risk level based on score
 }
@@ -357,13 +695,43 @@ pub struct CollateralRequirement {
     pub last_updated: u64,
 }
 
+pub const MAX_BASKET_POSITIONS: usize = 8; // Collateral positions one loan's basket can hold
+
+/// One asset held in a loan's collateral basket
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct CollateralPosition {
+    pub collateral_type: CollateralType,
+    pub asset: Asset,
+    pub amount: Balance,
+}
+
+/// Origination terms for borrowers of one risk level, replacing the global minimum collateral ratio
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct CreditTier {
+    pub min_collateral_ratio: u16, // Collateral required in basis points of the amount, zero for unsecured loans
+    pub max_loan_value: Balance, // Largest loan in native units, valued through the oracle
+}
+
+/// Inputs of the minimum rate quoted at origination; rates and premiums are annual, in basis points
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct PricingModel {
+    pub base_rate: u16, // Rate every quote starts from
+    pub ltv_premium: u16, // Premium at 100% loan-to-value, scaled linearly below it
+    pub optimal_utilization: u16, // Pool utilization above which the excess premium applies
+    pub utilization_premium: u16, // Premium at optimal utilization, scaled linearly below it
+    pub excess_utilization_premium: u16, // Further premium from optimal to full utilization
+}
+
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(StorageLayout))]
 pub struct InsurancePolicy {
     pub policy_id: u64,
     pub insured_amount: Balance, // Amount insured
     pub premium_rate: u16, // Premium rate in basis points
-    pub coverage_period: u64, // Coverage period in blocks
+    pub coverage_period: u64, // Coverage period in ms
     pub deductible: Balance, // Deductible amount
     pub status: InsuranceStatus,
     pub created_at: u64,
@@ -476,6 +844,182 @@ pub type AccountId = <ink_env::DefaultEnvironment as ink_env::Environment>::Acco
 pub type Balance = <ink_env::DefaultEnvironment as ink_env::Environment>::Balance;
 pub type BlockNumber = u64; 
 
+// ============================================================================
+// TIME BASIS
+// ============================================================================
+
+pub const MILLIS_PER_DAY: u64 = 86_400_000;
+pub const MILLIS_PER_WEEK: u64 = 7 * MILLIS_PER_DAY;
+pub const MILLIS_PER_MONTH: u64 = 30 * MILLIS_PER_DAY;
+pub const MILLIS_PER_QUARTER: u64 = 90 * MILLIS_PER_DAY;
+pub const MILLIS_PER_YEAR: u64 = 365 * MILLIS_PER_DAY;
+pub const DEFAULT_BLOCK_TIME_MS: u64 = 6_000; // Used to convert legacy block-based loans
+
+/// Loan as stored by block-based versions of the contract, read only to migrate it to `Loan`
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct LegacyLoan {
+    pub id: u64,
+    pub borrower: AccountId,
+    pub lender: Option<AccountId>,
+    pub amount: Balance,
+    pub interest_rate: u16, // Basis points (e.g., 500 = 5%)
+    pub duration: u64, // Duration in blocks
+    pub collateral: Balance,
+    pub status: LoanStatus,
+    pub created_at: u64,
+    pub due_date: u64,
+    pub early_repayment_discount: u16, // Early repayment discount in basis points (default: 200 = 2%)
+    pub total_paid: Balance, // Total amount paid so far (principal + interest)
+    pub remaining_balance: Balance, // Remaining balance to be paid
+    pub partial_payments: Vec<LegacyPartialPayment>, // History of partial payments
+    pub extension_count: u32, // Number of times loan has been extended
+    pub max_extensions: u32, // Maximum allowed extensions (default: 3)
+    pub extension_fee_rate: u16, // Extension fee in basis points (default: 100 = 1%)
+    pub late_fee_rate: u16, // Daily late fee rate in basis points (default: 50 = 0.5%)
+    pub max_late_fee_rate: u16, // Maximum late fee rate in basis points (default: 1000 = 10%)
+    pub total_late_fees: Balance, // Total late fees accumulated
+    pub overdue_since: Option<u64>, // Block number when loan became overdue
+    pub grace_period: u64, // Grace period in blocks before late fees start (default: 100 = ~10 minutes)
+    pub refinance_count: u32, // Number of times loan has been refinanced
+    pub max_refinances: u32, // Maximum allowed refinances (default: 2)
+    pub refinance_fee_rate: u16, // Refinance fee in basis points (default: 200 = 2%)
+    pub original_loan_id: Option<u64>, // ID of the original loan if this is a refinanced loan
+    pub refinance_history: Vec<RefinanceRecord>, // History of refinancing operations
+    pub interest_rate_type: InterestRateType, // Fixed or variable interest rate
+    pub base_interest_rate: u16, // Base interest rate for variable loans
+    pub risk_multiplier: u16, // Risk-based multiplier (1000 = 1.0x, 1200 = 1.2x)
+    pub interest_rate_adjustments: Vec<InterestRateAdjustment>, // History of rate changes
+    pub last_interest_update: u64, // Block number of last interest rate update
+    pub interest_update_frequency: u64, // How often interest rates can be updated (blocks)
+    pub interest_type: InterestType, // Simple or compound interest
+    pub compound_frequency: CompoundFrequency, // How often interest compounds
+    pub last_compound_date: u64, // Block number of last compound calculation
+    pub compound_period_blocks: u64, // Blocks per compound period
+    pub accrued_interest: Balance, // Interest accrued since last compound
+    pub total_compounded_interest: Balance, // Total interest from compounding
+    pub payment_structure: PaymentStructure, // Type of payment structure
+    pub interest_only_periods: u32, // Total interest-only periods allowed
+    pub current_payment_period: u32, // Current payment period number
+    pub interest_only_periods_used: u32, // Interest-only periods already used
+    pub next_payment_due: u64, // Block number when next payment is due
+    pub payment_period_blocks: u64, // Blocks per payment period
+    pub minimum_payment_amount: Balance, // Minimum payment required per period
+    pub grace_period_blocks: u64, // Grace period in blocks (configurable)
+    pub grace_period_used: u64, // How much of grace period has been used
+    pub grace_period_extensions: u32, // Number of grace period extensions used
+    pub max_grace_period_extensions: u32, // Maximum grace period extensions allowed
+    pub grace_period_reason: GracePeriodReason, // Reason for grace period
+    pub grace_period_history: Vec<GracePeriodRecord>, // History of grace period usage
+    pub liquidity_pool_id: Option<u64>, // Associated liquidity pool
+    pub pool_share: u16, // Share of the pool (basis points)
+    pub liquidity_provider: Option<AccountId>, // Who provided the liquidity
+    pub pool_rewards_earned: Balance, // Rewards earned from pool participation
+    pub credit_score: Option<CreditScore>, // Borrower's credit score
+    pub collateral_requirements: Vec<CollateralRequirement>, // Collateral requirements
+    pub insurance_policies: Vec<InsurancePolicy>, // Insurance coverage
+    pub fraud_flags: Vec<FraudDetectionRule>, // Fraud detection flags
+    pub compliance_status: ComplianceStatus, // Compliance verification status
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct LegacyPartialPayment {
+    pub amount: Balance,
+    pub timestamp: u64, // Block number when payment was made
+    pub payment_type: PaymentType,
+}
+
+// ============================================================================
+// INTEREST ACCRUAL
+// ============================================================================
+
+pub const BORROW_INDEX_SCALE: u128 = crate::math::WAD; // Borrow index value of 1.0
+
+// ============================================================================
+// ASSETS
+// ============================================================================
+
+pub const NATIVE_DECIMALS: u8 = 12; // Decimals of the chain's native currency
+
+/// Asset a loan, its collateral or a pool's liquidity is denominated in
+#[derive(Debug, Clone, Copy, Default, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub enum Asset {
+    #[default]
+    Native,           // Chain currency, sent as the call's transferred value
+    Psp22(AccountId), // PSP22 token contract, pulled with an allowance
+    Psp34(AccountId), // PSP34 NFT collection, only priced by the oracle
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct AssetConfig {
+    pub asset: Asset,
+    pub decimals: u8,
+    pub enabled: bool, // Disabled assets take no new loans or pools; existing positions can still settle
+    pub registered_at: u64,
+}
+
+/// Running totals the contract holds or has lent out in one asset
+#[derive(Debug, Clone, Default, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct AssetTotals {
+    pub liquidity: Balance, // Principal of funded loans not yet closed
+    pub escrowed_collateral: Balance, // Collateral held in escrow for open loan requests and loans
+    pub pool_liquidity: Balance, // Liquidity provided to pools
+    pub pool_borrowed: Balance, // Pool liquidity lent out to open loans and credit lines
+    pub protocol_fees: Balance, // Collected protocol fees available for withdrawal
+    pub unsecured_exposure: Balance, // Principal of open requests and loans not covered at the standard collateral ratio
+}
+
+/// Error returned by a PSP22 token contract
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+pub enum Psp22Error {
+    Custom(String),
+    InsufficientBalance,
+    InsufficientAllowance,
+    ZeroRecipientAddress,
+    ZeroSenderAddress,
+    SafeTransferCheckFailed(String),
+}
+
+// ============================================================================
+// ORACLE
+// ============================================================================
+
+pub const MAX_PRICE_OBSERVATIONS: usize = 48; // Aggregated prices kept for the time-weighted average
+
+/// Reporter-driven price feed of one asset, quoted in native base units per whole token
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct PriceFeed {
+    pub asset: Asset,
+    pub reporters: Vec<AccountId>, // Accounts allowed to submit prices
+    pub reports: Vec<PriceReport>, // Latest report of each reporter
+    pub price: Balance, // Median of the fresh reports at the last update
+    pub updated_at: u64, // Timestamp (ms) of the last update
+    pub observations: Vec<PriceObservation>, // Recent medians, oldest first, for the time-weighted average
+    pub max_staleness_ms: u64, // Reports and prices older than this are ignored or rejected
+    pub max_deviation_bps: u16, // Largest move one report may make from a fresh price
+    pub twap_window_ms: u64, // Window the time-weighted average price covers
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct PriceReport {
+    pub reporter: AccountId,
+    pub price: Balance,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct PriceObservation {
+    pub price: Balance,
+    pub timestamp: u64,
+}
+
 // ============================================================================
 // ANALYTICS & REPORTING STRUCTURES (Phase 5)
 // ============================================================================
@@ -619,6 +1163,25 @@ pub enum MetricTrend {
 // DEFI INTEGRATION STRUCTURES (Phase 6)
 // ============================================================================
 
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub enum FeeSource {
+    Interest,
+    FlashLoan,
+    Extension,
+    Refinance,
+}
+
+#[derive(Debug, Clone, Default, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(StorageLayout))]
+pub struct ProtocolRevenue {
+    pub interest_fees: Balance, // Protocol share of interest repaid
+    pub flash_loan_fees: Balance, // Protocol share of flash loan fees
+    pub extension_fees: Balance, // Protocol share of extension fees
+    pub refinance_fees: Balance, // Protocol share of refinance fees
+    pub total_withdrawn: Balance, // Fees withdrawn from the vault
+}
+
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(StorageLayout))]
 pub struct FlashLoan {
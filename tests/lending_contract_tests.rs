@@ -2,9 +2,11 @@ use ink::env::{
     DefaultEnvironment,
     test,
 };
+use ink::scale::DecodeAll;
+use ink::primitives::AccountId;
 
 use lending_smart_contract::{
-    LendingContract, types::{LoanStatus, InterestRateType}
+    LendingContract, errors::LendingError, types::{LoanStatus, InterestRateType, CollateralType, OfferStatus, InstallmentStatus, CompoundFrequency, LoanAction, Asset, CollateralPosition, CreditLineStatus, CreditLineTerms, InterestType, PoolConfig, CreditTier, RiskLevel, ComplianceType, ComplianceStatus, PricingModel, LegacyLoan, LegacyPartialPayment, PaymentType, PaymentStructure, GracePeriodReason, MILLIS_PER_DAY, MILLIS_PER_YEAR, BORROW_INDEX_SCALE, NATIVE_DECIMALS}
 };
 
 // Test environment setup
@@ -18,6 +20,20 @@ fn setup() -> (LendingContract, test::DefaultAccounts<DefaultEnvironment>) {
     (contract, accounts)
 }
 
+// Creates a loan of 1000 at 5% with a term of 1000 against 1500 collateral, funded
+// by bob, with the contract holding enough balance to pay out
+fn funded_loan(contract: &mut LendingContract, accounts: &test::DefaultAccounts<DefaultEnvironment>) -> u64 {
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    loan_id
+}
+
 
 
 #[test]
@@ -34,6 +50,7 @@ fn test_create_loan() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     assert_eq!(loan_id, 1);
     
@@ -52,6 +69,7 @@ fn test_fund_loan() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     
     // Fund the loan
@@ -70,6 +88,7 @@ fn test_early_repayment() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     
     // Fund the loan
@@ -98,6 +117,7 @@ fn test_partial_repayment() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     
     // Fund the loan
@@ -108,7 +128,10 @@ fn test_partial_repayment() {
     // Test partial repayment logic without actual transfer
     let _loan = contract.get_loan(loan_id).unwrap();
     assert_eq!(_loan.status, LoanStatus::Active);
-    assert_eq!(_loan.remaining_balance, 1050); // 1000 + 50 interest
+    assert_eq!(_loan.remaining_balance, 1000); // Interest accrues over the term
+    
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    assert_eq!(contract.get_current_debt(loan_id).unwrap(), 1050); // 1000 + 50 interest
     
     // Note: Actual partial repayment requires fund transfer which fails in test environment
 }
@@ -118,6 +141,7 @@ fn test_loan_extension() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     
     // Fund the loan
@@ -144,11 +168,30 @@ fn test_loan_extension() {
     // We've verified the core logic works correctly
 }
 
+#[test]
+fn test_extension_fee_paid_up_front() {
+    let (mut contract, accounts) = setup();
+    let loan_id = funded_loan(&mut contract, &accounts);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    
+    // The 1% fee is collected with the call and not added to the debt
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(10);
+    contract.extend_loan(loan_id, 1000).unwrap();
+    
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.due_date, 2000);
+    assert_eq!(loan.remaining_balance, 1000);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_000_009);
+    assert_eq!(contract.get_protocol_fee_vault(), 1);
+}
+
 #[test]
 fn test_late_fees() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     
     // Fund the loan
@@ -169,6 +212,7 @@ fn test_loan_refinancing() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     
     // Fund the loan
@@ -177,9 +221,10 @@ fn test_loan_refinancing() {
     contract.fund_loan(loan_id).unwrap();
     
     // Test refinancing logic without actual transfer (which fails in test environment)
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
     let _loan = contract.get_loan(loan_id).unwrap();
-    let refinance_fee = (_loan.remaining_balance * _loan.refinance_fee_rate as u128) / 10000;
-    println!("Refinance fee calculation: {} * {} / 10000 = {}", _loan.remaining_balance, _loan.refinance_fee_rate, refinance_fee);
+    let current_debt = contract.get_current_debt(loan_id).unwrap();
+    let refinance_fee = (current_debt * _loan.refinance_fee_rate as u128) / 10000;
     
     // The refinance fee calculation is correct: 1050 * 2% = 21
     assert_eq!(refinance_fee, 21);
@@ -193,6 +238,7 @@ fn test_variable_interest_rates() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     
     // Fund the loan
@@ -220,6 +266,7 @@ fn test_risk_multiplier() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     
     // Fund the loan
@@ -255,11 +302,26 @@ fn test_error_handling() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_error_codes() {
+    // Codes are grouped by subsystem in the thousands digit
+    assert_eq!(LendingError::InvalidAmount.code(), 1003);
+    assert_eq!(LendingError::MaxRefinancesReached.code(), 2018);
+    assert_eq!(LendingError::PoolNotFound.code(), 3001);
+    assert_eq!(LendingError::AlreadyVoted.code(), 4008);
+    assert_eq!(LendingError::FlashLoanNotFound.code(), 5001);
+    assert_eq!(LendingError::BatchNotFound.code(), 6006);
+    
+    assert_eq!(LendingError::AlreadyVoted.description(), "Already voted on this proposal");
+    assert_eq!(String::from(LendingError::LoanNotFound), "Loan not found");
+}
+
 #[test]
 fn test_unauthorized_operations() {
     let (mut contract, accounts) = setup();
     
     // Create a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
     
     // Try to fund with wrong account
@@ -278,7 +340,9 @@ fn test_loan_queries() {
     let (mut contract, accounts) = setup();
     
     // Create multiple loans
+    test::set_value_transferred::<DefaultEnvironment>(1500);
     let loan1 = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(3000);
     let _loan2 = contract.create_loan(2000, 800, 1500, 3000).unwrap();
     
     // Test total loans
@@ -296,4 +360,1473 @@ fn test_loan_queries() {
     
     // Test total liquidity
     assert_eq!(contract.get_total_liquidity(), 1000);
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_collateral_escrow() {
+    let (mut contract, _) = setup();
+    
+    // Collateral must be transferred with the request
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    let result = contract.create_loan(1000, 500, 1000, 1500);
+    assert!(result.is_err());
+    
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    
+    assert_eq!(contract.get_escrowed_collateral(loan_id), 1500);
+    assert_eq!(contract.get_total_escrowed_collateral(), 1500);
+}
+
+#[test]
+fn test_collateral_released_on_repayment() {
+    let (mut contract, accounts) = setup();
+    
+    // Create and fund a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    
+    // Contract holds the escrowed collateral and the repayment
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.alice, 1_000_000);
+    
+    // Repay in full at maturity
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(1050);
+    contract.repay_loan(loan_id).unwrap();
+    
+    assert_eq!(contract.get_escrowed_collateral(loan_id), 0);
+    assert_eq!(contract.get_total_escrowed_collateral(), 0);
+    assert_eq!(
+        test::get_account_balance::<DefaultEnvironment>(accounts.alice).unwrap(),
+        1_001_500
+    );
+}
+
+#[test]
+fn test_liquidate_overdue_loan() {
+    let (mut contract, accounts) = setup();
+    
+    // Create and fund a loan
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    
+    // Healthy loan within its term cannot be liquidated
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    assert!(!contract.can_liquidate_loan(loan_id).unwrap());
+    assert!(contract.liquidate_loan(loan_id).is_err());
+    
+    // Move past due date + grace period
+    test::set_block_timestamp::<DefaultEnvironment>(1000 + 600_000 + 100);
+    assert!(contract.can_liquidate_loan(loan_id).unwrap());
+    
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.alice, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.django, 1_000_000);
+    contract.liquidate_loan(loan_id).unwrap();
+    
+    // Lender repaid 1050, liquidator gets 5% bonus (52), borrower gets the surplus (398)
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_001_050);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.django).unwrap(), 1_000_052);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.alice).unwrap(), 1_000_398);
+    
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Liquidated);
+    assert_eq!(loan.remaining_balance, 0);
+    assert_eq!(contract.get_escrowed_collateral(loan_id), 0);
+    assert!(contract.get_user_profile(accounts.alice).unwrap().active_loans.is_empty());
+    assert!(contract.get_user_profile(accounts.bob).unwrap().active_loans.is_empty());
+}
+
+#[test]
+fn test_declare_default() {
+    let (mut contract, accounts) = setup();
+    
+    // Create and fund a loan
+    let loan_id = funded_loan(&mut contract, &accounts);
+    
+    // Past grace period but default delay not yet elapsed
+    test::set_block_timestamp::<DefaultEnvironment>(1000 + 600_000 + 100);
+    assert_eq!(contract.declare_default(loan_id), Err(LendingError::DefaultDelayNotElapsed));
+    
+    // Only the lender or a keeper can declare a default
+    test::set_block_timestamp::<DefaultEnvironment>(1000 + 600_000 + 100 + MILLIS_PER_DAY);
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    assert_eq!(contract.declare_default(loan_id), Err(LendingError::Unauthorized));
+    
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    contract.set_keeper(accounts.django, true).unwrap();
+    
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    contract.declare_default(loan_id).unwrap();
+    
+    // Lender receives the escrowed collateral
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_001_500);
+    
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Defaulted);
+    assert_eq!(contract.get_escrowed_collateral(loan_id), 0);
+    assert_eq!(contract.get_total_defaults(), 1);
+    
+    let borrower_profile = contract.get_user_profile(accounts.alice).unwrap();
+    assert_eq!(borrower_profile.default_count, 1);
+    assert!(borrower_profile.active_loans.is_empty());
+    assert!(contract.get_user_profile(accounts.bob).unwrap().active_loans.is_empty());
+}
+
+#[test]
+fn test_cancel_and_expire_loan_request() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.alice, 1_000_000);
+    
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let cancelled_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    let expired_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    
+    // Only the borrower can cancel
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    assert_eq!(contract.cancel_loan(cancelled_id), Err(LendingError::Unauthorized));
+    
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    contract.cancel_loan(cancelled_id).unwrap();
+    assert_eq!(contract.get_loan(cancelled_id).unwrap().status, LoanStatus::Cancelled);
+    assert_eq!(contract.get_escrowed_collateral(cancelled_id), 0);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.alice).unwrap(), 1_001_500);
+    
+    // Requests without a deadline cannot expire
+    assert_eq!(contract.expire_loan(expired_id), Err(LendingError::FundingDeadlineNotReached));
+    contract.set_funding_deadline(expired_id, Some(50)).unwrap();
+    assert_eq!(contract.expire_loan(expired_id), Err(LendingError::FundingDeadlineNotReached));
+    
+    // Past the deadline the request can no longer be funded, only expired
+    test::set_block_timestamp::<DefaultEnvironment>(51);
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    assert_eq!(contract.fund_loan(expired_id), Err(LendingError::InvalidStatus));
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    
+    contract.expire_loan(expired_id).unwrap();
+    assert_eq!(contract.get_loan(expired_id).unwrap().status, LoanStatus::Expired);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.alice).unwrap(), 1_003_000);
+    assert!(contract.get_user_profile(accounts.alice).unwrap().active_loans.is_empty());
+}
+
+#[test]
+fn test_syndicated_loan_funding_and_repayment() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.django, 1_000_000);
+    
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    
+    // Two lenders fill the loan in tranches
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(600);
+    contract.contribute_to_loan(loan_id).unwrap();
+    assert_eq!(contract.get_loan(loan_id).unwrap().status, LoanStatus::Pending);
+    
+    // A single lender can no longer fund the whole loan
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    assert_eq!(contract.fund_loan(loan_id), Err(LendingError::InvalidStatus));
+    assert_eq!(contract.contribute_to_loan(loan_id), Err(LendingError::ContributionExceedsRequest));
+    
+    test::set_value_transferred::<DefaultEnvironment>(400);
+    contract.contribute_to_loan(loan_id).unwrap();
+    
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Active);
+    assert_eq!(loan.lender, Some(accounts.bob));
+    let contributions = contract.get_loan_contributions(loan_id).unwrap();
+    assert_eq!(contributions.len(), 2);
+    assert_eq!(contributions[0].share, 6000);
+    assert_eq!(contributions[1].share, 4000);
+    
+    // Repayment at maturity, net of the rounded-up protocol fee, is split 60/40
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(1050);
+    contract.repay_loan(loan_id).unwrap();
+    
+    assert_eq!(contract.get_protocol_fee_vault(), 1);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_000_629);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.django).unwrap(), 1_000_420);
+    assert!(contract.get_user_profile(accounts.bob).unwrap().active_loans.is_empty());
+    assert!(contract.get_user_profile(accounts.django).unwrap().active_loans.is_empty());
+}
+
+#[test]
+fn test_syndicated_loan_refund_after_deadline() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    contract.set_funding_deadline(loan_id, Some(50)).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(300);
+    contract.contribute_to_loan(loan_id).unwrap();
+    
+    // Loan never reaches full funding before the deadline
+    test::set_block_timestamp::<DefaultEnvironment>(51);
+    test::set_value_transferred::<DefaultEnvironment>(700);
+    assert_eq!(contract.contribute_to_loan(loan_id), Err(LendingError::InvalidStatus));
+    
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    contract.expire_loan(loan_id).unwrap();
+    
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_000_300);
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Expired);
+    assert_eq!(loan.funded_amount, 0);
+    assert!(loan.lender_contributions.is_empty());
+}
+
+#[test]
+fn test_lender_offer_matching() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    
+    // Bob posts two offers at different rates
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(5000);
+    let offer_id = contract.create_loan_offer(100, 2000, 500, 0, vec![CollateralType::Stablecoin]).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(2000);
+    let other_offer_id = contract.create_loan_offer(100, 2000, 800, 0, vec![CollateralType::Cryptocurrency]).unwrap();
+    assert_eq!(contract.get_offer_market_depth(), vec![(500, 5000, 1), (800, 2000, 1)]);
+    
+    // Offer terms are enforced
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    assert_eq!(contract.accept_offer(offer_id, 1000, 50, 1500, CollateralType::Stablecoin), Err(LendingError::InvalidDuration));
+    assert_eq!(contract.accept_offer(offer_id, 1000, 1000, 1500, CollateralType::NFT), Err(LendingError::CollateralTypeNotAccepted));
+    assert_eq!(contract.accept_offer(offer_id, 6000, 1000, 1500, CollateralType::Stablecoin), Err(LendingError::InsufficientOfferLiquidity));
+    
+    // Accepting creates an active loan funded by the offer at the offered rate
+    let loan_id = contract.accept_offer(offer_id, 1000, 1000, 1500, CollateralType::Stablecoin).unwrap();
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Active);
+    assert_eq!(loan.lender, Some(accounts.bob));
+    assert_eq!(loan.interest_rate, 500);
+    assert_eq!(contract.get_escrowed_collateral(loan_id), 1500);
+    
+    let offer = contract.get_loan_offer(offer_id).unwrap();
+    assert_eq!(offer.available_amount, 4000);
+    assert_eq!(offer.status, OfferStatus::Active);
+    assert_eq!(contract.get_offer_market_depth(), vec![(500, 4000, 1), (800, 2000, 1)]);
+    
+    // Cancelling refunds the remaining liquidity and clears its depth level
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    contract.cancel_loan_offer(other_offer_id).unwrap();
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_002_000);
+    assert_eq!(contract.get_offer_market_depth(), vec![(500, 4000, 1)]);
+    
+    // Native pool depth includes the offer-backed liquidity at its rate
+    let pool_id = contract.create_liquidity_pool("Main".to_string(), 100_000, 100, 100, 1000, 1_000_000).unwrap();
+    let (depth, _, _) = contract.get_market_depth_info(pool_id).unwrap();
+    assert_eq!(depth.first(), Some(&(500, 4000, 1)));
+    assert_eq!(depth.len(), 4);
+}
+
+#[test]
+fn test_amortization_schedule() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    
+    // Three-day loan with daily installments
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 500, 3 * MILLIS_PER_DAY, 1500).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    
+    let schedule = contract.get_amortization_schedule(loan_id).unwrap();
+    assert_eq!(schedule.len(), 3);
+    assert_eq!(schedule.iter().map(|i| i.due_date).collect::<Vec<_>>(), vec![MILLIS_PER_DAY, 2 * MILLIS_PER_DAY, 3 * MILLIS_PER_DAY]);
+    assert_eq!(schedule.iter().map(|i| i.principal).collect::<Vec<_>>(), vec![333, 333, 334]);
+    assert_eq!(schedule.iter().map(|i| i.interest).collect::<Vec<_>>(), vec![16, 16, 18]);
+    assert_eq!(schedule.iter().map(|i| i.remaining_balance).collect::<Vec<_>>(), vec![701, 352, 0]);
+    
+    // First installment is missed once its grace period passes
+    test::set_block_timestamp::<DefaultEnvironment>(MILLIS_PER_DAY + 600_001);
+    contract.apply_late_fees(loan_id).unwrap();
+    let schedule = contract.get_amortization_schedule(loan_id).unwrap();
+    assert_eq!(schedule[0].status, InstallmentStatus::Missed);
+    assert_eq!(schedule[1].status, InstallmentStatus::Pending);
+    
+    // Paying it late advances the payment period
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(349);
+    contract.partial_repay_loan(loan_id).unwrap();
+    
+    let (_, _, _, current_period, next_payment_due, minimum_payment) = contract.get_payment_structure_info(loan_id).unwrap();
+    assert_eq!(current_period, 1);
+    assert_eq!(next_payment_due, 2 * MILLIS_PER_DAY);
+    assert_eq!(minimum_payment, 349);
+    
+    // Overpayment carries into the following installment
+    test::set_value_transferred::<DefaultEnvironment>(500);
+    contract.partial_repay_loan(loan_id).unwrap();
+    
+    let schedule = contract.get_amortization_schedule(loan_id).unwrap();
+    assert_eq!(schedule[0].status, InstallmentStatus::PaidLate);
+    assert_eq!(schedule[1].status, InstallmentStatus::Paid);
+    assert_eq!(schedule[2].status, InstallmentStatus::Pending);
+    assert_eq!(schedule[2].amount_paid, 151);
+}
+
+#[test]
+fn test_protocol_fee_vault() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.eve, 1_000_000);
+    
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(100_000, 1000, 1000, 1500).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(100_000);
+    contract.fund_loan(loan_id).unwrap();
+    
+    // 0.5% of the 10,000 interest goes to the vault, the lender gets the rest
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(110_000);
+    contract.repay_loan(loan_id).unwrap();
+    
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_109_950);
+    assert_eq!(contract.get_protocol_fee_vault(), 50);
+    assert_eq!(contract.get_protocol_revenue().interest_fees, 50);
+    
+    // Only the owner or treasury can withdraw
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    assert_eq!(contract.withdraw_protocol_fees(accounts.bob, 50), Err(LendingError::Unauthorized));
+    
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    contract.set_treasury_account(Some(accounts.eve)).unwrap();
+    test::set_caller::<DefaultEnvironment>(accounts.eve);
+    assert_eq!(contract.withdraw_protocol_fees(accounts.eve, 51), Err(LendingError::InsufficientBalance));
+    contract.withdraw_protocol_fees(accounts.eve, 50).unwrap();
+    
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.eve).unwrap(), 1_000_050);
+    assert_eq!(contract.get_protocol_fee_vault(), 0);
+    assert_eq!(contract.get_protocol_revenue().total_withdrawn, 50);
+}
+
+#[test]
+fn test_asset_registry() {
+    let (mut contract, accounts) = setup();
+    let token = Asset::Psp22(accounts.django);
+    
+    // The native currency is registered from the start
+    let assets = contract.get_registered_assets();
+    assert_eq!(assets.len(), 1);
+    assert_eq!(assets[0].asset, Asset::Native);
+    assert_eq!(assets[0].decimals, NATIVE_DECIMALS);
+    
+    // Only the owner registers tokens, once each
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    assert_eq!(contract.register_asset(token, 6), Err(LendingError::Unauthorized));
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    contract.register_asset(token, 6).unwrap();
+    assert_eq!(contract.register_asset(token, 6), Err(LendingError::AssetAlreadyRegistered));
+    assert_eq!(contract.get_asset_config(token).unwrap().decimals, 6);
+    
+    // Unregistered and disabled assets take no new loans or pools
+    assert_eq!(
+        contract.create_asset_loan(Asset::Psp22(accounts.eve), Asset::Native, 1000, 500, 1000, 1500),
+        Err(LendingError::AssetNotRegistered)
+    );
+    contract.set_asset_enabled(token, false).unwrap();
+    assert_eq!(contract.create_asset_loan(token, Asset::Native, 1000, 500, 1000, 1500), Err(LendingError::AssetDisabled));
+    assert_eq!(
+        contract.create_asset_liquidity_pool(token, PoolConfig {
+            name: "Token Pool".to_string(),
+            initial_liquidity: 10_000,
+            pool_fee_rate: 100,
+            reward_rate: 100,
+            min_liquidity: 1000,
+            max_liquidity: 100_000,
+        }),
+        Err(LendingError::AssetDisabled)
+    );
+    
+    // Native loans are tracked in the native totals
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_asset_loan(Asset::Native, Asset::Native, 1000, 500, 1000, 1500).unwrap();
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    
+    let totals = contract.get_asset_totals(Asset::Native);
+    assert_eq!(totals.liquidity, 1000);
+    assert_eq!(totals.escrowed_collateral, 1500);
+    assert_eq!(contract.get_asset_totals(token).liquidity, 0);
+    assert_eq!(contract.get_loan(loan_id).unwrap().asset, Asset::Native);
+}
+
+// Stands in for an external price feed by submitting through several reporters
+struct MockPriceFeed {
+    asset: Asset,
+    reporters: Vec<AccountId>,
+}
+
+impl MockPriceFeed {
+    fn new(contract: &mut LendingContract, asset: Asset, reporters: Vec<AccountId>) -> Self {
+        contract.configure_price_feed(asset, 10 * 60 * 1000, 1000, 60 * 60 * 1000).unwrap();
+        for reporter in reporters.iter() {
+            contract.add_price_reporter(asset, *reporter).unwrap();
+        }
+        Self { asset, reporters }
+    }
+
+    // Each reporter submits its price in turn, then the owner is the caller again
+    fn report(&self, contract: &mut LendingContract, prices: &[u128]) -> Result<u128, LendingError> {
+        let mut aggregate = Err(LendingError::PriceFeedNotFound);
+        for (reporter, price) in self.reporters.iter().zip(prices) {
+            test::set_caller::<DefaultEnvironment>(*reporter);
+            aggregate = contract.submit_price(self.asset, *price);
+        }
+        test::set_caller::<DefaultEnvironment>(test::default_accounts::<DefaultEnvironment>().alice);
+        aggregate
+    }
+}
+
+#[test]
+fn test_price_oracle() {
+    let (mut contract, accounts) = setup();
+    let token = Asset::Psp22(accounts.django);
+    let native_unit = 10u128.pow(NATIVE_DECIMALS as u32);
+    contract.register_asset(token, 6).unwrap();
+    
+    // Only the owner configures feeds, and the native currency needs none
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    assert_eq!(contract.configure_price_feed(token, 1000, 1000, 1000), Err(LendingError::Unauthorized));
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    assert_eq!(contract.configure_price_feed(Asset::Native, 1000, 1000, 1000), Err(LendingError::InvalidPrice));
+    
+    let feed = MockPriceFeed::new(&mut contract, token, vec![accounts.bob, accounts.eve, accounts.frank]);
+    assert_eq!(contract.add_price_reporter(token, accounts.bob), Err(LendingError::ReporterAlreadyAuthorized));
+    assert_eq!(contract.get_price(token), Err(LendingError::PriceStale));
+    
+    // The aggregate is the median of the reports
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    let aggregate = feed.report(&mut contract, &[2 * native_unit, 21 * native_unit / 10, 19 * native_unit / 10]);
+    assert_eq!(aggregate, Ok(2 * native_unit));
+    assert_eq!(contract.get_price(token), Ok(2 * native_unit));
+    assert_eq!(contract.get_price_feed(token).unwrap().reports.len(), 3);
+    
+    // Unauthorized reporters and outliers are rejected
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    assert_eq!(contract.submit_price(token, 2 * native_unit), Err(LendingError::ReporterNotAuthorized));
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    assert_eq!(contract.submit_price(token, 3 * native_unit), Err(LendingError::PriceDeviationTooLarge));
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    
+    // One whole token is worth two native units, so the 150 bps ratio on one token needs 0.03 native units
+    test::set_value_transferred::<DefaultEnvironment>(29 * native_unit / 1000);
+    assert_eq!(
+        contract.create_asset_loan(token, Asset::Native, 1_000_000, 500, 1000, 29 * native_unit / 1000),
+        Err(LendingError::InsufficientCollateral)
+    );
+    test::set_value_transferred::<DefaultEnvironment>(3 * native_unit / 100);
+    let loan_id = contract.create_asset_loan(token, Asset::Native, 1_000_000, 500, 1000, 3 * native_unit / 100).unwrap();
+    assert_eq!(contract.get_loan(loan_id).unwrap().collateral_asset, Asset::Native);
+    
+    // Half an hour later the earlier reports are stale and a new price arrives
+    test::set_block_timestamp::<DefaultEnvironment>(1000 + 30 * 60 * 1000);
+    feed.report(&mut contract, &[22 * native_unit / 10, 22 * native_unit / 10, 22 * native_unit / 10]).unwrap();
+    assert_eq!(contract.get_price(token), Ok(22 * native_unit / 10));
+    assert_eq!(contract.get_twap(token), Ok(2 * native_unit));
+    
+    // Debt is valued at the higher of spot and TWAP
+    test::set_value_transferred::<DefaultEnvironment>(32 * native_unit / 1000);
+    assert_eq!(
+        contract.create_asset_loan(token, Asset::Native, 1_000_000, 500, 1000, 32 * native_unit / 1000),
+        Err(LendingError::InsufficientCollateral)
+    );
+    
+    // Without fresh reports the feed cannot be used for valuation
+    test::set_block_timestamp::<DefaultEnvironment>(1000 + 45 * 60 * 1000);
+    assert_eq!(contract.get_price(token), Err(LendingError::PriceStale));
+    test::set_value_transferred::<DefaultEnvironment>(4 * native_unit);
+    assert_eq!(
+        contract.create_asset_loan(token, Asset::Native, 1_000_000, 500, 1000, 4 * native_unit),
+        Err(LendingError::PriceStale)
+    );
+    
+    // NFTs are valued by their collection's feed, and collections without one are rejected
+    assert_eq!(
+        contract.add_nft_collateral(accounts.frank, 1, "uri".to_string(), "NFT".to_string(), "NFT".to_string(), 0, 1, 5000, 5000),
+        Err(LendingError::PriceFeedNotFound)
+    );
+    let collection = Asset::Psp34(accounts.frank);
+    let nft_feed = MockPriceFeed::new(&mut contract, collection, vec![accounts.bob]);
+    nft_feed.report(&mut contract, &[5 * native_unit]).unwrap();
+    let nft_id = contract.add_nft_collateral(
+        accounts.frank, 1, "uri".to_string(), "NFT".to_string(), "NFT".to_string(), 0, 1, 5000, 5000
+    ).unwrap();
+    assert_eq!(contract.get_nft_collateral(nft_id).unwrap().valuation, 5 * native_unit);
+}
+
+#[test]
+fn test_lender_position_transfer_and_sale() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.eve, 1_000_000);
+    
+    let loan_id = funded_loan(&mut contract, &accounts);
+    
+    // Bob hands his position to Django
+    contract.transfer_lender_position(loan_id, accounts.django).unwrap();
+    assert_eq!(contract.get_loan(loan_id).unwrap().lender, Some(accounts.django));
+    let bob_profile = contract.get_user_profile(accounts.bob).unwrap();
+    assert_eq!(bob_profile.total_lent, 0);
+    assert!(bob_profile.active_loans.is_empty());
+    let django_profile = contract.get_user_profile(accounts.django).unwrap();
+    assert_eq!(django_profile.total_lent, 1000);
+    assert_eq!(django_profile.active_loans, vec![loan_id]);
+    
+    // Only the current lender can list the position
+    assert_eq!(contract.list_loan_for_sale(loan_id, 900), Err(LendingError::Unauthorized));
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    contract.list_loan_for_sale(loan_id, 900).unwrap();
+    
+    // Eve buys it and receives the repayment
+    test::set_caller::<DefaultEnvironment>(accounts.eve);
+    test::set_value_transferred::<DefaultEnvironment>(800);
+    assert_eq!(contract.buy_loan(loan_id), Err(LendingError::InvalidAmount));
+    test::set_value_transferred::<DefaultEnvironment>(900);
+    contract.buy_loan(loan_id).unwrap();
+    
+    assert!(contract.get_loan_listing(loan_id).is_none());
+    assert_eq!(contract.get_loan(loan_id).unwrap().lender, Some(accounts.eve));
+    assert!(contract.get_user_profile(accounts.django).unwrap().active_loans.is_empty());
+    
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(1050);
+    contract.repay_loan(loan_id).unwrap();
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.eve).unwrap(), 1_001_049);
+    assert!(contract.get_user_profile(accounts.eve).unwrap().active_loans.is_empty());
+}
+
+#[test]
+fn test_third_party_refinance() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    
+    let loan_id = funded_loan(&mut contract, &accounts);
+    
+    test::set_account_balance::<DefaultEnvironment>(accounts.django, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.eve, 1_000_000);
+    
+    // Half way through the term the balance is 1025, and each proposal escrows a cap above it
+    test::set_block_timestamp::<DefaultEnvironment>(500);
+    test::set_caller::<DefaultEnvironment>(accounts.eve);
+    test::set_value_transferred::<DefaultEnvironment>(1026);
+    let stale_proposal_id = contract.propose_refinance(loan_id, 400, 2000, 1026).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    assert_eq!(contract.propose_refinance(loan_id, 300, 2000, 1000), Err(LendingError::InvalidAmount));
+    test::set_value_transferred::<DefaultEnvironment>(1050);
+    assert_eq!(contract.propose_refinance(loan_id, 300, 2000, 1040), Err(LendingError::InvalidAmount));
+    assert_eq!(contract.propose_refinance(loan_id, 500, 2000, 1050), Err(LendingError::TermsNotImproved));
+    let proposal_id = contract.propose_refinance(loan_id, 300, 2000, 1050).unwrap();
+    
+    // Accepted later in the term, once the balance has grown past Eve's cap but not Django's
+    test::set_block_timestamp::<DefaultEnvironment>(600);
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    assert_eq!(contract.accept_refinance(stale_proposal_id), Err(LendingError::RefinanceProposalStale));
+    let new_loan_id = contract.accept_refinance(proposal_id).unwrap();
+    
+    // Old lender is paid off in full, less the protocol fee on the interest, and Django gets the unused escrow back
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_001_029);
+    assert_eq!(contract.get_protocol_fee_vault(), 1);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.django).unwrap(), 1_000_020);
+    let old_loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(old_loan.status, LoanStatus::Refinanced);
+    assert_eq!(old_loan.remaining_balance, 0);
+    assert_eq!(contract.get_escrowed_collateral(loan_id), 0);
+    
+    // New loan belongs to the new lender and keeps the history
+    let new_loan = contract.get_loan(new_loan_id).unwrap();
+    assert_eq!(new_loan.status, LoanStatus::Active);
+    assert_eq!(new_loan.lender, Some(accounts.django));
+    assert_eq!(new_loan.amount, 1030);
+    assert_eq!(new_loan.remaining_balance, 1030);
+    assert_eq!(new_loan.original_loan_id, Some(loan_id));
+    assert_eq!(new_loan.refinance_count, 1);
+    assert_eq!(new_loan.refinance_history[0].old_lender, accounts.bob);
+    assert_eq!(new_loan.refinance_history[0].new_lender, accounts.django);
+    assert_eq!(contract.get_escrowed_collateral(new_loan_id), 1500);
+    
+    assert_eq!(contract.get_user_profile(accounts.alice).unwrap().active_loans, vec![new_loan_id]);
+    assert!(contract.get_user_profile(accounts.bob).unwrap().active_loans.is_empty());
+    assert_eq!(contract.get_user_profile(accounts.django).unwrap().active_loans, vec![new_loan_id]);
+    
+    // Eve's stale proposal can still be withdrawn for a full refund
+    test::set_caller::<DefaultEnvironment>(accounts.eve);
+    contract.withdraw_refinance_proposal(stale_proposal_id).unwrap();
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.eve).unwrap(), 1_001_026);
+    
+    // Interest on the new loan accrues at its own rate over its own term
+    test::set_block_timestamp::<DefaultEnvironment>(2600);
+    assert_eq!(contract.get_current_debt(new_loan_id).unwrap(), 1061);
+}
+
+#[test]
+fn test_repay_on_behalf() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.alice, 1_000_000);
+    
+    let loan_id = funded_loan(&mut contract, &accounts);
+    
+    // Django cannot use the borrower-only path but can pay on Alice's behalf
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(1050);
+    assert_eq!(contract.partial_repay_loan(loan_id), Err(LendingError::Unauthorized));
+    contract.repay_on_behalf(loan_id).unwrap();
+    
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Repaid);
+    assert_eq!(loan.partial_payments[0].payer, accounts.django);
+    
+    // Collateral still goes back to the borrower
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.alice).unwrap(), 1_001_500);
+    assert!(contract.get_user_profile(accounts.alice).unwrap().active_loans.is_empty());
+    
+    // Early repayment at half term with the accrued interest discounted
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    let early_loan_id = funded_loan(&mut contract, &accounts);
+    test::set_block_timestamp::<DefaultEnvironment>(1500);
+    let discount = contract.get_early_repayment_discount(early_loan_id).unwrap() as u128;
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(1025 - 25 * discount / 10000);
+    assert_eq!(contract.early_repay_loan(early_loan_id), Err(LendingError::Unauthorized));
+    contract.early_repay_on_behalf(early_loan_id).unwrap();
+    let loan = contract.get_loan(early_loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::EarlyRepaid);
+    assert_eq!(loan.partial_payments[0].payer, accounts.django);
+    
+    // Full repayment at maturity
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    let full_loan_id = funded_loan(&mut contract, &accounts);
+    test::set_block_timestamp::<DefaultEnvironment>(2500);
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(1050);
+    assert_eq!(contract.repay_loan(full_loan_id), Err(LendingError::Unauthorized));
+    contract.repay_loan_on_behalf(full_loan_id).unwrap();
+    let loan = contract.get_loan(full_loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Repaid);
+    assert_eq!(loan.partial_payments[0].payer, accounts.django);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.alice).unwrap(), 1_004_500);
+}
+
+#[test]
+fn test_interest_only_payment_on_behalf() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 10_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    
+    // A year-long loan at 10% that pays interest only for two 30-day periods
+    test::set_value_transferred::<DefaultEnvironment>(1_500_000);
+    let loan_id = contract.create_loan(1_000_000, 1000, MILLIS_PER_YEAR, 1_500_000).unwrap();
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1_000_000);
+    contract.fund_loan(loan_id).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    contract.set_interest_only_periods(loan_id, 2, 30 * MILLIS_PER_DAY).unwrap();
+    
+    // A call without the period's interest attached is rejected and uses up no period
+    test::set_block_timestamp::<DefaultEnvironment>(30 * MILLIS_PER_DAY);
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    assert_eq!(contract.make_interest_only_payment_on_behalf(loan_id), Err(LendingError::InvalidAmount));
+    assert_eq!(contract.get_payment_structure_info(loan_id).unwrap().2, 0);
+    
+    // Django pays the period's interest, which reaches the lender net of the protocol fee
+    test::set_value_transferred::<DefaultEnvironment>(8220);
+    contract.make_interest_only_payment_on_behalf(loan_id).unwrap();
+    assert_eq!(contract.get_payment_structure_info(loan_id).unwrap().2, 1);
+    assert_eq!(contract.get_protocol_fee_vault(), 42);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_008_178);
+    
+    // The payment clears the interest accrued over the period and leaves the principal outstanding
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::PartiallyPaid);
+    assert_eq!(loan.partial_payments[0].payer, accounts.django);
+    assert_eq!((loan.interest_repaid, loan.principal_repaid), (8220, 0));
+    assert_eq!(loan.remaining_balance, 1_000_000);
+}
+
+#[test]
+fn test_guarantor_called_on_default() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.eve, 1_000_000);
+    
+    test::set_value_transferred::<DefaultEnvironment>(300);
+    let loan_id = contract.create_loan(1000, 500, 1000, 300).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    
+    // Eve guarantees the loan
+    test::set_caller::<DefaultEnvironment>(accounts.eve);
+    test::set_value_transferred::<DefaultEnvironment>(600);
+    contract.add_guarantee(loan_id).unwrap();
+    assert_eq!(contract.release_guarantee(loan_id), Err(LendingError::LoanStillOpen));
+    
+    // On default the stake pays first, then the collateral is seized
+    test::set_block_timestamp::<DefaultEnvironment>(1000 + 600_000 + 100 + MILLIS_PER_DAY);
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    contract.declare_default(loan_id).unwrap();
+    
+    // Bob receives the stake less the protocol fee on the interest it covers, then the collateral
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_000_899);
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.remaining_balance, 150);
+    assert_eq!(loan.partial_payments[0].payer, accounts.eve);
+    
+    let guarantee = contract.get_loan_guarantee(loan_id).unwrap();
+    assert_eq!(guarantee.amount_called, 600);
+    assert_eq!(guarantee.recovery_claim, 600);
+    
+    // Borrower works off the guarantor's recovery claim
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(200);
+    contract.repay_guarantor(loan_id).unwrap();
+    assert_eq!(contract.get_loan_guarantee(loan_id).unwrap().recovery_claim, 400);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.eve).unwrap(), 1_000_200);
+}
+
+#[test]
+fn test_guarantor_called_on_liquidation() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.alice, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.django, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.eve, 1_000_000);
+    
+    let loan_id = funded_loan(&mut contract, &accounts);
+    test::set_caller::<DefaultEnvironment>(accounts.eve);
+    test::set_value_transferred::<DefaultEnvironment>(600);
+    contract.add_guarantee(loan_id).unwrap();
+    
+    // The stake pays 600 of the 1050 debt before the collateral covers the remaining 450
+    test::set_block_timestamp::<DefaultEnvironment>(1000 + 600_000 + 100);
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    contract.liquidate_loan(loan_id).unwrap();
+    
+    // Liquidator bonus is 5% of 450, and the surplus reimburses Eve before the borrower
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_001_049);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.django).unwrap(), 1_000_022);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.eve).unwrap(), 1_000_600);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.alice).unwrap(), 1_000_428);
+    
+    let guarantee = contract.get_loan_guarantee(loan_id).unwrap();
+    assert_eq!(guarantee.amount_called, 600);
+    assert_eq!(guarantee.recovery_claim, 0);
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Liquidated);
+    assert_eq!(loan.partial_payments[0].payer, accounts.eve);
+}
+
+#[test]
+fn test_payment_waterfall() {
+    let (mut contract, accounts) = setup();
+    
+    let loan_id = funded_loan(&mut contract, &accounts);
+    
+    // Overdue loan accrues late fees, which are paid before interest
+    test::set_block_timestamp::<DefaultEnvironment>(1000 + 600_000 + MILLIS_PER_DAY);
+    contract.apply_late_fees(loan_id).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(40);
+    contract.partial_repay_loan(loan_id).unwrap();
+    
+    // The day already charged by apply_late_fees is not charged again
+    let loan = contract.get_loan(loan_id).unwrap();
+    let late_fees = loan.total_late_fees;
+    assert_eq!(late_fees, 6);
+    let payment = &loan.partial_payments[0];
+    assert_eq!(payment.late_fees_paid, late_fees);
+    assert_eq!(payment.interest_paid, 40 - late_fees);
+    assert_eq!(payment.principal_paid, 0);
+    
+    // A day later only that day's fee is added on the reduced balance, then cleared with the interest before principal
+    test::set_block_timestamp::<DefaultEnvironment>(1000 + 600_000 + 2 * MILLIS_PER_DAY);
+    test::set_value_transferred::<DefaultEnvironment>(100);
+    contract.partial_repay_loan(loan_id).unwrap();
+    
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.total_late_fees, 12);
+    let payment = &loan.partial_payments[1];
+    assert_eq!(payment.late_fees_paid, loan.total_late_fees - late_fees);
+    assert_eq!(payment.interest_paid, 50 - (40 - late_fees));
+    assert_eq!(payment.principal_paid, 100 - payment.late_fees_paid - payment.interest_paid);
+    assert_eq!(loan.late_fees_repaid, loan.total_late_fees);
+    assert_eq!(loan.interest_repaid, 50);
+    
+    // Final repayment is all principal
+    test::set_value_transferred::<DefaultEnvironment>(loan.remaining_balance);
+    contract.repay_loan(loan_id).unwrap();
+    
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Repaid);
+    assert_eq!(loan.partial_payments[2].interest_paid, 0);
+    assert_eq!(loan.principal_repaid, 1000);
+    assert_eq!(loan.interest_repaid, 50);
+    assert_eq!(loan.total_paid, 1000 + 50 + loan.total_late_fees);
+}
+
+#[test]
+fn test_timestamp_loan_clock() {
+    let (mut contract, accounts) = setup();
+    
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 500, MILLIS_PER_DAY, 1500).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.due_date, MILLIS_PER_DAY);
+    assert_eq!(loan.next_payment_due, MILLIS_PER_DAY);
+    
+    // Block height alone does not move the loan clock
+    test::set_block_number::<DefaultEnvironment>(1_000_000);
+    assert!(!contract.is_loan_overdue(loan_id).unwrap());
+    
+    // Late fees accrue per elapsed day after the grace period
+    test::set_block_timestamp::<DefaultEnvironment>(MILLIS_PER_DAY + 600_000 + 2 * MILLIS_PER_DAY);
+    assert!(contract.is_loan_overdue(loan_id).unwrap());
+    assert_eq!(contract.calculate_current_late_fees(loan_id).unwrap(), 1050 * 100 / 10000);
+    
+    // Loans already on the timestamp clock cannot be migrated again
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    assert_eq!(contract.migrate_loan_to_timestamps(loan_id), Err(LendingError::AlreadyTimestampBased));
+    assert_eq!(contract.get_block_time(), 6_000);
+}
+
+#[test]
+fn test_migrate_legacy_block_loan() {
+    let (mut contract, accounts) = setup();
+    
+    // A loan written by the block-based contract: 100 blocks long, created at block 10, 300 of 1050 repaid at block 50
+    let legacy = LegacyLoan {
+        id: 1,
+        borrower: accounts.alice,
+        lender: Some(accounts.bob),
+        amount: 1000,
+        interest_rate: 500,
+        duration: 100,
+        collateral: 1500,
+        status: LoanStatus::PartiallyPaid,
+        created_at: 10,
+        due_date: 110,
+        early_repayment_discount: 200,
+        total_paid: 300,
+        remaining_balance: 750,
+        partial_payments: vec![LegacyPartialPayment { amount: 300, timestamp: 50, payment_type: PaymentType::Partial }],
+        extension_count: 0,
+        max_extensions: 3,
+        extension_fee_rate: 100,
+        late_fee_rate: 50,
+        max_late_fee_rate: 1000,
+        total_late_fees: 0,
+        overdue_since: None,
+        grace_period: 100,
+        refinance_count: 0,
+        max_refinances: 2,
+        refinance_fee_rate: 200,
+        original_loan_id: None,
+        refinance_history: Vec::new(),
+        interest_rate_type: InterestRateType::Fixed,
+        base_interest_rate: 500,
+        risk_multiplier: 1000,
+        interest_rate_adjustments: Vec::new(),
+        last_interest_update: 10,
+        interest_update_frequency: 14_400,
+        interest_type: InterestType::Simple,
+        compound_frequency: CompoundFrequency::Daily,
+        last_compound_date: 10,
+        compound_period_blocks: 14_400,
+        accrued_interest: 0,
+        total_compounded_interest: 0,
+        payment_structure: PaymentStructure::PrincipalAndInterest,
+        interest_only_periods: 0,
+        current_payment_period: 0,
+        interest_only_periods_used: 0,
+        next_payment_due: 110,
+        payment_period_blocks: 14_400,
+        minimum_payment_amount: 0,
+        grace_period_blocks: 100,
+        grace_period_used: 0,
+        grace_period_extensions: 0,
+        max_grace_period_extensions: 2,
+        grace_period_reason: GracePeriodReason::None,
+        grace_period_history: Vec::new(),
+        liquidity_pool_id: None,
+        pool_share: 0,
+        liquidity_provider: None,
+        pool_rewards_earned: 0,
+        credit_score: None,
+        collateral_requirements: Vec::new(),
+        insurance_policies: Vec::new(),
+        fraud_flags: Vec::new(),
+        compliance_status: ComplianceStatus::Pending,
+    };
+    let loans_key = ink::primitives::KeyComposer::compute_key("LendingContract", "", "loans").unwrap();
+    ink::env::set_contract_storage(&(loans_key, 1u64), &legacy);
+    
+    // Only admins can migrate
+    test::set_block_number::<DefaultEnvironment>(60);
+    test::set_block_timestamp::<DefaultEnvironment>(60 * 6_000);
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    assert_eq!(contract.migrate_loan_to_timestamps(1), Err(LendingError::Unauthorized));
+    
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    assert_eq!(contract.migrate_loan_to_timestamps(2), Err(LendingError::LoanNotFound));
+    assert_eq!(contract.migrate_loan_to_timestamps(1), Ok(()));
+    assert_eq!(contract.migrate_loan_to_timestamps(1), Err(LendingError::AlreadyTimestampBased));
+    
+    // Block numbers become timestamps relative to the current block, block counts become milliseconds
+    let loan = contract.get_loan(1).unwrap();
+    assert_eq!(loan.created_at, 60_000);
+    assert_eq!(loan.due_date, 660_000);
+    assert_eq!(loan.duration, 600_000);
+    assert_eq!(loan.grace_period_ms, 600_000);
+    assert_eq!(loan.payment_period_ms, MILLIS_PER_DAY);
+    assert_eq!(loan.partial_payments[0].timestamp, 300_000);
+    
+    // The payment already made covers the term's interest first
+    assert_eq!(loan.partial_payments[0].interest_paid, 50);
+    assert_eq!(loan.partial_payments[0].principal_paid, 250);
+    assert_eq!(loan.principal_repaid, 250);
+    assert_eq!(loan.remaining_balance, 750);
+    
+    // The legacy balance already includes the term's interest, so the loan repays at that balance
+    test::set_block_timestamp::<DefaultEnvironment>(660_000);
+    assert_eq!(contract.get_current_debt(1).unwrap(), 750);
+    
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 0);
+    test::set_value_transferred::<DefaultEnvironment>(750);
+    assert_eq!(contract.repay_loan(1), Ok(()));
+    assert_eq!(contract.get_loan(1).unwrap().status, LoanStatus::Repaid);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob), Ok(750));
+}
+
+#[test]
+fn test_borrow_index_accrual() {
+    let (mut contract, accounts) = setup();
+    
+    let loan_id = funded_loan(&mut contract, &accounts);
+    assert_eq!(contract.get_borrow_index(loan_id).unwrap(), BORROW_INDEX_SCALE);
+    
+    // Half way through the term half of the interest has accrued
+    test::set_block_timestamp::<DefaultEnvironment>(500);
+    assert_eq!(contract.calculate_accrued_interest(loan_id).unwrap(), 25);
+    assert_eq!(contract.get_current_debt(loan_id).unwrap(), 1025);
+    assert_eq!(contract.get_borrow_index(loan_id).unwrap(), BORROW_INDEX_SCALE * 1025 / 1000);
+    
+    // Anyone can book the accrued interest
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    assert_eq!(contract.accrue_interest(loan_id), Ok(25));
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.remaining_balance, 1025);
+    assert_eq!(loan.last_accrual, 500);
+    
+    // A rate change re-prices only the rest of the term
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    contract.convert_to_variable_rate(loan_id, 1000).unwrap();
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    assert_eq!(contract.get_current_debt(loan_id).unwrap(), 1075);
+    
+    // Interest stops at the due date, late fees take over from there
+    test::set_block_timestamp::<DefaultEnvironment>(2000);
+    assert_eq!(contract.get_current_debt(loan_id).unwrap(), 1075);
+}
+
+#[test]
+fn test_compound_accrual_per_period() {
+    let (mut contract, accounts) = setup();
+    
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 1000, 4 * MILLIS_PER_DAY, 1500).unwrap();
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    contract.convert_to_compound_interest(loan_id, CompoundFrequency::Daily).unwrap();
+    
+    // 10% over four days is 2.5% per daily period: 1000 × 1.025^4 = 1103.8, rounded up
+    test::set_block_timestamp::<DefaultEnvironment>(4 * MILLIS_PER_DAY);
+    assert_eq!(contract.get_current_debt(loan_id).unwrap(), 1104);
+}
+
+#[test]
+fn test_rounding_favours_protocol() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.bob, 1_000_000);
+    
+    let loan_id = funded_loan(&mut contract, &accounts);
+    
+    // Interest owed rounds up: 0.05 after 1 ms and 16.65 after 333 ms
+    test::set_block_timestamp::<DefaultEnvironment>(1);
+    assert_eq!(contract.get_current_debt(loan_id).unwrap(), 1001);
+    test::set_block_timestamp::<DefaultEnvironment>(333);
+    assert_eq!(contract.get_current_debt(loan_id).unwrap(), 1017);
+    
+    // The protocol fee on 17 of interest rounds up from 0.085, and the lender receives the rest
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(17);
+    contract.partial_repay_loan(loan_id).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    assert_eq!(contract.get_loan(loan_id).unwrap().partial_payments[0].interest_paid, 17);
+    assert_eq!(contract.get_protocol_fee_vault(), 1);
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), 1_000_016);
+    
+    // Pool shares round down: 2000 of 3000 is 66.67%
+    let pool_id = contract.create_liquidity_pool("Main".to_string(), 1000, 100, 100, 1, 1_000_000).unwrap();
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    contract.provide_liquidity(pool_id, 2000).unwrap();
+    assert_eq!(contract.get_liquidity_provider_info(pool_id, accounts.django).unwrap().1, 6666);
+    
+    // Rewards round down: 1% a year on 2000 is 0.05 after a day and 1.64 after 30 days
+    test::set_block_timestamp::<DefaultEnvironment>(333 + MILLIS_PER_DAY);
+    assert_eq!(contract.claim_pool_rewards(pool_id), Err(LendingError::NoRewardsToClaim));
+    test::set_block_timestamp::<DefaultEnvironment>(333 + 30 * MILLIS_PER_DAY);
+    assert_eq!(contract.claim_pool_rewards(pool_id), Ok(1));
+}
+
+#[test]
+fn test_checked_arithmetic() {
+    let (mut contract, accounts) = setup();
+    
+    // A borrower who has never lent scores as fully utilized instead of dividing by zero
+    let loan_id = funded_loan(&mut contract, &accounts);
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    test::set_value_transferred::<DefaultEnvironment>(1050);
+    contract.repay_loan(loan_id).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    assert!(contract.calculate_credit_score(accounts.alice).is_ok());
+    
+    // Funding an oversized loan reports the overflow instead of wrapping or panicking
+    let amount = u128::MAX / 3 * 2;
+    test::set_value_transferred::<DefaultEnvironment>(u128::MAX);
+    let loan_id = contract.create_loan(amount, 10000, 1000, u128::MAX).unwrap();
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(amount);
+    assert_eq!(contract.fund_loan(loan_id), Err(LendingError::ArithmeticOverflow));
+}
+
+#[test]
+fn test_loan_state_machine() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    assert_eq!(
+        contract.get_allowed_actions(loan_id).unwrap(),
+        vec![LoanAction::Fund, LoanAction::Cancel, LoanAction::Expire, LoanAction::SetFundingDeadline, LoanAction::AddGuarantee]
+    );
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    let actions = contract.get_allowed_actions(loan_id).unwrap();
+    assert!(actions.contains(&LoanAction::Repay) && actions.contains(&LoanAction::Refinance));
+    assert!(!actions.contains(&LoanAction::Fund));
+    
+    // A partially paid loan can still be refinanced or repaid early
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_block_timestamp::<DefaultEnvironment>(200);
+    test::set_value_transferred::<DefaultEnvironment>(300);
+    contract.partial_repay_loan(loan_id).unwrap();
+    assert_eq!(contract.get_loan(loan_id).unwrap().status, LoanStatus::PartiallyPaid);
+    let actions = contract.get_allowed_actions(loan_id).unwrap();
+    assert!(actions.contains(&LoanAction::EarlyRepay) && actions.contains(&LoanAction::Refinance));
+    
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    test::set_value_transferred::<DefaultEnvironment>(contract.get_current_debt(loan_id).unwrap());
+    contract.repay_loan(loan_id).unwrap();
+    assert_eq!(contract.get_loan(loan_id).unwrap().status, LoanStatus::Repaid);
+    assert_eq!(contract.get_allowed_actions(loan_id).unwrap(), vec![LoanAction::ReleaseGuarantee]);
+    
+    // Closed loans cannot move again
+    assert!(!LoanStatus::Repaid.can_transition_to(&LoanStatus::Active));
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    assert_eq!(contract.fund_loan(loan_id), Err(LendingError::LoanNotActive));
+    
+    // Every transition was announced with the action that caused it
+    let transitions: Vec<(LoanStatus, LoanStatus, LoanAction)> = test::recorded_events()
+        .filter_map(|event| <(u64, LoanStatus, LoanStatus, LoanAction)>::decode_all(&mut &event.data[..]).ok())
+        .map(|(_, from, to, reason)| (from, to, reason))
+        .collect();
+    assert_eq!(transitions, vec![
+        (LoanStatus::Pending, LoanStatus::Active, LoanAction::Fund),
+        (LoanStatus::Active, LoanStatus::PartiallyPaid, LoanAction::Repay),
+        (LoanStatus::PartiallyPaid, LoanStatus::Repaid, LoanAction::Repay),
+    ]);
+}
+
+#[test]
+fn test_loan_status_changed_events() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.alice, 1_000_000);
+    
+    let loan_id = funded_loan(&mut contract, &accounts);
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    test::set_value_transferred::<DefaultEnvironment>(1050);
+    contract.repay_loan(loan_id).unwrap();
+    
+    // Funding and repaying each emit one LoanStatusChanged { from, to, reason }
+    let status_changes: Vec<(u64, LoanStatus, LoanStatus, LoanAction)> = test::recorded_events()
+        .filter_map(|event| <(u64, LoanStatus, LoanStatus, LoanAction)>::decode_all(&mut &event.data[..]).ok())
+        .collect();
+    assert_eq!(status_changes, vec![
+        (loan_id, LoanStatus::Pending, LoanStatus::Active, LoanAction::Fund),
+        (loan_id, LoanStatus::Active, LoanStatus::Repaid, LoanAction::Repay),
+    ]);
+}
+
+#[test]
+fn test_collateral_management() {
+    let (mut contract, accounts) = setup();
+    
+    let loan_id = funded_loan(&mut contract, &accounts);
+    
+    // 1500 collateral at the 80% threshold against 1000 of debt
+    assert_eq!(contract.get_health_factor(loan_id), Ok(12000));
+    assert_eq!(contract.get_maintenance_margin(), 12000);
+    
+    // Only the borrower manages collateral, and never below the maintenance margin
+    assert_eq!(contract.withdraw_excess_collateral(loan_id, 100), Err(LendingError::Unauthorized));
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    assert_eq!(contract.withdraw_excess_collateral(loan_id, 1), Err(LendingError::InsufficientCollateral));
+    
+    test::set_value_transferred::<DefaultEnvironment>(500);
+    assert_eq!(contract.add_collateral(loan_id), Ok(16000));
+    assert_eq!(contract.get_escrowed_collateral(loan_id), 2000);
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    
+    // A per-loan requirement raises the margin and tracks the escrowed amount
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    contract.set_collateral_requirements(loan_id, CollateralType::Cryptocurrency, 1500, 0, 15000).unwrap();
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    assert_eq!(contract.get_collateral_requirements(loan_id).unwrap()[0].2, 2000);
+    
+    assert_eq!(contract.withdraw_excess_collateral(loan_id, 200), Err(LendingError::InsufficientCollateral));
+    assert_eq!(contract.withdraw_excess_collateral(loan_id, 100), Ok(15200));
+    assert_eq!(contract.get_collateral_requirements(loan_id).unwrap()[0].2, 1900);
+    assert_eq!(contract.get_total_escrowed_collateral(), 1900);
+    
+    // Accrued interest pushes health below the margin, which issues a single margin call
+    test::set_block_timestamp::<DefaultEnvironment>(1000);
+    assert_eq!(contract.check_margin_call(loan_id), Ok(true));
+    assert_eq!(contract.get_loan(loan_id).unwrap().margin_call_at, Some(1000));
+    test::set_block_timestamp::<DefaultEnvironment>(1100);
+    assert_eq!(contract.check_margin_call(loan_id), Ok(true));
+    assert_eq!(contract.get_loan(loan_id).unwrap().margin_call_at, Some(1000));
+    
+    // Topping up restores health and clears the call
+    test::set_value_transferred::<DefaultEnvironment>(500);
+    assert!(contract.add_collateral(loan_id).unwrap() >= 15000);
+    assert_eq!(contract.get_loan(loan_id).unwrap().margin_call_at, None);
+    assert_eq!(contract.get_collateral_requirements(loan_id).unwrap()[0].2, 2400);
+}
+
+#[test]
+fn test_collateral_basket() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    let position = |collateral_type: CollateralType, amount: u128| CollateralPosition {
+        collateral_type,
+        asset: Asset::Native,
+        amount,
+    };
+    
+    // Collateral factors are set by the admin for registered assets only
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    assert_eq!(contract.set_collateral_factor(CollateralType::Stablecoin, Asset::Native, 9000), Err(LendingError::Unauthorized));
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    assert_eq!(contract.set_collateral_factor(CollateralType::Stablecoin, Asset::Native, 10001), Err(LendingError::InvalidAmount));
+    assert_eq!(
+        contract.set_collateral_factor(CollateralType::Stablecoin, Asset::Psp22(accounts.django), 9000),
+        Err(LendingError::AssetNotRegistered)
+    );
+    contract.set_collateral_factor(CollateralType::Stablecoin, Asset::Native, 9000).unwrap();
+    contract.set_collateral_factor(CollateralType::Cryptocurrency, Asset::Native, 5000).unwrap();
+    assert_eq!(contract.get_collateral_factor(CollateralType::Cryptocurrency, Asset::Native), 5000);
+    
+    // Positions without a factor are not accepted
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    assert_eq!(
+        contract.create_basket_loan(Asset::Native, 1000, 500, 1000, vec![position(CollateralType::Commodities, 1000)]),
+        Err(LendingError::CollateralTypeNotAccepted)
+    );
+    
+    // 600 at 90% and 800 at 50% give 940 of borrowing power, short of 1000
+    test::set_value_transferred::<DefaultEnvironment>(1400);
+    assert_eq!(
+        contract.create_basket_loan(Asset::Native, 1000, 500, 1000, vec![
+            position(CollateralType::Stablecoin, 600),
+            position(CollateralType::Cryptocurrency, 800),
+        ]),
+        Err(LendingError::InsufficientCollateral)
+    );
+    
+    // Native positions are paid together with the call
+    let basket = vec![position(CollateralType::Stablecoin, 600), position(CollateralType::Cryptocurrency, 1000)];
+    assert_eq!(contract.create_basket_loan(Asset::Native, 1000, 500, 1000, basket.clone()), Err(LendingError::InvalidAmount));
+    test::set_value_transferred::<DefaultEnvironment>(1600);
+    let loan_id = contract.create_basket_loan(Asset::Native, 1000, 500, 1000, basket).unwrap();
+    assert_eq!(contract.get_collateral_basket(loan_id).len(), 2);
+    assert_eq!(contract.get_asset_totals(Asset::Native).escrowed_collateral, 1600);
+    
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(1000);
+    contract.fund_loan(loan_id).unwrap();
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    assert_eq!(contract.get_borrowing_power(loan_id), Ok(1040));
+    assert_eq!(contract.get_health_factor(loan_id), Ok(10400));
+    
+    // Topping up a position merges into it
+    test::set_value_transferred::<DefaultEnvironment>(400);
+    assert_eq!(contract.add_basket_collateral(loan_id, CollateralType::Stablecoin, Asset::Native, 400), Ok(14000));
+    assert_eq!(contract.get_collateral_basket(loan_id)[0].amount, 1000);
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    
+    // Withdrawals must keep health above the maintenance margin
+    assert_eq!(
+        contract.withdraw_basket_collateral(loan_id, CollateralType::Commodities, Asset::Native, 100),
+        Err(LendingError::CollateralPositionNotFound)
+    );
+    assert_eq!(contract.withdraw_basket_collateral(loan_id, CollateralType::Cryptocurrency, Asset::Native, 200), Ok(13000));
+    assert_eq!(
+        contract.withdraw_basket_collateral(loan_id, CollateralType::Cryptocurrency, Asset::Native, 300),
+        Err(LendingError::InsufficientCollateral)
+    );
+    
+    // A lower haircut-adjusted value makes the loan liquidatable, and liquidation empties the basket
+    contract.set_collateral_factor(CollateralType::Cryptocurrency, Asset::Native, 1000).unwrap();
+    assert_eq!(contract.get_health_factor(loan_id), Ok(9800));
+    assert_eq!(contract.can_liquidate_loan(loan_id), Ok(true));
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    contract.liquidate_loan(loan_id).unwrap();
+    
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!(loan.status, LoanStatus::Liquidated);
+    assert_eq!(loan.remaining_balance, 0);
+    assert!(contract.get_collateral_basket(loan_id).is_empty());
+    assert_eq!(contract.get_asset_totals(Asset::Native).escrowed_collateral, 0);
+}
+
+#[test]
+fn test_revolving_credit_line() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    
+    // Bob escrows a 10_000 limit for Django at 10% a year with a 1% commitment fee on the undrawn part
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(10_000);
+    let terms = CreditLineTerms::new(1000, InterestType::Simple, 100, 30 * MILLIS_PER_DAY);
+    assert_eq!(
+        contract.open_credit_line(accounts.django, Asset::Native, 10_000, CreditLineTerms { commitment_fee_rate: 1100, ..terms.clone() }),
+        Err(LendingError::InvalidInterestRate)
+    );
+    assert_eq!(
+        contract.open_credit_line(accounts.django, Asset::Native, 10_000, CreditLineTerms { grace_period_ms: 0, ..terms.clone() }),
+        Err(LendingError::InvalidGracePeriod)
+    );
+    let line_id = contract.open_credit_line(accounts.django, Asset::Native, 10_000, terms).unwrap();
+    assert_eq!(contract.get_total_credit_lines(), 1);
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    
+    // Only the borrower draws, and never past the limit
+    assert_eq!(contract.draw(line_id, 4000), Err(LendingError::Unauthorized));
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    assert_eq!(contract.draw(line_id, 10_001), Err(LendingError::CreditLimitExceeded));
+    contract.draw(line_id, 4000).unwrap();
+    
+    // Interest runs on the 4000 drawn and the commitment fee on the 6000 undrawn
+    test::set_block_timestamp::<DefaultEnvironment>(10 * MILLIS_PER_DAY);
+    assert_eq!(contract.get_credit_line_debt(line_id), Ok(4013));
+    
+    // Repaying clears charges first, and repaid principal can be drawn again
+    test::set_value_transferred::<DefaultEnvironment>(2013);
+    contract.repay_line(line_id).unwrap();
+    let line = contract.get_credit_line(line_id).unwrap();
+    assert_eq!((line.drawn_principal, line.balance), (2000, 2000));
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    assert_eq!(contract.draw(line_id, 8001), Err(LendingError::CreditLimitExceeded));
+    contract.draw(line_id, 8000).unwrap();
+    assert_eq!(contract.get_credit_line(line_id).unwrap().total_drawn, 12_000);
+    
+    // Draws stop at expiry, and an unpaid balance is charged late fees after the grace period
+    test::set_block_timestamp::<DefaultEnvironment>(30 * MILLIS_PER_DAY);
+    assert_eq!(contract.draw(line_id, 1), Err(LendingError::CreditLineExpired));
+    assert_eq!(contract.apply_line_late_fees(line_id), Err(LendingError::LoanNotOverdue));
+    test::set_block_timestamp::<DefaultEnvironment>(32 * MILLIS_PER_DAY);
+    contract.apply_line_late_fees(line_id).unwrap();
+    let line = contract.get_credit_line(line_id).unwrap();
+    assert_eq!(line.status, CreditLineStatus::Overdue);
+    assert!(line.total_late_fees > 0);
+    
+    // Closing hands the undrawn limit back to the lender and the balance stays repayable
+    let lender_balance = test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap();
+    contract.close_credit_line(line_id).unwrap();
+    assert_eq!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap(), lender_balance);
+    assert_eq!(contract.close_credit_line(line_id), Err(LendingError::CreditLineNotActive));
+    
+    test::set_value_transferred::<DefaultEnvironment>(line.balance);
+    contract.repay_line(line_id).unwrap();
+    assert_eq!(contract.get_credit_line_debt(line_id), Ok(0));
+    assert!(test::get_account_balance::<DefaultEnvironment>(accounts.bob).unwrap() > lender_balance + 10_000);
+}
+
+#[test]
+fn test_credit_tiers() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    let tier = |max_loan_value: u128| CreditTier { min_collateral_ratio: 0, max_loan_value };
+    
+    // Tiers are set by the admin, with larger loans for KYC-verified borrowers
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    assert_eq!(contract.set_credit_tier(RiskLevel::Excellent, false, Some(tier(5000))), Err(LendingError::Unauthorized));
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    assert_eq!(contract.set_credit_tier(RiskLevel::Excellent, false, Some(tier(0))), Err(LendingError::InvalidAmount));
+    contract.set_credit_tier(RiskLevel::Excellent, false, Some(tier(5000))).unwrap();
+    contract.set_credit_tier(RiskLevel::Excellent, true, Some(tier(20_000))).unwrap();
+    
+    // Unscored borrowers post the standard collateral
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    assert_eq!(contract.get_borrower_credit_tier(accounts.django), None);
+    assert_eq!(contract.create_loan(4000, 500, 1000, 0), Err(LendingError::InsufficientCollateral));
+    test::set_value_transferred::<DefaultEnvironment>(60);
+    contract.create_loan(4000, 500, 1000, 60).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    
+    // An excellent score lends without collateral up to the tier's size, within the exposure cap
+    contract.calculate_credit_score(accounts.django).unwrap();
+    assert_eq!(contract.get_borrower_credit_tier(accounts.django), Some(tier(5000)));
+    assert_eq!(contract.create_loan(6000, 500, 1000, 0), Err(LendingError::CreditTierLimitExceeded));
+    assert_eq!(contract.create_loan(4000, 500, 1000, 0), Err(LendingError::UnsecuredExposureCapExceeded));
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    contract.set_unsecured_exposure_cap(Asset::Native, 5000).unwrap();
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    let loan_id = contract.create_loan(4000, 500, 1000, 0).unwrap();
+    assert_eq!(contract.get_unsecured_principal(loan_id), 4000);
+    assert_eq!(contract.create_loan(2000, 500, 1000, 0), Err(LendingError::UnsecuredExposureCapExceeded));
+    
+    // Verified KYC moves the borrower to the KYC tier
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+    contract.update_compliance_status(accounts.django, ComplianceType::KYC, ComplianceStatus::Verified, Vec::new()).unwrap();
+    assert_eq!(contract.get_borrower_credit_tier(accounts.django), Some(tier(20_000)));
+    
+    // The unsecured part of the debt is not held against collateral
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    test::set_value_transferred::<DefaultEnvironment>(4000);
+    contract.fund_loan(loan_id).unwrap();
+    assert_eq!(contract.get_health_factor(loan_id), Ok(u128::MAX));
+    assert_eq!(contract.can_liquidate_loan(loan_id), Ok(false));
+    
+    // Repaying frees the exposure for new loans
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    test::set_value_transferred::<DefaultEnvironment>(contract.get_loan(loan_id).unwrap().remaining_balance);
+    contract.repay_loan(loan_id).unwrap();
+    assert_eq!(contract.get_asset_totals(Asset::Native).unsecured_exposure, 0);
+    assert_eq!(contract.get_unsecured_principal(loan_id), 0);
+}
+
+#[test]
+fn test_risk_based_pricing() {
+    let (mut contract, accounts) = setup();
+    test::set_account_balance::<DefaultEnvironment>(accounts.charlie, 1_000_000);
+    let pool_id = contract.create_liquidity_pool("Main".to_string(), 100_000, 100, 100, 1000, 1_000_000).unwrap();
+    
+    // The pricing model and risk multipliers are governed by the admin
+    let model = contract.get_pricing_model();
+    assert_eq!(contract.set_pricing_model(PricingModel { optimal_utilization: 0, ..model.clone() }), Err(LendingError::InvalidAmount));
+    assert_eq!(contract.set_risk_level_multiplier(RiskLevel::Excellent, 400), Err(LendingError::InvalidRiskMultiplier));
+    test::set_caller::<DefaultEnvironment>(accounts.bob);
+    assert_eq!(contract.set_pricing_model(model), Err(LendingError::Unauthorized));
+    
+    // 5% base, 2.5% for 50% loan-to-value and 0.5% for 10% utilization, over a one year term
+    test::set_caller::<DefaultEnvironment>(accounts.django);
+    assert_eq!(contract.quote_pool_loan(pool_id, 10_000, MILLIS_PER_YEAR, 20_000), Ok(800));
+    assert_eq!(contract.quote_pool_loan(pool_id, 10_000, MILLIS_PER_YEAR / 2, 20_000), Ok(400));
+    
+    // Pool loans priced below the quote are rejected, and the pool funds the rest at once
+    test::set_value_transferred::<DefaultEnvironment>(20_000);
+    assert_eq!(
+        contract.create_pool_loan(pool_id, 10_000, 799, MILLIS_PER_YEAR, 20_000),
+        Err(LendingError::InterestRateBelowQuote)
+    );
+    let loan_id = contract.create_pool_loan(pool_id, 10_000, 800, MILLIS_PER_YEAR, 20_000).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(0);
+    let loan = contract.get_loan(loan_id).unwrap();
+    assert_eq!((loan.status, loan.liquidity_pool_id, loan.risk_multiplier), (LoanStatus::Active, Some(pool_id), 1000));
+    let totals = contract.get_asset_totals(Asset::Native);
+    assert_eq!((totals.pool_liquidity, totals.pool_borrowed), (90_000, 10_000));
+    
+    // An excellent score is quoted at 0.8x, and utilization now includes the first loan
+    contract.calculate_credit_score(accounts.django).unwrap();
+    assert_eq!(contract.quote_pool_loan(pool_id, 10_000, MILLIS_PER_YEAR, 20_000), Ok(680));
+    assert_eq!(contract.quote_loan(10_000, MILLIS_PER_YEAR, 20_000), Ok(680));
+    assert_eq!(contract.quote_loan(10_000, 100 * MILLIS_PER_YEAR, 20_000), Err(LendingError::ArithmeticOverflow));
+    
+    // Loans are originated with the borrower's risk multiplier, keeping the requested rate as the effective rate
+    test::set_value_transferred::<DefaultEnvironment>(1500);
+    let other_loan_id = contract.create_loan(1000, 500, 1000, 1500).unwrap();
+    let other_loan = contract.get_loan(other_loan_id).unwrap();
+    assert_eq!((other_loan.risk_multiplier, other_loan.base_interest_rate, other_loan.interest_rate), (800, 625, 500));
+    
+    // Repayments go back to the pool
+    test::set_block_timestamp::<DefaultEnvironment>(MILLIS_PER_YEAR);
+    let repayment = contract.get_current_debt(loan_id).unwrap();
+    test::set_value_transferred::<DefaultEnvironment>(repayment);
+    contract.repay_loan(loan_id).unwrap();
+    let totals = contract.get_asset_totals(Asset::Native);
+    assert_eq!((totals.pool_liquidity, totals.pool_borrowed), (100_796, 0));
+    assert_eq!(contract.get_liquidity_pool_info(pool_id).unwrap().2, 0);
+}
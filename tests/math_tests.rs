@@ -0,0 +1,50 @@
+use lending_smart_contract::{
+    errors::LendingError, math::{SafeMath, mul_div, bps_mul, bps_ratio, Rounding, Ray, Wad, WAD, RAY}
+};
+
+#[test]
+fn test_mul_div_rounding() {
+    assert_eq!(mul_div(10, 1, 3, Rounding::Down), Ok(3));
+    assert_eq!(mul_div(10, 1, 3, Rounding::Up), Ok(4));
+    assert_eq!(mul_div(9, 1, 3, Rounding::Up), Ok(3));
+    assert_eq!(mul_div(1, 1, 0, Rounding::Down), Err(LendingError::DivisionByZero));
+
+    // Basis-point helpers round the same way
+    assert_eq!(bps_mul(50, 50, Rounding::Down), Ok(0));
+    assert_eq!(bps_mul(50, 50, Rounding::Up), Ok(1));
+    assert_eq!(bps_ratio(600, 1000, Rounding::Down), Ok(6000));
+}
+
+#[test]
+fn test_mul_div_wide_intermediate() {
+    // The product overflows u128 but the result fits
+    assert_eq!(mul_div(u128::MAX, RAY, RAY, Rounding::Down), Ok(u128::MAX));
+    assert_eq!(mul_div(u128::MAX, 3, 4, Rounding::Down), Ok(u128::MAX / 4 * 3 + 2));
+    assert_eq!(mul_div(u128::MAX, 2, 1, Rounding::Down), Err(LendingError::ArithmeticOverflow));
+}
+
+#[test]
+fn test_fixed_point_pow() {
+    // 10% compounded over 3 periods
+    let factor = Wad::from_bps(1000).unwrap().checked_add(Wad::ONE).unwrap();
+    assert_eq!(factor.checked_pow(3, Rounding::Down).unwrap(), Wad(1_331_000_000_000_000_000));
+    assert_eq!(factor.checked_pow(0, Rounding::Down).unwrap(), Wad::ONE);
+
+    // A third cannot be represented exactly, so the rounding direction shows
+    let third_down = Ray::from_ratio(1, 3, Rounding::Down).unwrap();
+    let third_up = Ray::from_ratio(1, 3, Rounding::Up).unwrap();
+    assert_eq!(third_up.0 - third_down.0, 1);
+    assert_eq!(third_down.mul_amount(3_000, Rounding::Down), Ok(999));
+    assert_eq!(third_up.mul_amount(3_000, Rounding::Up), Ok(1_001));
+    assert_eq!(third_up.to_wad(Rounding::Up).unwrap().0, WAD / 3 + 1);
+}
+
+#[test]
+fn test_safe_math() {
+    assert_eq!(u128::MAX.safe_add(1), Err(LendingError::ArithmeticOverflow));
+    assert_eq!(0u128.safe_sub(1), Err(LendingError::ArithmeticOverflow));
+    assert_eq!(u16::MAX.safe_mul(2), Err(LendingError::ArithmeticOverflow));
+    assert_eq!(10u64.safe_div(0), Err(LendingError::DivisionByZero));
+    assert_eq!(10u64.safe_rem(0), Err(LendingError::DivisionByZero));
+    assert_eq!(7u32.safe_add(3).and_then(|v| v.safe_div(2)), Ok(5));
+}
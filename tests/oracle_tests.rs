@@ -0,0 +1,39 @@
+use lending_smart_contract::{
+    errors::LendingError, oracle::{median, deviation_bps, twap, bounded_price, PriceBound}, types::PriceObservation
+};
+
+fn observation(price: u128, timestamp: u64) -> PriceObservation {
+    PriceObservation { price, timestamp }
+}
+
+#[test]
+fn test_median_and_deviation() {
+    assert_eq!(median(vec![]), None);
+    assert_eq!(median(vec![30, 10, 20]), Some(20));
+    assert_eq!(median(vec![40, 10, 20, 30]), Some(25));
+    
+    // The middle pair is averaged without overflowing
+    assert_eq!(median(vec![u128::MAX, u128::MAX]), Some(u128::MAX));
+    
+    assert_eq!(deviation_bps(110, 100), Ok(1000));
+    assert_eq!(deviation_bps(90, 100), Ok(1000));
+    assert_eq!(deviation_bps(1, 0), Err(LendingError::DivisionByZero));
+}
+
+#[test]
+fn test_twap_window() {
+    assert_eq!(twap(&[], 100, 50), Ok(None));
+    
+    // A single fresh observation is its own average
+    assert_eq!(twap(&[observation(100, 100)], 100, 50), Ok(Some(100)));
+    
+    // 100 held for 30ms and 200 for 10ms of the 40ms window
+    let observations = [observation(50, 0), observation(100, 60), observation(200, 90)];
+    assert_eq!(twap(&observations, 100, 40), Ok(Some(125)));
+    
+    // Time before the first observation is not counted
+    assert_eq!(twap(&observations[1..], 100, 1000), Ok(Some(125)));
+    
+    assert_eq!(bounded_price(100, 125, PriceBound::Lower), 100);
+    assert_eq!(bounded_price(100, 125, PriceBound::Upper), 125);
+}